@@ -1,11 +1,13 @@
 use amethyst::{
     assets::Loader,
-    audio::{AudioSink, OggFormat, SourceHandle},
+    audio::{AudioSink, OggFormat, SourceHandle, WavFormat},
     ecs::{World, WorldExt},
 };
 
 use amethyst::core::ecs::Read;
 
+use crate::resources::save_data::{AudioSettings, SaveDataResource};
+
 use amethyst::{
     assets::AssetStorage,
     audio::{output::Output, Source},
@@ -23,7 +25,11 @@ pub struct MusicResource {
 
 /// Sound effects resource.
 pub struct SoundsResource {
-    pub sounds: HashMap<String, SourceHandle>,
+    /// Each sound's handle and base volume (from `SOUNDS`).
+    pub sounds: HashMap<String, (SourceHandle, f32)>,
+    /// What the base volumes are multiplied by (the master and SFX volumes, or 0 while muted).
+    /// Kept up to date by `apply_audio_settings`.
+    pub volume: f32,
 }
 
 /// Keys for the `sounds` HashMap.
@@ -39,6 +45,85 @@ pub mod sound_keys {
     pub const HEAL_SOUND: &str = "audio/heal.ogg";
     pub const COVID_SQUISH: &str = "audio/covid_squish.ogg";
     pub const COVID_DIE: &str = "audio/covid_die.ogg";
+    pub const LOCUST_SQUISH_SOUND: &str = "audio/locust_squish.wav";
+    pub const CROP_EATEN_SOUND: &str = "audio/crop_eaten.wav";
+    pub const PESTICIDE_SOUND: &str = "audio/pesticide.wav";
+    pub const CASH_REGISTER_SOUND: &str = "audio/cash_register.wav";
+    pub const RESTOCK_SOUND: &str = "audio/restock.wav";
+    pub const TURNED_AWAY_SOUND: &str = "audio/turned_away.wav";
+    pub const HAND_RAISED_SOUND: &str = "audio/hand_raised.wav";
+    pub const UNMUTED_NOISE_SOUND: &str = "audio/unmuted_noise.wav";
+    pub const VIDEO_FROZEN_SOUND: &str = "audio/video_frozen.wav";
+    pub const INCIDENT_RESOLVED_SOUND: &str = "audio/incident_resolved.wav";
+    pub const WRONG_CLICK_SOUND: &str = "audio/wrong_click.wav";
+    pub const BOSS_APPEAR_SOUND: &str = "audio/boss_appear.wav";
+    pub const BOSS_HIT_SOUND: &str = "audio/boss_hit.wav";
+    pub const SHIELD_DEFLECT_SOUND: &str = "audio/shield_deflect.wav";
+    pub const BOSS_DEFEATED_SOUND: &str = "audio/boss_defeated.wav";
+}
+
+/// Every sound effect and the volume it plays at before the player's volume settings.
+/// Lower a sound's base volume here if it's too loud next to the others.
+pub const SOUNDS: &[(&str, f32)] = &[
+    (sound_keys::BEE_TAP_SOUND, 1.0),
+    (sound_keys::BUG_SPRAY_SOUND, 1.0),
+    (sound_keys::FLY_SWAT_SOUND, 1.0),
+    (sound_keys::HIVE_TRAP_SOUND, 1.0),
+    (sound_keys::BUCKET_SOUND, 1.0),
+    (sound_keys::FIRE_SOUND, 1.0),
+    (sound_keys::FIRE_OUT_SOUND, 1.0),
+    (sound_keys::COUGH_SOUND, 1.0),
+    (sound_keys::HEAL_SOUND, 1.0),
+    (sound_keys::COVID_SQUISH, 1.0),
+    (sound_keys::COVID_DIE, 1.0),
+    (sound_keys::LOCUST_SQUISH_SOUND, 1.0),
+    (sound_keys::CROP_EATEN_SOUND, 1.0),
+    (sound_keys::PESTICIDE_SOUND, 1.0),
+    (sound_keys::CASH_REGISTER_SOUND, 1.0),
+    (sound_keys::RESTOCK_SOUND, 1.0),
+    (sound_keys::TURNED_AWAY_SOUND, 1.0),
+    (sound_keys::HAND_RAISED_SOUND, 1.0),
+    (sound_keys::UNMUTED_NOISE_SOUND, 1.0),
+    (sound_keys::VIDEO_FROZEN_SOUND, 1.0),
+    (sound_keys::INCIDENT_RESOLVED_SOUND, 1.0),
+    (sound_keys::WRONG_CLICK_SOUND, 1.0),
+    (sound_keys::BOSS_APPEAR_SOUND, 1.0),
+    (sound_keys::BOSS_HIT_SOUND, 1.0),
+    (sound_keys::SHIELD_DEFLECT_SOUND, 1.0),
+    (sound_keys::BOSS_DEFEATED_SOUND, 1.0),
+];
+
+/// The music is a lot louder than the sound effects, so it plays at this volume before the player's volume settings.
+pub const MUSIC_BASE_VOLUME: f32 = 0.025;
+
+/// How many steps the volume settings have between silent and full volume.
+pub const VOLUME_STEPS: u32 = 10;
+
+impl AudioSettings {
+    /// The volume of the background music.
+    pub fn music_level(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            MUSIC_BASE_VOLUME * self.master_volume * self.music_volume
+        }
+    }
+
+    /// What sound effects' base volumes are multiplied by.
+    pub fn sfx_level(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master_volume * self.sfx_volume
+        }
+    }
+}
+
+/// The next louder volume step, going back to silent after full volume.
+pub fn next_volume(volume: f32) -> f32 {
+    let step = (volume * VOLUME_STEPS as f32).round() as u32;
+
+    ((step + 1) % (VOLUME_STEPS + 1)) as f32 / VOLUME_STEPS as f32
 }
 
 pub const MUSIC_TRACKS: &[&str] = &[
@@ -46,9 +131,13 @@ pub const MUSIC_TRACKS: &[&str] = &[
     "audio/background_music_2.ogg",
 ];
 
-/// Loads an ogg audio track.
+/// Loads an audio track. Tracks are ogg unless the file ends with ".wav".
 fn load_audio_track(loader: &Loader, world: &World, file: &str) -> SourceHandle {
-    loader.load(file, OggFormat, (), &world.read_resource())
+    if file.ends_with(".wav") {
+        loader.load(file, WavFormat, (), &world.read_resource())
+    } else {
+        loader.load(file, OggFormat, (), &world.read_resource())
+    }
 }
 
 /// Initialise audio in the world.
@@ -56,67 +145,15 @@ pub fn initialise_audio(world: &mut World) {
     let (sound_effects, music) = {
         let loader = world.read_resource::<Loader>();
 
-        let mut sounds = HashMap::new();
-
-        sounds.insert(
-            sound_keys::BEE_TAP_SOUND.to_string(),
-            load_audio_track(&loader, &world, sound_keys::BEE_TAP_SOUND),
-        );
-
-        sounds.insert(
-            sound_keys::BUG_SPRAY_SOUND.to_string(),
-            load_audio_track(&loader, &world, sound_keys::BUG_SPRAY_SOUND),
-        );
-
-        sounds.insert(
-            sound_keys::FLY_SWAT_SOUND.to_string(),
-            load_audio_track(&loader, &world, sound_keys::FLY_SWAT_SOUND),
-        );
-
-        sounds.insert(
-            sound_keys::HIVE_TRAP_SOUND.to_string(),
-            load_audio_track(&loader, &world, sound_keys::HIVE_TRAP_SOUND),
-        );
-
-        sounds.insert(
-            sound_keys::BUCKET_SOUND.to_string(),
-            load_audio_track(&loader, &world, sound_keys::BUCKET_SOUND),
-        );
-
-        sounds.insert(
-            sound_keys::FIRE_SOUND.to_string(),
-            load_audio_track(&loader, &world, sound_keys::FIRE_SOUND),
-        );
-
-        sounds.insert(
-            sound_keys::FIRE_OUT_SOUND.to_string(),
-            load_audio_track(&loader, &world, sound_keys::FIRE_OUT_SOUND),
-        );
-
-        sounds.insert(
-            sound_keys::COUGH_SOUND.to_string(),
-            load_audio_track(&loader, &world, sound_keys::COUGH_SOUND),
-        );
-
-        sounds.insert(
-            sound_keys::HEAL_SOUND.to_string(),
-            load_audio_track(&loader, &world, sound_keys::HEAL_SOUND),
-        );
-
-        sounds.insert(
-            sound_keys::COVID_SQUISH.to_string(),
-            load_audio_track(&loader, &world, sound_keys::COVID_SQUISH),
-        );
-
-        sounds.insert(
-            sound_keys::COVID_DIE.to_string(),
-            load_audio_track(&loader, &world, sound_keys::COVID_DIE),
-        );
-
-        let mut sink = world.write_resource::<AudioSink>();
-        // Music is a bit loud, reduce the volume.
-        // This only affects background music.
-        sink.set_volume(0.025);
+        let sounds = SOUNDS
+            .iter()
+            .map(|(key, base_volume)| {
+                (
+                    key.to_string(),
+                    (load_audio_track(&loader, &world, key), *base_volume),
+                )
+            })
+            .collect::<HashMap<_, _>>();
 
         let music = MUSIC_TRACKS
             .iter()
@@ -125,11 +162,38 @@ pub fn initialise_audio(world: &mut World) {
             .into_iter()
             .cycle();
 
-        (SoundsResource { sounds }, MusicResource { music })
+        (
+            SoundsResource {
+                sounds,
+                volume: 1.0,
+            },
+            MusicResource { music },
+        )
     };
 
     world.insert(sound_effects);
     world.insert(music);
+
+    apply_audio_settings(world);
+}
+
+/// Sets the music and sound effect volumes from the saved audio settings.
+/// Called when the audio is initialised and whenever the settings change.
+pub fn apply_audio_settings(world: &mut World) {
+    let settings = world
+        .read_resource::<SaveDataResource>()
+        .data
+        .settings
+        .audio
+        .clone();
+
+    world
+        .write_resource::<AudioSink>()
+        .set_volume(settings.music_level());
+
+    if let Some(mut sounds) = world.try_fetch_mut::<SoundsResource>() {
+        sounds.volume = settings.sfx_level();
+    }
 }
 
 /// Play a sound based on its key. (Meant for systems to use, as this func takes Read<T>)
@@ -150,8 +214,10 @@ pub fn play_score_sound(
     output: Option<&Output>,
 ) {
     if let Some(ref output) = output.as_ref() {
-        if let Some(sound) = storage.get(&sounds.sounds.get(key).unwrap()) {
-            output.play_once(sound, 1.0);
+        let (handle, base_volume) = sounds.sounds.get(key).unwrap();
+
+        if let Some(sound) = storage.get(handle) {
+            output.play_once(sound, base_volume * sounds.volume);
         }
     }
 }
@@ -0,0 +1,300 @@
+use crate::resources::data_dir::write_atomically;
+use amethyst::config::Config;
+use amethyst::input::{Axis, Bindings, Button, InputHandler, StringBindings};
+use ron::ser::PrettyConfig;
+
+use std::path::Path;
+
+use self::action_names::{ABILITY_HOTKEYS, MUTE_ALL, SHOOT, USE_ABILITY};
+use self::axis_names::{MOVE_X, MOVE_Y, ROTATE};
+
+/// File (in the data directory, next to the save data) the player's remapped bindings are saved to.
+pub const USER_BINDINGS_FILE: &str = "bindings.ron";
+
+/// Names of the axes in `assets/input.ron`.
+pub mod axis_names {
+    /// Moves the player left (negative) and right (positive).
+    pub const MOVE_X: &str = "move_x";
+    /// Moves the player down (negative) and up (positive).
+    pub const MOVE_Y: &str = "move_y";
+    /// Rotates the player counterclockwise (negative) and clockwise (positive).
+    pub const ROTATE: &str = "rotate";
+}
+
+/// Names of the actions in `assets/input.ron`.
+pub mod action_names {
+    /// Shoots droplets in Wildfires and Locust Swarms.
+    pub const SHOOT: &str = "shoot";
+    /// Uses an ability that was placed with the mouse (like the bucket or the fly swatter).
+    pub const USE_ABILITY: &str = "use_ability";
+
+    /// Triggers the ability in the slot at the same index of the ability bar.
+    pub const ABILITY_HOTKEYS: &[&str] = &["ability_1", "ability_2", "ability_3"];
+
+    /// Mutes and unmutes the music and sound effects, in menus and levels.
+    pub const MUTE_ALL: &str = "mute_all";
+}
+
+/// Set by states that read raw key presses (like typing a name or remapping a control),
+/// so hotkeys that work everywhere (like mute) don't go off while the player types.
+#[derive(Default)]
+pub struct TextEntryActiveResource {
+    pub active: bool,
+}
+
+/// Gets the value of an axis. Returns 0 if the axis isn't bound.
+pub fn axis_value(input: &InputHandler<StringBindings>, axis: &str) -> f32 {
+    input.axis_value(axis).unwrap_or(0.0)
+}
+
+/// Returns true if any of an action's bindings are down. Returns false if the action isn't bound.
+pub fn action_is_down(input: &InputHandler<StringBindings>, action: &str) -> bool {
+    input.action_is_down(action).unwrap_or(false)
+}
+
+/// Collects the buttons an axis is bound to.
+fn axis_buttons(axis: &Axis, buttons: &mut Vec<Button>) {
+    match axis {
+        Axis::Emulated { pos, neg } => {
+            buttons.push(*pos);
+            buttons.push(*neg);
+        }
+        Axis::Multiple(axes) => {
+            for axis in axes {
+                axis_buttons(axis, buttons);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Lists every button used by the bindings, without duplicates.
+/// Axes and actions are visited in order of their names so the list is the same for the same bindings.
+pub fn bound_buttons(bindings: &Bindings<StringBindings>) -> Vec<Button> {
+    let mut buttons = Vec::new();
+
+    let mut axes: Vec<&String> = bindings.axes().collect();
+    axes.sort();
+
+    for axis in axes {
+        if let Some(axis) = bindings.axis(axis) {
+            axis_buttons(axis, &mut buttons);
+        }
+    }
+
+    let mut actions: Vec<&String> = bindings.actions().collect();
+    actions.sort();
+
+    for action in actions {
+        for binding in bindings.action_bindings(action) {
+            buttons.extend_from_slice(binding);
+        }
+    }
+
+    let mut unique_buttons = Vec::new();
+    for button in buttons {
+        if !unique_buttons.contains(&button) {
+            unique_buttons.push(button);
+        }
+    }
+
+    unique_buttons
+}
+
+/// Something the player can remap: one direction of an axis, or an action.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+    AxisPositive(&'static str),
+    AxisNegative(&'static str),
+    Action(&'static str),
+}
+
+/// Every control shown in the controls menu, with its display name.
+pub const CONTROLS: &[(&str, Control)] = &[
+    ("Move up", Control::AxisPositive(MOVE_Y)),
+    ("Move down", Control::AxisNegative(MOVE_Y)),
+    ("Move left", Control::AxisNegative(MOVE_X)),
+    ("Move right", Control::AxisPositive(MOVE_X)),
+    ("Rotate left", Control::AxisNegative(ROTATE)),
+    ("Rotate right", Control::AxisPositive(ROTATE)),
+    ("Shoot", Control::Action(SHOOT)),
+    ("Use ability", Control::Action(USE_ABILITY)),
+    ("Ability 1", Control::Action(ABILITY_HOTKEYS[0])),
+    ("Ability 2", Control::Action(ABILITY_HOTKEYS[1])),
+    ("Ability 3", Control::Action(ABILITY_HOTKEYS[2])),
+    ("Mute all", Control::Action(MUTE_ALL)),
+];
+
+/// The bindings from `assets/input.ron`, used when the player resets their controls.
+pub struct DefaultBindingsResource {
+    pub bindings: Bindings<StringBindings>,
+}
+
+/// Gets the buttons a control is bound to.
+pub fn control_buttons(bindings: &Bindings<StringBindings>, control: Control) -> Vec<Button> {
+    match control {
+        Control::AxisPositive(axis) | Control::AxisNegative(axis) => match bindings.axis(axis) {
+            Some(Axis::Emulated { pos, neg }) => {
+                if let Control::AxisPositive(_) = control {
+                    vec![*pos]
+                } else {
+                    vec![*neg]
+                }
+            }
+            _ => Vec::new(),
+        },
+        Control::Action(action) => bindings
+            .action_bindings(action)
+            .flat_map(|binding| binding.iter().cloned())
+            .collect(),
+    }
+}
+
+/// A short name for a button (like "W", "1" or "Left click").
+pub fn button_name(button: &Button) -> String {
+    match button {
+        Button::Key(key) => {
+            let name = format!("{:?}", key);
+
+            // Number keys are called Key1, Key2...
+            if name.starts_with("Key") && name.len() > 3 {
+                name[3..].to_string()
+            } else {
+                name
+            }
+        }
+        Button::Mouse(mouse_button) => format!("{:?} click", mouse_button),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Binds a control to a single button, replacing what it was bound to before.
+/// Fails without changing anything if another control already uses the button.
+pub fn rebind_control(
+    bindings: &mut Bindings<StringBindings>,
+    control: Control,
+    button: Button,
+) -> Result<(), String> {
+    for (name, other) in CONTROLS.iter() {
+        if *other != control && control_buttons(bindings, *other).contains(&button) {
+            return Err(format!(
+                "{} is already used by {}",
+                button_name(&button),
+                name
+            ));
+        }
+    }
+
+    match control {
+        Control::AxisPositive(axis) | Control::AxisNegative(axis) => {
+            let (pos, neg) = match bindings.axis(axis) {
+                Some(Axis::Emulated { pos, neg }) => (*pos, *neg),
+                _ => return Err(format!("{} can't be remapped", axis)),
+            };
+
+            let new_axis = if let Control::AxisPositive(_) = control {
+                Axis::Emulated { pos: button, neg }
+            } else {
+                Axis::Emulated { pos, neg: button }
+            };
+
+            // Both directions of an axis are checked against each other, so take the axis out first.
+            bindings.remove_axis(axis);
+            if let Err(error) = bindings.insert_axis(axis.to_string(), new_axis) {
+                bindings
+                    .insert_axis(axis.to_string(), Axis::Emulated { pos, neg })
+                    .expect("Couldn't restore the axis binding!");
+
+                return Err(format!("{:?}", error));
+            }
+        }
+        Control::Action(action) => {
+            let old_bindings: Vec<Vec<Button>> = bindings
+                .action_bindings(action)
+                .map(|binding| binding.to_vec())
+                .collect();
+
+            for binding in old_bindings.iter() {
+                bindings.remove_action_binding(action, binding);
+            }
+
+            if let Err(error) = bindings.insert_action_binding(action.to_string(), vec![button]) {
+                for binding in old_bindings {
+                    bindings
+                        .insert_action_binding(action.to_string(), binding)
+                        .expect("Couldn't restore the action binding!");
+                }
+
+                return Err(format!("{:?}", error));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the default bindings from `assets/input.ron`.
+pub fn load_default_bindings(path: &Path) -> Result<Bindings<StringBindings>, amethyst::Error> {
+    Ok(Bindings::<StringBindings>::load(path)?)
+}
+
+/// Combines the default bindings with the player's saved bindings.
+/// Axes and actions in the saved file override the defaults; ones missing from it (like newly added actions) keep their default.
+pub fn load_user_bindings(
+    defaults: &Bindings<StringBindings>,
+    data_dir: &Path,
+) -> Bindings<StringBindings> {
+    let user_bindings = match std::fs::read_to_string(data_dir.join(USER_BINDINGS_FILE)) {
+        Ok(contents) => contents,
+        Err(_) => return defaults.clone(),
+    };
+
+    let mut bindings: Bindings<StringBindings> = match ron::de::from_str(&user_bindings) {
+        Ok(bindings) => bindings,
+        Err(error) => {
+            log::error!("Couldn't read {}: {}", USER_BINDINGS_FILE, error);
+            return defaults.clone();
+        }
+    };
+
+    for axis in defaults.axes() {
+        if bindings.axis(axis).is_none() {
+            if let Err(error) =
+                bindings.insert_axis(axis.clone(), defaults.axis(axis).unwrap().clone())
+            {
+                log::warn!("Couldn't add the default binding for {}: {:?}", axis, error);
+            }
+        }
+    }
+
+    for action in defaults.actions() {
+        if bindings.action_bindings(action).next().is_none() {
+            for binding in defaults.action_bindings(action) {
+                if let Err(error) = bindings.insert_action_binding(action.clone(), binding.to_vec())
+                {
+                    log::warn!(
+                        "Couldn't add the default binding for {}: {:?}",
+                        action,
+                        error
+                    );
+                }
+            }
+        }
+    }
+
+    bindings
+}
+
+/// Saves the player's bindings so they get loaded the next time the game starts.
+pub fn save_user_bindings(bindings: &Bindings<StringBindings>, data_dir: &Path) {
+    let result = ron::ser::to_string_pretty(bindings, PrettyConfig::default())
+        .map_err(|error| error.to_string())
+        .and_then(|contents| {
+            write_atomically(&data_dir.join(USER_BINDINGS_FILE), contents.as_bytes())
+                .map_err(|error| error.to_string())
+        });
+
+    if let Err(error) = result {
+        log::error!("Couldn't save {}: {}", USER_BINDINGS_FILE, error);
+    }
+}
@@ -1,11 +1,14 @@
 pub mod audio;
+pub mod input;
+pub mod playfield;
 pub mod resources;
 pub mod states;
 pub mod systems;
+pub mod timers;
 
-use amethyst::core::{Time, Transform};
+use amethyst::core::Transform;
 use amethyst::renderer::palette::Srgba;
-use amethyst::ui::{FontHandle, TtfFormat, UiImage};
+use amethyst::ui::{Anchor, FontHandle, TtfFormat, UiButton, UiButtonBuilder, UiImage};
 use amethyst::{
     assets::{AssetStorage, Loader},
     ecs::prelude::Join,
@@ -94,6 +97,36 @@ pub fn delete_all_entities_with_component<T: Component>(world: &mut World) {
     }
 }
 
+/// Deletes a UiButton's image and text entities.
+pub fn delete_ui_button(world: &mut World, button: &UiButton) {
+    world
+        .entities()
+        .delete(button.image_entity)
+        .expect("Cannot delete UiButton's image entity.");
+    world
+        .entities()
+        .delete(button.text_entity)
+        .expect("Cannot delete UiButton's text entity.");
+}
+
+/// Creates a button in the horizontal center of the screen. The y position is relative to the middle of the screen.
+pub fn create_centered_button(world: &mut World, text: &str, y: f32, layer: f32) -> UiButton {
+    let font = get_main_font(world);
+
+    let (_, button) = UiButtonBuilder::<(), u32>::new(text)
+        .with_font(font)
+        .with_font_size(21.5)
+        .with_position(0.0, y)
+        .with_size(240.0, 64.0)
+        .with_anchor(Anchor::Middle)
+        .with_layer(layer)
+        .with_image(create_ui_color_from_rgba(195, 130, 51, 1.0))
+        .with_hover_image(create_ui_color_from_rgba(195, 130, 51, 0.5))
+        .build_from_world(&world);
+
+    button
+}
+
 /// Resource storing fonts.
 pub struct FontsResource {
     pub main_font: FontHandle,
@@ -117,11 +150,6 @@ pub fn create_ui_color_from_rgba(r: u32, g: u32, b: u32, a: f32) -> UiImage {
     UiImage::SolidColor([r, g, b, a])
 }
 
-/// Returns true if the `time.frame_number()` is a multiple of 60 * n
-pub fn every_n_seconds(n: f64, time: &Time) -> bool {
-    (time.frame_number() as f64 % (60. * n)) == 0.0
-}
-
 /// Keeps a number in min max bounds.
 pub fn bound(num: f32, min: f32, max: f32) -> f32 {
     if num < min {
@@ -1,18 +1,46 @@
+use amethyst::assets::Processor;
 use amethyst::audio::AudioBundle;
 use amethyst::audio::DjSystemDesc;
-use amethyst::input::{InputBundle, StringBindings};
+use amethyst::input::{InputHandler, StringBindings};
 use amethyst::renderer::RenderFlat2D;
 use amethyst::{
+    config::Config,
     core::transform::TransformBundle,
     prelude::*,
     renderer::{plugins::RenderToWindow, types::DefaultBackend, RenderingBundle},
     ui::{RenderUi, UiBundle},
     utils::application_root_dir,
+    window::DisplayConfig,
 };
 
 use survive2020::audio::MusicResource;
+use survive2020::input::{
+    load_default_bindings, load_user_bindings, DefaultBindingsResource, TextEntryActiveResource,
+};
+use survive2020::resources::data_dir::{resolve_data_dir, DataDirResource};
+use survive2020::resources::difficulty::DifficultyResource;
+use survive2020::resources::display::AppliedDisplaySettingsResource;
+use survive2020::resources::levels::LevelDefinition;
+use survive2020::resources::replay::{load_replay, PendingReplayResource, ReplayPlaybackResource};
+use survive2020::resources::rng::SeedResource;
+use survive2020::resources::save_data::load_save_data;
+use survive2020::resources::spawn_timeline::SpawnTimeline;
+use survive2020::states::campaign::CampaignResource;
 use survive2020::states::main_menu::MainMenuState;
 use survive2020::systems::ability_bar::AbilityBarSystemDesc;
+use survive2020::systems::live_input::LiveInputSystemDesc;
+use survive2020::systems::mute::MuteSystem;
+use survive2020::systems::playfield::PlayfieldLayoutSystem;
+
+use std::path::Path;
+
+/// Gets the value following a command line flag (e.g. `--seed 42`).
+fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != flag);
+
+    args.next();
+    args.next()
+}
 
 fn main() -> amethyst::Result<()> {
     amethyst::start_logger(Default::default());
@@ -21,9 +49,29 @@ fn main() -> amethyst::Result<()> {
 
     let resources = app_root.join("assets");
     let display_config = resources.join("display_config.ron");
+    let input_config = resources.join("input.ron");
+
+    let data_dir = resolve_data_dir(arg_value("--data-dir"));
+    log::info!("Using data directory {}", data_dir.display());
+
+    // The player's saved bindings override the defaults.
+    let default_bindings = load_default_bindings(&input_config)?;
+    let mut input = InputHandler::<StringBindings>::new();
+    input.bindings = load_user_bindings(&default_bindings, &data_dir);
+
+    // The window starts at the saved size. Fullscreen is applied once the main menu starts.
+    let mut save_data = load_save_data(&data_dir);
+    let display_settings = save_data.data.settings.display.clone();
+
+    let mut display_config = DisplayConfig::load(&display_config)?;
+    display_config.dimensions = Some((
+        display_settings.window_width,
+        display_settings.window_height,
+    ));
 
     let game_data = GameDataBuilder::default()
-        .with_bundle(InputBundle::<StringBindings>::new())?
+        // Takes the place of `InputBundle`'s input system, so live input can be kept out of replays.
+        .with_system_desc(LiveInputSystemDesc::default(), "input_system", &[])
         .with_bundle(TransformBundle::new())?
         .with_bundle(UiBundle::<StringBindings>::new())?
         .with_bundle(AudioBundle::default())?
@@ -33,17 +81,75 @@ fn main() -> amethyst::Result<()> {
             "background_music",
             &[],
         )
+        .with(MuteSystem::default(), "mute", &[])
         .with_system_desc(AbilityBarSystemDesc::default(), "ability_bar", &[])
+        .with(
+            PlayfieldLayoutSystem::default(),
+            "playfield_layout",
+            &["ability_bar"],
+        )
+        .with(
+            Processor::<LevelDefinition>::new(),
+            "level_definition_processor",
+            &[],
+        )
+        .with(
+            Processor::<SpawnTimeline>::new(),
+            "spawn_timeline_processor",
+            &[],
+        )
         .with_bundle(
             RenderingBundle::<DefaultBackend>::new()
                 .with_plugin(
-                    RenderToWindow::from_config_path(display_config)?.with_clear([0., 0., 0., 1.0]),
+                    RenderToWindow::from_config(display_config).with_clear([0., 0., 0., 1.0]),
                 )
                 .with_plugin(RenderFlat2D::default())
                 .with_plugin(RenderUi::default()),
         )?;
 
-    let mut game = Application::new(resources, MainMenuState::default(), game_data)?;
+    // An invalid seed is reported instead of crashing, and levels pick a random seed like without `--seed`.
+    let seed = arg_value("--seed").and_then(|seed| match seed.parse::<u64>() {
+        Ok(seed) => Some(seed),
+        Err(error) => {
+            log::error!("Ignoring --seed {}, it must be a u64: {}", seed, error);
+            None
+        }
+    });
+
+    // A replay that can't be loaded is reported in the main menu (like a save that couldn't be read) instead of crashing.
+    let replay = arg_value("--replay").and_then(|path| match load_replay(Path::new(&path)) {
+        Ok(replay) => Some(replay),
+        Err(error) => {
+            log::error!("Couldn't load the replay {}: {:?}", path, error);
+
+            let report = format!("The replay {} couldn't be loaded.", path);
+            save_data.load_report = Some(match save_data.load_report.take() {
+                Some(save_report) => format!("{} {}", save_report, report),
+                None => report,
+            });
+
+            None
+        }
+    });
+
+    let (frame_limit_strategy, max_fps) = display_settings.frame_limit();
+
+    let mut game = Application::build(resources, MainMenuState::default())?
+        .with_frame_limit(frame_limit_strategy, max_fps)
+        .with_resource(input)
+        .with_resource(SeedResource { seed })
+        .with_resource(PendingReplayResource { replay })
+        .with_resource(ReplayPlaybackResource::default())
+        .with_resource(save_data)
+        .with_resource(AppliedDisplaySettingsResource::default())
+        .with_resource(DefaultBindingsResource {
+            bindings: default_bindings,
+        })
+        .with_resource(DataDirResource { path: data_dir })
+        .with_resource(TextEntryActiveResource::default())
+        .with_resource(DifficultyResource::default())
+        .with_resource(CampaignResource::default())
+        .build(game_data)?;
     game.run();
 
     Ok(())
@@ -0,0 +1,142 @@
+use amethyst::core::ecs::{Component, DenseVecStorage};
+use amethyst::ui::{Anchor, UiTransform};
+use amethyst::window::ScreenDimensions;
+
+/// Width of the playfield in logical units. Levels are laid out in these units no matter the window size.
+pub const PLAYFIELD_WIDTH: f32 = 600.;
+/// Height of the playfield in logical units.
+pub const PLAYFIELD_HEIGHT: f32 = 600.;
+
+/// Where the playfield sits in the window.
+/// It's scaled as big as it fits and centered, leaving black bars on the longer side of the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Playfield {
+    pub screen_width: f32,
+    pub screen_height: f32,
+    /// Pixels per logical unit.
+    pub scale: f32,
+    /// Pixels between the left of the window and the left of the playfield.
+    pub x_offset: f32,
+    /// Pixels between the bottom of the window and the bottom of the playfield.
+    pub y_offset: f32,
+}
+
+impl Playfield {
+    pub fn new(screen_width: f32, screen_height: f32) -> Self {
+        let scale = (screen_width / PLAYFIELD_WIDTH)
+            .min(screen_height / PLAYFIELD_HEIGHT)
+            .max(f32::EPSILON);
+
+        Playfield {
+            screen_width,
+            screen_height,
+            scale,
+            x_offset: (screen_width - PLAYFIELD_WIDTH * scale) * 0.5,
+            y_offset: (screen_height - PLAYFIELD_HEIGHT * scale) * 0.5,
+        }
+    }
+
+    pub fn from_dimensions(dimensions: &ScreenDimensions) -> Self {
+        Playfield::new(dimensions.width(), dimensions.height())
+    }
+
+    /// Logical units the camera has to show across the whole window (including the black bars).
+    pub fn view_width(&self) -> f32 {
+        self.screen_width / self.scale
+    }
+
+    /// Logical units the camera has to show up the whole window (including the black bars).
+    pub fn view_height(&self) -> f32 {
+        self.screen_height / self.scale
+    }
+
+    /// Converts a playfield point to pixels from the bottom left of the window.
+    pub fn to_screen(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.x_offset + x * self.scale,
+            self.y_offset + y * self.scale,
+        )
+    }
+
+    /// Converts pixels from the bottom left of the window to a playfield point.
+    pub fn from_screen(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            (x - self.x_offset) / self.scale,
+            (y - self.y_offset) / self.scale,
+        )
+    }
+
+    /// Converts a mouse position (in pixels from the top left of the window) to a playfield point.
+    pub fn mouse_to_playfield(&self, mouse_position: (f32, f32)) -> (f32, f32) {
+        self.from_screen(mouse_position.0, self.screen_height - mouse_position.1)
+    }
+
+    /// Converts a playfield point to a mouse position (in pixels from the top left of the window).
+    pub fn playfield_to_mouse(&self, x: f32, y: f32) -> (f32, f32) {
+        let (screen_x, screen_y) = self.to_screen(x, y);
+
+        (screen_x, self.screen_height - screen_y)
+    }
+
+    /// Creates a UI transform covering the playfield rectangle.
+    pub fn ui_transform(&self, id: String, rect: &PlayfieldUiComponent) -> UiTransform {
+        let (x, y) = self.to_screen(rect.x, rect.y);
+
+        UiTransform::new(
+            id,
+            Anchor::BottomLeft,
+            Anchor::Middle,
+            x,
+            y,
+            rect.z,
+            rect.width * self.scale,
+            rect.height * self.scale,
+        )
+    }
+}
+
+/// Lays out a UI element in playfield units instead of pixels.
+/// The `PlayfieldLayoutSystem` keeps its `UiTransform` in place when the window is resized,
+/// so systems should move the element by changing this instead of the transform.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayfieldUiComponent {
+    /// The center of the element.
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub width: f32,
+    pub height: f32,
+    /// The font size (in playfield units) of the element's `UiText`, if it has one.
+    pub font_size: Option<f32>,
+}
+impl Component for PlayfieldUiComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl PlayfieldUiComponent {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        PlayfieldUiComponent {
+            x,
+            y,
+            z: 0.,
+            width,
+            height,
+            font_size: None,
+        }
+    }
+
+    /// A square element centered on a point.
+    pub fn square(x: f32, y: f32, height_and_width: f32) -> Self {
+        PlayfieldUiComponent::new(x, y, height_and_width, height_and_width)
+    }
+
+    pub fn with_z(mut self, z: f32) -> Self {
+        self.z = z;
+        self
+    }
+
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+}
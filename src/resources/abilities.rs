@@ -1,5 +1,6 @@
 use amethyst::renderer::SpriteRender;
 use amethyst::ui::UiButton;
+use serde::Deserialize;
 
 /// All available abilities and all active abilities.
 #[derive(Default)]
@@ -26,6 +27,7 @@ pub struct Ability {
 }
 
 /// Type of ability.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
 pub enum AbilityType {
     FlySwatter,
     BugSpray,
@@ -37,6 +39,16 @@ pub enum AbilityType {
 
     Mask,
     SprayBottle,
+
+    Pesticide,
+    WideSpray,
+    Scarecrow,
+
+    PurchaseLimit,
+    RestockTruck,
+
+    MuteAll,
+    BreakoutRooms,
 }
 
 /// Information about an ability.
@@ -64,6 +76,8 @@ pub struct AbilityState {
     pub ui_button: Option<UiButton>,
     /// How many times the ability has been used.
     pub uses: u32,
+    /// Whether the ability's hotkey was down last frame (so holding it only triggers the ability once).
+    pub hotkey_down: bool,
 }
 
 impl AbilityState {
@@ -72,6 +86,7 @@ impl AbilityState {
             percentage: 1.0,
             ui_button: None,
             uses: 0,
+            hotkey_down: false,
         }
     }
     pub fn start_on_cooldown() -> Self {
@@ -79,6 +94,7 @@ impl AbilityState {
             percentage: 0.0,
             ui_button: None,
             uses: 0,
+            hotkey_down: false,
         }
     }
 }
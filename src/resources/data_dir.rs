@@ -0,0 +1,55 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that overrides the data directory (handy for tests).
+pub const DATA_DIR_ENV: &str = "SURVIVE2020_DATA_DIR";
+
+/// Name of the game's folder in the platform's data directory.
+const DATA_DIR_NAME: &str = "survive2020";
+
+/// Where the player's save data and bindings are stored.
+pub struct DataDirResource {
+    pub path: PathBuf,
+}
+
+/// Finds the data directory: `--data-dir` on the command line, then `SURVIVE2020_DATA_DIR`,
+/// then the platform's per-user data directory (`$XDG_DATA_HOME` or `~/.local/share` on Linux).
+/// Falls back to the working directory if none of those exist.
+pub fn resolve_data_dir(command_line_dir: Option<String>) -> PathBuf {
+    if let Some(dir) = command_line_dir {
+        return PathBuf::from(dir);
+    }
+
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV) {
+        return PathBuf::from(dir);
+    }
+
+    match dirs::data_dir() {
+        Some(dir) => dir.join(DATA_DIR_NAME),
+        None => {
+            log::warn!("Couldn't find a data directory, saving to the working directory instead.");
+            PathBuf::from(".")
+        }
+    }
+}
+
+/// Writes a file by writing a temporary file next to it and renaming it over the original.
+/// A crash part way through leaves the old file untouched.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder)?;
+    }
+
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+
+    std::fs::rename(&temp_path, path)
+}
@@ -0,0 +1,171 @@
+use amethyst::core::ecs::{World, WorldExt};
+
+/// How hard the levels are. Chosen in the main menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::Normal
+    }
+}
+
+/// Every difficulty, from easiest to hardest.
+pub const DIFFICULTIES: &[Difficulty] = &[
+    Difficulty::Easy,
+    Difficulty::Normal,
+    Difficulty::Hard,
+    Difficulty::Nightmare,
+];
+
+/// How a difficulty scales each level's tuning. Normal leaves everything as is (1.0).
+pub struct DifficultyModifiers {
+    /// Multiplies how many fires can burn before the Wildfires level is lost.
+    pub max_fires: f32,
+    /// Multiplies the player's health in the COVID-19 level and the toilet paper stock in Toilet Paper Panic.
+    pub health_pool: f32,
+    /// Multiplies the lives the player starts endless Murder Hornets with.
+    pub lives: f32,
+    /// Multiplies how long the Murder Hornets level lasts.
+    pub max_seconds: f32,
+    /// Multiplies the time between fire spawns (lower spawns faster).
+    pub fire_interval: f32,
+    /// Multiplies the most bees that can spawn at once.
+    pub bees_per_spawn: f32,
+    /// Multiplies how fast the amount of super spreaders ramps up.
+    pub spreader_ramp: f32,
+    /// Multiplies how many locusts fly in each flock.
+    pub locusts_per_flock: f32,
+    /// Multiplies the time between shopper spawns (lower spawns faster).
+    pub shopper_interval: f32,
+    /// Multiplies the time between Zoom meeting incidents (lower happens more often).
+    pub incident_interval: f32,
+    /// Multiplies how long the player has to respond to a Zoom meeting incident.
+    pub incident_seconds: f32,
+    /// Multiplies the health of bosses.
+    pub boss_health: f32,
+}
+
+impl Difficulty {
+    /// The name shown to the player.
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Nightmare => "Nightmare",
+        }
+    }
+
+    /// Finds a difficulty by its name (as stored in replays).
+    pub fn from_name(name: &str) -> Option<Self> {
+        DIFFICULTIES
+            .iter()
+            .find(|difficulty| difficulty.name() == name)
+            .cloned()
+    }
+
+    /// The next harder difficulty, wrapping around to Easy after Nightmare.
+    pub fn next(self) -> Self {
+        let index = DIFFICULTIES
+            .iter()
+            .position(|difficulty| *difficulty == self)
+            .unwrap();
+
+        DIFFICULTIES[(index + 1) % DIFFICULTIES.len()]
+    }
+
+    pub fn modifiers(self) -> DifficultyModifiers {
+        match self {
+            Difficulty::Easy => DifficultyModifiers {
+                max_fires: 1.5,
+                health_pool: 1.5,
+                lives: 1.5,
+                max_seconds: 0.8,
+                fire_interval: 1.3,
+                bees_per_spawn: 0.6,
+                spreader_ramp: 0.7,
+                locusts_per_flock: 0.7,
+                shopper_interval: 1.3,
+                incident_interval: 1.3,
+                incident_seconds: 1.3,
+                boss_health: 0.7,
+            },
+            Difficulty::Normal => DifficultyModifiers {
+                max_fires: 1.0,
+                health_pool: 1.0,
+                lives: 1.0,
+                max_seconds: 1.0,
+                fire_interval: 1.0,
+                bees_per_spawn: 1.0,
+                spreader_ramp: 1.0,
+                locusts_per_flock: 1.0,
+                shopper_interval: 1.0,
+                incident_interval: 1.0,
+                incident_seconds: 1.0,
+                boss_health: 1.0,
+            },
+            Difficulty::Hard => DifficultyModifiers {
+                max_fires: 0.75,
+                health_pool: 0.75,
+                lives: 0.75,
+                max_seconds: 1.2,
+                fire_interval: 0.8,
+                bees_per_spawn: 1.4,
+                spreader_ramp: 1.4,
+                locusts_per_flock: 1.3,
+                shopper_interval: 0.8,
+                incident_interval: 0.8,
+                incident_seconds: 0.8,
+                boss_health: 1.3,
+            },
+            Difficulty::Nightmare => DifficultyModifiers {
+                max_fires: 0.5,
+                health_pool: 0.5,
+                lives: 0.5,
+                max_seconds: 1.5,
+                fire_interval: 0.6,
+                bees_per_spawn: 1.8,
+                spreader_ramp: 2.0,
+                locusts_per_flock: 1.6,
+                shopper_interval: 0.6,
+                incident_interval: 0.6,
+                incident_seconds: 0.65,
+                boss_health: 1.6,
+            },
+        }
+    }
+}
+
+/// The difficulty levels are started with.
+#[derive(Default)]
+pub struct DifficultyResource {
+    pub difficulty: Difficulty,
+}
+
+/// Scales a whole number by a modifier (never going below 1).
+pub fn scale(value: u64, modifier: f32) -> u64 {
+    ((value as f32 * modifier).round() as u64).max(1)
+}
+
+/// The key a level's score and leaderboard are saved under for a difficulty.
+/// Normal uses the plain level key so scores from before difficulties existed count as Normal.
+pub fn score_key(level_key: &str, difficulty: Difficulty) -> String {
+    match difficulty {
+        Difficulty::Normal => level_key.to_string(),
+        _ => format!("{}_{}", level_key, difficulty.name().to_lowercase()),
+    }
+}
+
+/// The score key of a level for the difficulty that's currently selected.
+pub fn current_score_key(world: &World, level_key: &str) -> String {
+    score_key(
+        level_key,
+        world.read_resource::<DifficultyResource>().difficulty,
+    )
+}
@@ -0,0 +1,129 @@
+use crate::resources::save_data::{DisplaySettings, SaveDataResource};
+
+use amethyst::core::ecs::{World, WorldExt};
+use amethyst::core::frame_limiter::{FrameLimiter, FrameRateLimitStrategy};
+use amethyst::window::Window;
+use amethyst::winit::dpi::LogicalSize;
+use std::time::Duration;
+
+/// Window sizes (width, height) the settings menu cycles through. The playfield is scaled to fit any of them.
+pub const WINDOW_SIZES: &[(u32, u32)] = &[
+    (600, 600),
+    (800, 800),
+    (1000, 1000),
+    (1024, 768),
+    (1280, 720),
+    (1600, 900),
+    (1920, 1080),
+];
+
+/// Frame caps the settings menu cycles through. 0 means no cap.
+pub const FRAME_CAPS: &[u32] = &[30, 60, 120, 144, 240, 0];
+
+/// The refresh rate frames are paced to with vsync on.
+/// Amethyst doesn't let the game pick the swapchain's present mode and winit can't tell the monitor's refresh rate,
+/// so vsync is done by the frame limiter at the most common refresh rate.
+pub const VSYNC_REFRESH_RATE: u32 = 60;
+
+/// The display settings the window was last set up with, so only the ones that changed get applied again.
+#[derive(Default)]
+pub struct AppliedDisplaySettingsResource {
+    pub settings: Option<DisplaySettings>,
+}
+
+/// The item after `current` in a list of presets, wrapping around. Starts from the first preset if `current` isn't one.
+fn next_preset<T: Copy + PartialEq>(presets: &[T], current: T) -> T {
+    let index = presets
+        .iter()
+        .position(|preset| *preset == current)
+        .map_or(0, |index| index + 1);
+
+    presets[index % presets.len()]
+}
+
+impl DisplaySettings {
+    /// Switches to the next size in `WINDOW_SIZES`.
+    pub fn next_window_size(&mut self) {
+        let (width, height) = next_preset(WINDOW_SIZES, (self.window_width, self.window_height));
+
+        self.window_width = width;
+        self.window_height = height;
+    }
+
+    /// Switches to the next cap in `FRAME_CAPS`.
+    pub fn next_frame_cap(&mut self) {
+        self.frame_cap = next_preset(FRAME_CAPS, self.frame_cap);
+    }
+
+    /// How the frame limiter paces frames (strategy and frames per second).
+    pub fn frame_limit(&self) -> (FrameRateLimitStrategy, u32) {
+        if self.vsync {
+            // Yielding instead of sleeping keeps frames evenly spaced, so they line up with the display's refreshes.
+            (FrameRateLimitStrategy::Yield, VSYNC_REFRESH_RATE)
+        } else if self.frame_cap == 0 {
+            // The limiter still needs a non-zero rate.
+            (FrameRateLimitStrategy::Unlimited, FRAME_CAPS[0])
+        } else {
+            (
+                FrameRateLimitStrategy::SleepAndYield(Duration::from_millis(2)),
+                self.frame_cap,
+            )
+        }
+    }
+}
+
+/// Applies the saved display settings that changed since they were last applied to the window and frame limiter.
+/// Called when the main menu starts (so the settings apply at startup) and whenever they change in the settings menu.
+pub fn apply_display_settings(world: &mut World) {
+    let settings = world
+        .read_resource::<SaveDataResource>()
+        .data
+        .settings
+        .display
+        .clone();
+
+    let applied = world
+        .read_resource::<AppliedDisplaySettingsResource>()
+        .settings
+        .clone();
+
+    if applied.as_ref() == Some(&settings) {
+        return;
+    }
+
+    {
+        let window = world.read_resource::<Window>();
+
+        let fullscreen_changed = applied
+            .as_ref()
+            .map_or(true, |applied| applied.fullscreen != settings.fullscreen);
+
+        if fullscreen_changed {
+            if settings.fullscreen {
+                window.set_fullscreen(Some(window.get_current_monitor()));
+            } else {
+                window.set_fullscreen(None);
+            }
+        }
+
+        let size_changed = applied.as_ref().map_or(true, |applied| {
+            (applied.window_width, applied.window_height)
+                != (settings.window_width, settings.window_height)
+        });
+
+        // The size is kept for when the window leaves fullscreen.
+        if !settings.fullscreen && (size_changed || fullscreen_changed) {
+            window.set_inner_size(LogicalSize::new(
+                settings.window_width as f64,
+                settings.window_height as f64,
+            ));
+        }
+    }
+
+    let (strategy, fps) = settings.frame_limit();
+    *world.write_resource::<FrameLimiter>() = FrameLimiter::new(strategy, fps);
+
+    world
+        .write_resource::<AppliedDisplaySettingsResource>()
+        .settings = Some(settings);
+}
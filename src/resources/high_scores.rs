@@ -1,14 +1,19 @@
-use savefile::prelude::*;
+use crate::resources::difficulty::current_score_key;
+use crate::resources::replay::ReplayPlaybackResource;
+use crate::resources::save_data::SaveDataResource;
 
 use amethyst::core::ecs::{World, WorldExt};
-use savefile_derive::Savefile;
-use std::collections::HashMap;
 
-/// Keys for the `high_scores` HashMap.
+/// Keys for the level scores in `SaveData::scores` (on Normal, see `difficulty::score_key`).
 pub mod highscores_keys {
     pub const WILDFIRES: &str = "wildfires";
     pub const HORNETS: &str = "hornets";
+    pub const HORNETS_ENDLESS: &str = "hornets_endless";
     pub const COVID: &str = "covid";
+    pub const LOCUSTS: &str = "locusts";
+    pub const TOILET_PAPER: &str = "toilet_paper";
+    pub const ZOOM: &str = "zoom";
+    pub const CAMPAIGN: &str = "campaign";
 }
 
 /// Each level will create this resource when it starts.
@@ -17,44 +22,27 @@ pub struct CurrentLevelScoreResource {
     pub(crate) score: u64,
 }
 
-#[derive(Default, Savefile)]
-pub struct HighScoresResource {
-    /// Keys:
-    /// wildfires
-    /// hornets
-    /// covid
-    pub high_scores: HashMap<String, u64>,
-}
-
-impl HighScoresResource {
-    pub fn get_score(&self, key: &str) -> u64 {
-        *self.high_scores.get(key).unwrap_or(&0)
+/// Updates the high score for a level based on it's key and the current difficulty (only if the new score is higher!)
+pub fn update_high_score_if_greater(world: &mut World, level_key: &str) {
+    // Replays are someone's old run, not a new score.
+    if world.read_resource::<ReplayPlaybackResource>().active {
+        return;
     }
-}
 
-/// Save HighScores to file.
-pub fn save_scores(scores: &HighScoresResource) {
-    save_file("high_scores.txt", 0, scores).expect("Couldn't save high scores file.");
-}
-
-/// Load HighScores from file.
-pub fn load_scores() -> HighScoresResource {
-    load_file("high_scores.txt", 0).unwrap_or_default()
-}
+    let key = &current_score_key(world, level_key);
 
-/// Updates the high score for a level based on it's key (only if the new score is higher!)
-pub fn update_high_score_if_greater(world: &mut World, key: &str) {
     let new_score = world.write_resource::<CurrentLevelScoreResource>();
 
-    let mut resource = world.write_resource::<HighScoresResource>();
+    let mut resource = world.write_resource::<SaveDataResource>();
 
-    let past_score = resource.get_score(key);
+    let past_score = resource.data.get_score(key);
 
     if new_score.score > past_score {
         resource
-            .high_scores
+            .data
+            .scores
             .insert(key.to_string(), new_score.score);
 
-        save_scores(&*resource);
+        resource.save();
     }
 }
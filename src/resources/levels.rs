@@ -0,0 +1,287 @@
+use amethyst::assets::{
+    Asset, AssetStorage, Handle, Loader, ProcessingState, ProgressCounter, RonFormat,
+};
+use amethyst::core::ecs::{VecStorage, World, WorldExt};
+use amethyst::Error;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::load_sprite;
+use crate::resources::abilities::{
+    AbilitiesResource, Ability, AbilityInfo, AbilityState, AbilityType,
+};
+use crate::resources::high_scores::highscores_keys::{
+    CAMPAIGN, COVID, HORNETS, HORNETS_ENDLESS, LOCUSTS, TOILET_PAPER, WILDFIRES, ZOOM,
+};
+use crate::resources::spawn_timeline::{SpawnTimeline, SPAWN_TIMELINE_KEYS};
+
+/// Keys of all level definitions that get loaded from `assets/levels/{key}.ron`.
+pub const LEVEL_DEFINITION_KEYS: &[&str] = &[
+    WILDFIRES,
+    HORNETS,
+    HORNETS_ENDLESS,
+    COVID,
+    LOCUSTS,
+    TOILET_PAPER,
+    ZOOM,
+];
+
+/// The name of a level (or the campaign) shown to the player.
+pub fn level_name(level_key: &str) -> &str {
+    match level_key {
+        WILDFIRES => "Wildfires",
+        HORNETS => "Murder Hornets",
+        HORNETS_ENDLESS => "Endless Hornets",
+        COVID => "Covid-19",
+        LOCUSTS => "Locust Swarms",
+        TOILET_PAPER => "Toilet Paper Panic",
+        ZOOM => "Zoom Meeting Survival",
+        CAMPAIGN => "Campaign",
+        _ => level_key,
+    }
+}
+
+/// Everything a level needs to set itself up. Loaded from `assets/levels/*.ron`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LevelDefinition {
+    /// Filename of the background sprite.
+    pub background: String,
+    /// Filename of the title sprite.
+    pub title: String,
+    /// How many seconds the level lasts (only used by timed levels).
+    #[serde(default)]
+    pub max_seconds: Option<f32>,
+    /// How many fires can be burning at once before the level ends (only used by Wildfires).
+    #[serde(default)]
+    pub max_fires: Option<u64>,
+    /// How much health the player starts with (only used by COVID-19).
+    #[serde(default)]
+    pub health_pool: Option<u64>,
+    /// How many crops the player defends (only used by Locust Swarms).
+    #[serde(default)]
+    pub crops: Option<u64>,
+    /// How many crops are planted in each row of the field (only used by Locust Swarms).
+    #[serde(default)]
+    pub crops_per_row: Option<u64>,
+    /// Seconds between flock spawns (only used by Locust Swarms).
+    #[serde(default)]
+    pub flock_spawn_seconds: Option<f32>,
+    /// How many locusts fly in each flock on Normal at the start of the level (only used by Locust Swarms).
+    #[serde(default)]
+    pub locusts_per_flock: Option<u64>,
+    /// Seconds it takes for flocks to grow by one locust (only used by Locust Swarms).
+    #[serde(default)]
+    pub flock_growth_seconds: Option<f32>,
+    /// How many rolls of toilet paper the shelves hold (only used by Toilet Paper Panic).
+    #[serde(default)]
+    pub max_stock: Option<u64>,
+    /// Seconds between shopper spawns on Normal (only used by Toilet Paper Panic).
+    #[serde(default)]
+    pub shopper_spawn_seconds: Option<f32>,
+    /// Seconds between restock packs appearing (only used by Toilet Paper Panic).
+    #[serde(default)]
+    pub restock_pack_spawn_seconds: Option<f32>,
+    /// Seconds it takes for one more shopper to be able to spawn at a time (only used by Toilet Paper Panic).
+    #[serde(default)]
+    pub shopper_ramp_seconds: Option<f32>,
+    /// Seconds it takes for the hoarder chance to go up by 100%, capped at 50% (only used by Toilet Paper Panic).
+    #[serde(default)]
+    pub hoarder_ramp_seconds: Option<f32>,
+    /// Seconds the player has to respond to an incident before it's missed (only used by Zoom Meeting Survival).
+    #[serde(default)]
+    pub incident_seconds: Option<f32>,
+    /// Seconds between incidents on Normal at the start of the level (only used by Zoom Meeting Survival).
+    #[serde(default)]
+    pub incident_spawn_seconds: Option<f32>,
+    /// Incidents never happen more often than this many seconds apart (only used by Zoom Meeting Survival).
+    #[serde(default)]
+    pub min_incident_spawn_seconds: Option<f32>,
+    /// Every this many seconds incidents happen `incident_ramp_step_seconds` sooner (only used by Zoom Meeting Survival).
+    #[serde(default)]
+    pub incident_ramp_seconds: Option<f32>,
+    #[serde(default)]
+    pub incident_ramp_step_seconds: Option<f32>,
+    /// Points lost for clicking on a tile with the wrong response (only used by Zoom Meeting Survival).
+    #[serde(default)]
+    pub wrong_click_penalty: Option<u64>,
+    /// Seconds into the level when the boss appears (only used by COVID-19). The level has no boss if not set.
+    #[serde(default)]
+    pub boss_seconds: Option<f32>,
+    /// How much health the boss has (only used by COVID-19).
+    #[serde(default)]
+    pub boss_health: Option<u64>,
+    /// How many lives the player starts with (only used by endless levels).
+    #[serde(default)]
+    pub lives: Option<u64>,
+    /// The score needed to pass the level in the campaign and to unlock the levels after it. Always passes if not set.
+    #[serde(default)]
+    pub pass_score: Option<u64>,
+    /// Seed for the level's random number generator. Random if not set.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// The abilities shown in the ability bar, in order.
+    pub abilities: Vec<AbilityDefinition>,
+}
+
+impl Asset for LevelDefinition {
+    const NAME: &'static str = "survive2020::LevelDefinition";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<LevelDefinition>>;
+}
+
+impl From<LevelDefinition> for Result<ProcessingState<LevelDefinition>, Error> {
+    fn from(definition: LevelDefinition) -> Result<ProcessingState<LevelDefinition>, Error> {
+        Ok(ProcessingState::Loaded(definition))
+    }
+}
+
+/// An ability as described in a level definition.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AbilityDefinition {
+    pub ability_type: AbilityType,
+    /// Filename of the ability's icon sprite.
+    pub icon: String,
+    pub seconds_to_charge: u32,
+    #[serde(default)]
+    pub duration: Option<u32>,
+    #[serde(default)]
+    pub max_uses: Option<u32>,
+    /// If true the ability has to charge before it can be used for the first time.
+    #[serde(default)]
+    pub start_on_cooldown: bool,
+}
+
+impl AbilityDefinition {
+    /// Loads the ability's icon and creates an `Ability` from this definition.
+    pub fn create_ability(&self, world: &mut World) -> Ability {
+        let icon = load_sprite(world, &self.icon, 0);
+
+        Ability {
+            info: AbilityInfo {
+                ability_type: self.ability_type,
+                seconds_to_charge: self.seconds_to_charge,
+                duration: self.duration,
+                icon,
+                max_uses: self.max_uses,
+            },
+            current_state: if self.start_on_cooldown {
+                AbilityState::start_on_cooldown()
+            } else {
+                AbilityState::default()
+            },
+        }
+    }
+}
+
+/// Resource storing the handles of all level definitions and spawn timelines.
+pub struct LevelDefinitionsResource {
+    pub definitions: HashMap<String, Handle<LevelDefinition>>,
+    pub spawn_timelines: HashMap<String, Handle<SpawnTimeline>>,
+    /// Tracks the loading of all the definitions and timelines.
+    pub progress: ProgressCounter,
+}
+
+impl LevelDefinitionsResource {
+    /// Returns true once every level definition and spawn timeline has finished loading.
+    pub fn is_loaded(&self) -> bool {
+        self.progress.is_complete()
+    }
+}
+
+/// Starts loading every level definition and spawn timeline (if they are not already loaded).
+pub fn load_level_definitions(world: &mut World) {
+    if world.has_value::<LevelDefinitionsResource>() {
+        return;
+    }
+
+    let resource = {
+        let loader = world.read_resource::<Loader>();
+        let storage = world.read_resource::<AssetStorage<LevelDefinition>>();
+        let timeline_storage = world.read_resource::<AssetStorage<SpawnTimeline>>();
+
+        let mut progress = ProgressCounter::new();
+        let mut definitions = HashMap::new();
+
+        for key in LEVEL_DEFINITION_KEYS {
+            definitions.insert(
+                key.to_string(),
+                loader.load(
+                    format!("levels/{}.ron", key),
+                    RonFormat,
+                    &mut progress,
+                    &storage,
+                ),
+            );
+        }
+
+        let mut spawn_timelines = HashMap::new();
+
+        for key in SPAWN_TIMELINE_KEYS {
+            spawn_timelines.insert(
+                key.to_string(),
+                loader.load(
+                    format!("spawns/{}.ron", key),
+                    RonFormat,
+                    &mut progress,
+                    &timeline_storage,
+                ),
+            );
+        }
+
+        LevelDefinitionsResource {
+            definitions,
+            spawn_timelines,
+            progress,
+        }
+    };
+
+    world.insert(resource);
+}
+
+/// Gets a copy of a level's definition based on its key.
+/// Will panic if the definition has not finished loading.
+pub fn get_level_definition(world: &World, key: &str) -> LevelDefinition {
+    let definitions = world.read_resource::<LevelDefinitionsResource>();
+    let storage = world.read_resource::<AssetStorage<LevelDefinition>>();
+
+    let handle = definitions
+        .definitions
+        .get(key)
+        .unwrap_or_else(|| panic!("No level definition with the key {}!", key));
+
+    storage
+        .get(handle)
+        .unwrap_or_else(|| panic!("Level definition {} has not been loaded!", key))
+        .clone()
+}
+
+/// Gets a copy of a level's spawn timeline based on its key.
+/// Will panic if the timeline has not finished loading.
+pub fn get_spawn_timeline(world: &World, key: &str) -> SpawnTimeline {
+    let definitions = world.read_resource::<LevelDefinitionsResource>();
+    let storage = world.read_resource::<AssetStorage<SpawnTimeline>>();
+
+    let handle = definitions
+        .spawn_timelines
+        .get(key)
+        .unwrap_or_else(|| panic!("No spawn timeline with the key {}!", key));
+
+    storage
+        .get(handle)
+        .unwrap_or_else(|| panic!("Spawn timeline {} has not been loaded!", key))
+        .clone()
+}
+
+/// Creates an `AbilitiesResource` from the abilities in a level definition.
+pub fn create_abilities_from_definition(
+    world: &mut World,
+    definition: &LevelDefinition,
+) -> AbilitiesResource {
+    let abilities = definition
+        .abilities
+        .iter()
+        .map(|ability| ability.create_ability(world))
+        .collect();
+
+    AbilitiesResource::new(abilities)
+}
@@ -1,2 +1,11 @@
 pub mod abilities;
+pub mod data_dir;
+pub mod difficulty;
+pub mod display;
 pub mod high_scores;
+pub mod levels;
+pub mod replay;
+pub mod rng;
+pub mod save_data;
+pub mod spawn_timeline;
+pub mod unlocks;
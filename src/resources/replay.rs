@@ -0,0 +1,167 @@
+use savefile::prelude::*;
+
+use crate::input::bound_buttons;
+use crate::playfield::{Playfield, PLAYFIELD_HEIGHT};
+
+use amethyst::input::{Bindings, Button, InputHandler, StringBindings};
+use amethyst::winit::{MouseButton, VirtualKeyCode};
+use savefile_derive::Savefile;
+use std::path::{Path, PathBuf};
+
+/// Version of the replay file format.
+pub const REPLAY_VERSION: u32 = 2;
+
+/// Folder (in the data directory, next to the save data) where replays get saved.
+pub const REPLAYS_FOLDER: &str = "replays";
+
+/// Mouse buttons whose state gets recorded. A button's index in this list is its bit in `RecordedFrame::mouse_buttons_down`.
+pub const RECORDED_MOUSE_BUTTONS: &[MouseButton] =
+    &[MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+/// The keys whose state gets recorded: every key used by the bindings.
+/// A key's index in this list is its bit in `RecordedFrame::keys_down`.
+pub fn recorded_keys(bindings: &Bindings<StringBindings>) -> Vec<VirtualKeyCode> {
+    bound_buttons(bindings)
+        .into_iter()
+        .filter_map(|button| match button {
+            Button::Key(key) => Some(key),
+            _ => None,
+        })
+        .take(32)
+        .collect()
+}
+
+/// A recorded run of a level.
+#[derive(Default, Savefile)]
+pub struct Replay {
+    /// The key of the level that was played.
+    pub level_key: String,
+    /// The seed of the level's `RngResource`.
+    pub seed: u64,
+    /// The input bindings (as RON) the level was played with.
+    pub bindings: String,
+    /// The name of the difficulty the level was played on. Empty in replays from before difficulties (played on Normal).
+    #[savefile_versions = "2.."]
+    pub difficulty: String,
+    /// The input of every frame the level was updated.
+    pub frames: Vec<RecordedFrame>,
+}
+
+/// The mouse position in a frame, in playfield units from the top left of the playfield
+/// (the same as pixels from the top left of a 600x600 window), so replays play back at any window size.
+#[derive(Clone, Copy, Default, Savefile)]
+pub struct RecordedMousePosition {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Everything the level systems could read from the player in one frame.
+#[derive(Clone, Default, Savefile)]
+pub struct RecordedFrame {
+    pub delta_seconds: f32,
+    /// Bitmask of the `recorded_keys` that were down.
+    pub keys_down: u32,
+    /// Bitmask of the `RECORDED_MOUSE_BUTTONS` that were down.
+    pub mouse_buttons_down: u8,
+    pub mouse_position: Option<RecordedMousePosition>,
+    /// Indexes of the abilities that were clicked in the ability bar.
+    pub ability_clicks: Vec<u8>,
+}
+
+impl RecordedFrame {
+    /// Records the state of the input handler.
+    pub fn capture(
+        input: &InputHandler<StringBindings>,
+        keys: &[VirtualKeyCode],
+        playfield: &Playfield,
+        delta_seconds: f32,
+    ) -> Self {
+        let mut keys_down = 0;
+        for (i, key) in keys.iter().enumerate() {
+            if input.key_is_down(*key) {
+                keys_down |= 1 << i;
+            }
+        }
+
+        let mut mouse_buttons_down = 0;
+        for (i, button) in RECORDED_MOUSE_BUTTONS.iter().enumerate() {
+            if input.mouse_button_is_down(*button) {
+                mouse_buttons_down |= 1 << i;
+            }
+        }
+
+        RecordedFrame {
+            delta_seconds,
+            keys_down,
+            mouse_buttons_down,
+            mouse_position: input.mouse_position().map(|mouse_position| {
+                let (x, y) = playfield.mouse_to_playfield(mouse_position);
+
+                RecordedMousePosition {
+                    x,
+                    y: PLAYFIELD_HEIGHT - y,
+                }
+            }),
+            ability_clicks: Vec::new(),
+        }
+    }
+
+    /// Converts the recorded mouse position to pixels from the top left of the window.
+    pub fn window_mouse_position(&self, playfield: &Playfield) -> Option<(f32, f32)> {
+        self.mouse_position
+            .map(|position| playfield.playfield_to_mouse(position.x, PLAYFIELD_HEIGHT - position.y))
+    }
+
+    /// Returns true if the key at this index of `recorded_keys` was down.
+    pub fn key_is_down(&self, index: usize) -> bool {
+        self.keys_down & (1 << index) != 0
+    }
+
+    /// Returns true if the button at this index of `RECORDED_MOUSE_BUTTONS` was down.
+    pub fn mouse_button_is_down(&self, index: usize) -> bool {
+        self.mouse_buttons_down & (1 << index) != 0
+    }
+}
+
+/// A replay passed on the command line with `--replay <file>`, waiting for the main menu to start it.
+#[derive(Default)]
+pub struct PendingReplayResource {
+    pub replay: Option<Replay>,
+}
+
+/// Whether a replay is being played back. Finishing a level during playback doesn't save anything.
+#[derive(Default)]
+pub struct ReplayPlaybackResource {
+    pub active: bool,
+}
+
+/// Converts input bindings to RON so they can be stored in a replay.
+pub fn serialize_bindings(bindings: &Bindings<StringBindings>) -> String {
+    ron::ser::to_string(bindings).expect("Couldn't serialize the input bindings!")
+}
+
+/// Reads the input bindings stored in a replay.
+pub fn deserialize_bindings(bindings: &str) -> Result<Bindings<StringBindings>, ron::de::Error> {
+    ron::de::from_str(bindings)
+}
+
+/// The path (in the data directory) the latest run of a level gets saved to.
+pub fn last_replay_path(data_dir: &Path, level_key: &str) -> PathBuf {
+    data_dir
+        .join(REPLAYS_FOLDER)
+        .join(format!("last_{}.replay", level_key))
+}
+
+/// Save a replay to a file.
+pub fn save_replay(path: &Path, replay: &Replay) -> Result<(), SavefileError> {
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder)?;
+    }
+
+    save_file(&path.to_string_lossy(), REPLAY_VERSION, replay)
+}
+
+/// Load a replay from a file.
+pub fn load_replay(path: &Path) -> Result<Replay, SavefileError> {
+    load_file(&path.to_string_lossy(), REPLAY_VERSION)
+}
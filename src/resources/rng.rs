@@ -0,0 +1,48 @@
+use amethyst::core::ecs::{World, WorldExt};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::resources::levels::LevelDefinition;
+
+/// The seed passed on the command line with `--seed <u64>` (if any).
+#[derive(Default)]
+pub struct SeedResource {
+    pub seed: Option<u64>,
+}
+
+/// Random number generator shared by all gameplay systems.
+/// Every spawn, jitter and expiration roll should draw from this so runs can be reproduced from their seed.
+pub struct RngResource {
+    /// The seed the generator was created from.
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl RngResource {
+    pub fn new(seed: u64) -> Self {
+        RngResource {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for RngResource {
+    fn default() -> Self {
+        RngResource::new(rand::random())
+    }
+}
+
+/// Creates a fresh RNG resource for a level.
+/// The seed comes from the command line, then the level definition, and is random if neither has one.
+pub fn init_level_rng(world: &mut World, definition: &LevelDefinition) {
+    let seed = world
+        .read_resource::<SeedResource>()
+        .seed
+        .or(definition.seed)
+        .unwrap_or_else(rand::random);
+
+    log::info!("Starting level with seed {}", seed);
+
+    world.insert(RngResource::new(seed));
+}
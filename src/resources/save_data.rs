@@ -0,0 +1,377 @@
+use savefile::prelude::*;
+
+use crate::resources::data_dir::write_atomically;
+
+use chrono::{Local, TimeZone};
+use savefile_derive::Savefile;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File (in the data directory) the save data is written to.
+pub const SAVE_FILE: &str = "save_data.bin";
+
+/// File (relative to the working directory) the high scores were saved to before `SaveData` existed (schema version 0).
+pub const LEGACY_HIGH_SCORES_FILE: &str = "high_scores.txt";
+
+/// The current schema version of `SaveData`. Also used as the savefile version.
+/// Bump this when changing `SaveData`: mark new fields with `#[savefile_versions = "N.."]`
+/// and add a step to `migrate` if old saves need more than the field's default.
+pub const SAVE_DATA_VERSION: u32 = 5;
+
+/// How many entries each level's leaderboard keeps.
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// The name used on the leaderboard until the player enters one.
+pub const DEFAULT_PLAYER_NAME: &str = "Player";
+
+/// Everything the game keeps between runs.
+#[derive(Clone, Default, Savefile)]
+pub struct SaveData {
+    /// The schema version the save was written with. Brought up to `SAVE_DATA_VERSION` by `migrate`.
+    pub schema_version: u32,
+    /// The high score of each level (keys in `highscores_keys`).
+    pub scores: HashMap<String, u64>,
+    pub settings: SettingsData,
+    /// Keys of the levels the player has unlocked (see `unlocks::LEVEL_UNLOCKS`).
+    pub unlocks: Vec<String>,
+    pub stats: StatsData,
+    /// The best runs of each level (keys in `highscores_keys`), highest score first.
+    #[savefile_versions = "2.."]
+    pub leaderboards: HashMap<String, Vec<LeaderboardEntry>>,
+    /// The campaign the player is part way through, if any.
+    #[savefile_versions = "3.."]
+    pub campaign: Option<CampaignProgress>,
+}
+
+/// Player preferences.
+#[derive(Clone, Default, Savefile)]
+pub struct SettingsData {
+    /// The name last entered on the results screen.
+    #[savefile_versions = "2.."]
+    pub player_name: String,
+    #[savefile_versions = "4.."]
+    pub display: DisplaySettings,
+    #[savefile_versions = "5.."]
+    pub audio: AudioSettings,
+}
+
+/// How the game window is shown. Changed in the settings menu.
+#[derive(Clone, Debug, PartialEq, Savefile)]
+pub struct DisplaySettings {
+    pub fullscreen: bool,
+    /// The size of the window (in logical pixels) when it isn't fullscreen.
+    pub window_width: u32,
+    pub window_height: u32,
+    /// Paces frames to the display (see `display::VSYNC_REFRESH_RATE`).
+    pub vsync: bool,
+    /// The most frames drawn per second, or 0 for no cap. Ignored while vsync is on.
+    pub frame_cap: u32,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            fullscreen: false,
+            window_width: 600,
+            window_height: 600,
+            vsync: true,
+            frame_cap: 144,
+        }
+    }
+}
+
+/// How loud the game is. Changed in the settings menu, and muted with the mute hotkey.
+#[derive(Clone, Debug, PartialEq, Savefile)]
+pub struct AudioSettings {
+    /// Scales the music and sound effect volumes. Volumes go from 0 to 1.
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    /// Silences everything without forgetting the volumes.
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+/// A run on a level's leaderboard.
+#[derive(Clone, Default, Savefile)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: u64,
+    /// When the run finished (seconds since the Unix epoch). 0 if unknown.
+    pub timestamp: i64,
+    /// The seed of the run's `RngResource`.
+    pub seed: u64,
+    pub difficulty: String,
+}
+
+impl LeaderboardEntry {
+    /// The date of the run (like "2020-11-03"), or "-" if unknown.
+    pub fn date(&self) -> String {
+        if self.timestamp == 0 {
+            return "-".to_string();
+        }
+
+        Local
+            .timestamp(self.timestamp, 0)
+            .format("%Y-%m-%d")
+            .to_string()
+    }
+}
+
+/// How far the player got in a campaign. Saved after every passed level so the campaign can be resumed.
+#[derive(Clone, Default, Savefile)]
+pub struct CampaignProgress {
+    /// Index (in `CAMPAIGN_LEVELS`) of the level to play next.
+    pub level_index: u32,
+    /// The combined score of the passed levels.
+    pub score: u64,
+    /// The name of the difficulty the campaign is played on.
+    pub difficulty: String,
+}
+
+/// Lifetime stats.
+#[derive(Clone, Default, Savefile)]
+pub struct StatsData {
+    /// How many times each level has been played to the end.
+    pub runs_finished: HashMap<String, u64>,
+    /// Seconds spent in levels (counting only finished runs).
+    pub seconds_played: f64,
+}
+
+impl SaveData {
+    pub fn get_score(&self, key: &str) -> u64 {
+        *self.scores.get(key).unwrap_or(&0)
+    }
+
+    /// Counts a finished run of a level in the stats.
+    pub fn record_finished_run(&mut self, level_key: &str, seconds: f32) {
+        *self
+            .stats
+            .runs_finished
+            .entry(level_key.to_string())
+            .or_insert(0) += 1;
+
+        self.stats.seconds_played += seconds as f64;
+    }
+
+    /// The entries on a level's leaderboard, highest score first.
+    pub fn leaderboard(&self, level_key: &str) -> &[LeaderboardEntry] {
+        self.leaderboards
+            .get(level_key)
+            .map(|entries| entries.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Whether a score would make it onto a level's leaderboard.
+    pub fn qualifies_for_leaderboard(&self, level_key: &str, score: u64) -> bool {
+        let leaderboard = self.leaderboard(level_key);
+
+        score > 0
+            && (leaderboard.len() < LEADERBOARD_SIZE
+                || leaderboard.last().map_or(true, |entry| score > entry.score))
+    }
+
+    /// Adds a run to a level's leaderboard (dropping the lowest entry if it's full) and updates the high score.
+    pub fn add_leaderboard_entry(&mut self, level_key: &str, entry: LeaderboardEntry) {
+        if entry.score > self.get_score(level_key) {
+            self.scores.insert(level_key.to_string(), entry.score);
+        }
+
+        let leaderboard = self
+            .leaderboards
+            .entry(level_key.to_string())
+            .or_insert_with(Vec::new);
+
+        // Ties go below the runs that got the score first.
+        let index = leaderboard
+            .iter()
+            .position(|existing| entry.score > existing.score)
+            .unwrap_or_else(|| leaderboard.len());
+
+        leaderboard.insert(index, entry);
+        leaderboard.truncate(LEADERBOARD_SIZE);
+    }
+
+    /// The name to suggest on the results screen.
+    pub fn player_name(&self) -> &str {
+        if self.settings.player_name.is_empty() {
+            DEFAULT_PLAYER_NAME
+        } else {
+            &self.settings.player_name
+        }
+    }
+}
+
+/// The layout of `high_scores.txt` (schema version 0).
+#[derive(Default, Savefile)]
+struct LegacyHighScores {
+    high_scores: HashMap<String, u64>,
+}
+
+/// The save data and any problem found while loading it.
+pub struct SaveDataResource {
+    pub data: SaveData,
+    /// The file the save data is written to.
+    pub path: PathBuf,
+    /// Shown once in the main menu (like when a corrupt save had to be backed up).
+    pub load_report: Option<String>,
+}
+
+impl SaveDataResource {
+    /// Writes the save data to its file. Failing to save is logged instead of crashing the game.
+    pub fn save(&self) {
+        if let Err(error) = save_data_to_file(&self.path, &self.data) {
+            log::error!("Couldn't save {}: {:?}", self.path.display(), error);
+        }
+    }
+}
+
+/// Writes save data to a file atomically, so a crash part way through can't corrupt the old save.
+fn save_data_to_file(path: &Path, data: &SaveData) -> Result<(), SavefileError> {
+    let mut contents = Vec::new();
+    save(&mut contents, SAVE_DATA_VERSION, data)?;
+
+    write_atomically(path, &contents)?;
+
+    Ok(())
+}
+
+/// Migrates the legacy high scores (schema version 0) to schema version 1.
+fn migrate_legacy_high_scores(legacy: LegacyHighScores) -> SaveData {
+    SaveData {
+        schema_version: 1,
+        scores: legacy.high_scores,
+        ..Default::default()
+    }
+}
+
+/// Brings save data written with an older schema up to `SAVE_DATA_VERSION`, one version at a time.
+/// Fields added by a version are already filled with their default by savefile.
+fn migrate(mut save: SaveData) -> SaveData {
+    if save.schema_version < SAVE_DATA_VERSION {
+        log::info!(
+            "Migrating save data from schema version {} to {}",
+            save.schema_version,
+            SAVE_DATA_VERSION
+        );
+    }
+
+    // Version 2 added leaderboards: start each one with the level's high score.
+    if save.schema_version == 1 {
+        let scores: Vec<(String, u64)> = save
+            .scores
+            .iter()
+            .filter(|(_, score)| **score > 0)
+            .map(|(key, score)| (key.clone(), *score))
+            .collect();
+
+        for (level_key, score) in scores {
+            save.add_leaderboard_entry(
+                &level_key,
+                LeaderboardEntry {
+                    name: DEFAULT_PLAYER_NAME.to_string(),
+                    score,
+                    ..Default::default()
+                },
+            );
+        }
+
+        save.schema_version = 2;
+    }
+
+    // Version 3 added campaign progress, which starts out empty.
+
+    // Version 4 added display settings, which start out as the defaults.
+
+    // Version 5 added audio settings, which start out at full volume.
+
+    // Steps for later versions go here.
+
+    save.schema_version = SAVE_DATA_VERSION;
+    save
+}
+
+/// Moves a file that couldn't be loaded out of the way so it doesn't get overwritten.
+/// Returns a message for the player.
+fn back_up_unreadable_file(path: &Path, error: SavefileError) -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(format!(".corrupt-{}", seconds));
+    let backup_path = PathBuf::from(backup_path);
+
+    log::error!("Couldn't load {}: {:?}", path.display(), error);
+
+    match std::fs::rename(path, &backup_path) {
+        Ok(()) => format!(
+            "Your save couldn't be read and was backed up to {}.",
+            backup_path.display()
+        ),
+        Err(rename_error) => {
+            log::error!("Couldn't back up {}: {}", path.display(), rename_error);
+
+            "Your save couldn't be read (and couldn't be backed up).".to_string()
+        }
+    }
+}
+
+/// Loads the save data from the data directory.
+/// Imports the legacy high scores the first time, and backs up a save that can't be read instead of silently resetting it.
+pub fn load_save_data(data_dir: &Path) -> SaveDataResource {
+    let path = data_dir.join(SAVE_FILE);
+    let legacy_path = Path::new(LEGACY_HIGH_SCORES_FILE);
+
+    let mut load_report = None;
+
+    let data = if path.exists() {
+        match load_file::<SaveData>(&path.to_string_lossy(), SAVE_DATA_VERSION) {
+            Ok(save) => migrate(save),
+            Err(error) => {
+                load_report = Some(back_up_unreadable_file(&path, error));
+                SaveData::default()
+            }
+        }
+    } else if legacy_path.exists() {
+        match load_file::<LegacyHighScores>(LEGACY_HIGH_SCORES_FILE, 0) {
+            Ok(legacy) => {
+                log::info!("Importing high scores from {}", LEGACY_HIGH_SCORES_FILE);
+                migrate(migrate_legacy_high_scores(legacy))
+            }
+            Err(error) => {
+                load_report = Some(back_up_unreadable_file(legacy_path, error));
+                SaveData::default()
+            }
+        }
+    } else {
+        SaveData::default()
+    };
+
+    let resource = SaveDataResource {
+        data: SaveData {
+            schema_version: SAVE_DATA_VERSION,
+            ..data
+        },
+        path,
+        load_report,
+    };
+
+    // Write the save right away so an import only happens once.
+    if !resource.path.exists() {
+        resource.save();
+    }
+
+    resource
+}
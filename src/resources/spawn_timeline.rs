@@ -0,0 +1,548 @@
+use amethyst::assets::{Asset, Handle, ProcessingState};
+use amethyst::core::ecs::VecStorage;
+use amethyst::Error;
+use rand::Rng;
+use serde::Deserialize;
+use std::f32::consts::PI;
+
+use crate::resources::difficulty::scale;
+use crate::resources::high_scores::highscores_keys::{COVID, HORNETS, HORNETS_ENDLESS, WILDFIRES};
+use crate::systems::distance_between_points;
+use crate::timers::IntervalTimer;
+
+/// Keys of all spawn timelines that get loaded from `assets/spawns/{key}.ron`.
+pub const SPAWN_TIMELINE_KEYS: &[&str] = &[WILDFIRES, HORNETS, HORNETS_ENDLESS, COVID];
+
+/// How many times a random spawn position is rerolled when it's too close to the player.
+const MAX_SPAWN_ATTEMPTS: u32 = 10;
+
+/// Everything a spawn timeline can spawn. The level's system decides what each one looks like.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum EnemyType {
+    Fire,
+    Bee,
+    CovidCell,
+    SuperSpreader,
+    /// Not an enemy, but spawned the same way.
+    HealthPack,
+}
+
+/// How many enemies a wave spawns at once.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpawnCount {
+    pub min: u32,
+    /// The most enemies spawned at once at the start of the level.
+    pub max: u32,
+    /// Every this many seconds, one more enemy can spawn at once. The max never changes if not set.
+    #[serde(default)]
+    pub ramp_seconds: Option<f32>,
+}
+
+impl SpawnCount {
+    /// The most enemies spawned at once after the level has been running for some seconds.
+    pub fn max_at(&self, seconds_elapsed: f32) -> u32 {
+        let ramp = self
+            .ramp_seconds
+            .map_or(0, |ramp_seconds| (seconds_elapsed / ramp_seconds) as u32);
+
+        self.max.max(self.min) + ramp
+    }
+
+    /// Rolls how many enemies to spawn (from min to max, both included).
+    pub fn roll<R: Rng>(&self, seconds_elapsed: f32, rng: &mut R) -> u32 {
+        rng.gen_range(self.min, self.max_at(seconds_elapsed) + 1)
+    }
+}
+
+/// The area enemies spawn in, in playfield units from the bottom left of the playfield.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpawnRegion {
+    pub min_x: f32,
+    pub max_x: f32,
+    pub min_y: f32,
+    pub max_y: f32,
+}
+
+impl SpawnRegion {
+    fn center(&self) -> (f32, f32) {
+        (
+            (self.min_x + self.max_x) * 0.5,
+            (self.min_y + self.max_y) * 0.5,
+        )
+    }
+
+    /// Checks the region isn't inside out. A region can be a line or a point.
+    fn validate(&self) -> Result<(), String> {
+        if self.min_x > self.max_x || self.min_y > self.max_y {
+            return Err(format!(
+                "Spawn region {:?} has a min bigger than its max",
+                self
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// A random number from min to max. Also works when they're the same, like for a region that's a line.
+fn random_between<R: Rng>(rng: &mut R, min: f32, max: f32) -> f32 {
+    if min < max {
+        rng.gen_range(min, max)
+    } else {
+        min
+    }
+}
+
+/// Where in the region a wave's enemies spawn.
+#[derive(Clone, Debug, Deserialize)]
+pub enum SpawnPattern {
+    /// Anywhere in the region, heading in a random direction.
+    Random,
+    /// On a random edge of the region, heading into it.
+    Edges,
+    /// Evenly spaced in a ring around the middle of the region, heading outwards.
+    Ring { radius: f32 },
+}
+
+/// Where to spawn an enemy and which way it should head (in radians).
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnPoint {
+    pub x: f32,
+    pub y: f32,
+    pub heading: f32,
+}
+
+impl SpawnPattern {
+    /// Picks where to spawn one enemy of a wave. `index` is the enemy's position in the wave.
+    fn spawn_point<R: Rng>(
+        &self,
+        region: &SpawnRegion,
+        index: u32,
+        count: u32,
+        ring_offset: f32,
+        rng: &mut R,
+    ) -> SpawnPoint {
+        match self {
+            SpawnPattern::Random => SpawnPoint {
+                x: random_between(rng, region.min_x, region.max_x),
+                y: random_between(rng, region.min_y, region.max_y),
+                heading: rng.gen_range(0., 2. * PI),
+            },
+            SpawnPattern::Edges => {
+                let x = random_between(rng, region.min_x, region.max_x);
+                let y = random_between(rng, region.min_y, region.max_y);
+
+                match rng.gen_range(0, 4) {
+                    0 => SpawnPoint {
+                        x,
+                        y: region.min_y,
+                        heading: PI * 0.5,
+                    },
+                    1 => SpawnPoint {
+                        x,
+                        y: region.max_y,
+                        heading: PI * 1.5,
+                    },
+                    2 => SpawnPoint {
+                        x: region.min_x,
+                        y,
+                        heading: 0.,
+                    },
+                    _ => SpawnPoint {
+                        x: region.max_x,
+                        y,
+                        heading: PI,
+                    },
+                }
+            }
+            SpawnPattern::Ring { radius } => {
+                let (center_x, center_y) = region.center();
+                let heading = ring_offset + index as f32 * 2. * PI / count as f32;
+
+                SpawnPoint {
+                    x: center_x + heading.cos() * radius,
+                    y: center_y + heading.sin() * radius,
+                    heading,
+                }
+            }
+        }
+    }
+}
+
+/// A group of enemies spawned once or over and over.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpawnWave {
+    pub enemy: EnemyType,
+    /// Seconds into the level when the wave first spawns.
+    #[serde(default)]
+    pub start_seconds: f32,
+    /// Spawns the wave again every this many seconds. The wave only spawns once if not set.
+    #[serde(default)]
+    pub repeat_seconds: Option<f32>,
+    /// Seconds into the level when the wave stops repeating. Repeats until the level ends if not set.
+    #[serde(default)]
+    pub end_seconds: Option<f32>,
+    pub count: SpawnCount,
+    pub region: SpawnRegion,
+    pub pattern: SpawnPattern,
+    /// Enemies never spawn closer than this to the player.
+    #[serde(default)]
+    pub player_clearance: f32,
+}
+
+impl SpawnWave {
+    /// A timer that goes off every time the wave spawns.
+    pub fn timer(&self) -> IntervalTimer {
+        IntervalTimer::starting_in(self.start_seconds, self.repeat_seconds)
+    }
+
+    /// Whether the wave has stopped repeating.
+    pub fn is_over(&self, seconds_elapsed: f32) -> bool {
+        self.end_seconds
+            .map_or(false, |end_seconds| seconds_elapsed > end_seconds)
+    }
+
+    /// Rolls where each enemy of the wave spawns.
+    /// Points too close to the player are rerolled a few times, then dropped.
+    pub fn spawn_points<R: Rng>(
+        &self,
+        seconds_elapsed: f32,
+        player_position: Option<(f32, f32)>,
+        rng: &mut R,
+    ) -> Vec<SpawnPoint> {
+        let count = self.count.roll(seconds_elapsed, rng);
+        let ring_offset = rng.gen_range(0., 2. * PI);
+
+        let is_clear = |point: &SpawnPoint| {
+            player_position.map_or(true, |(player_x, player_y)| {
+                distance_between_points(point.x, point.y, player_x, player_y)
+                    > self.player_clearance
+            })
+        };
+
+        (0..count)
+            .filter_map(|index| {
+                (0..MAX_SPAWN_ATTEMPTS)
+                    .map(|_| {
+                        self.pattern
+                            .spawn_point(&self.region, index, count, ring_offset, rng)
+                    })
+                    .find(|point| is_clear(point))
+            })
+            .collect()
+    }
+}
+
+/// How a difficulty scales a spawn timeline. 1.0 leaves everything as is.
+pub struct SpawnModifiers {
+    /// Multiplies the time between repeating waves (lower spawns faster).
+    pub interval: f32,
+    /// Multiplies the most enemies spawned at once.
+    pub count: f32,
+    /// Multiplies how fast the amount of enemies spawned at once ramps up.
+    pub ramp: f32,
+}
+
+impl Default for SpawnModifiers {
+    fn default() -> Self {
+        SpawnModifiers {
+            interval: 1.0,
+            count: 1.0,
+            ramp: 1.0,
+        }
+    }
+}
+
+/// A level's spawn schedule. Loaded from `assets/spawns/{key}.ron`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SpawnTimeline {
+    pub waves: Vec<SpawnWave>,
+}
+
+impl Asset for SpawnTimeline {
+    const NAME: &'static str = "survive2020::SpawnTimeline";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<SpawnTimeline>>;
+}
+
+/// Timelines with a broken wave fail to load instead of crashing the level when the wave spawns.
+impl From<SpawnTimeline> for Result<ProcessingState<SpawnTimeline>, Error> {
+    fn from(timeline: SpawnTimeline) -> Result<ProcessingState<SpawnTimeline>, Error> {
+        for wave in timeline.waves.iter() {
+            wave.region.validate().map_err(Error::from_string)?;
+        }
+
+        Ok(ProcessingState::Loaded(timeline))
+    }
+}
+
+impl SpawnTimeline {
+    /// Scales every wave of the timeline for a difficulty.
+    pub fn scaled(mut self, modifiers: &SpawnModifiers) -> Self {
+        for wave in self.waves.iter_mut() {
+            wave.repeat_seconds = wave
+                .repeat_seconds
+                .map(|repeat_seconds| repeat_seconds * modifiers.interval);
+
+            // Rounding can take the max below the min (like a max of 2 on Easy).
+            wave.count.max =
+                (scale(wave.count.max as u64, modifiers.count) as u32).max(wave.count.min);
+
+            wave.count.ramp_seconds = wave
+                .count
+                .ramp_seconds
+                .map(|ramp_seconds| ramp_seconds / modifiers.ramp);
+        }
+
+        self
+    }
+}
+
+/// The spawn timeline of the level being played.
+#[derive(Default)]
+pub struct SpawnTimelineResource {
+    pub waves: Vec<SpawnWave>,
+    /// A timer for each wave, going off whenever the wave spawns.
+    pub timers: Vec<IntervalTimer>,
+}
+
+impl SpawnTimelineResource {
+    pub fn new(timeline: SpawnTimeline) -> Self {
+        let timers = timeline.waves.iter().map(SpawnWave::timer).collect();
+
+        SpawnTimelineResource {
+            waves: timeline.waves,
+            timers,
+        }
+    }
+}
+
+/// Enemies the `SpawnerSystem` wants spawned this frame.
+/// Each level's system takes the enemy types it knows how to create.
+#[derive(Default)]
+pub struct SpawnRequestsResource {
+    pub requests: Vec<(EnemyType, SpawnPoint)>,
+}
+
+impl SpawnRequestsResource {
+    /// Removes and returns the spawn points requested for an enemy type.
+    pub fn take(&mut self, enemy: EnemyType) -> Vec<SpawnPoint> {
+        let mut taken = Vec::new();
+
+        self.requests.retain(|(requested_enemy, point)| {
+            if *requested_enemy == enemy {
+                taken.push(*point);
+                false
+            } else {
+                true
+            }
+        });
+
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::difficulty::DIFFICULTIES;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const SEED: u64 = 2020;
+
+    fn count(min: u32, max: u32, ramp_seconds: Option<f32>) -> SpawnCount {
+        SpawnCount {
+            min,
+            max,
+            ramp_seconds,
+        }
+    }
+
+    fn region(min_x: f32, max_x: f32, min_y: f32, max_y: f32) -> SpawnRegion {
+        SpawnRegion {
+            min_x,
+            max_x,
+            min_y,
+            max_y,
+        }
+    }
+
+    fn wave(count: SpawnCount, region: SpawnRegion, player_clearance: f32) -> SpawnWave {
+        SpawnWave {
+            enemy: EnemyType::Fire,
+            start_seconds: 0.,
+            repeat_seconds: Some(1.),
+            end_seconds: None,
+            count,
+            region,
+            pattern: SpawnPattern::Random,
+            player_clearance,
+        }
+    }
+
+    fn loads(timeline: SpawnTimeline) -> bool {
+        Result::<ProcessingState<SpawnTimeline>, Error>::from(timeline).is_ok()
+    }
+
+    #[test]
+    fn max_at_ramps_up_one_every_ramp_seconds() {
+        let ramping = count(1, 5, Some(30.));
+
+        assert_eq!(ramping.max_at(0.), 5);
+        assert_eq!(ramping.max_at(29.9), 5);
+        assert_eq!(ramping.max_at(30.), 6);
+        assert_eq!(ramping.max_at(95.), 8);
+
+        assert_eq!(count(1, 5, None).max_at(1000.), 5);
+    }
+
+    #[test]
+    fn max_at_is_never_below_the_min() {
+        assert_eq!(count(3, 1, None).max_at(0.), 3);
+    }
+
+    #[test]
+    fn roll_stays_between_min_and_max() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+
+        for spawn_count in [count(1, 5, None), count(2, 2, None), count(4, 1, None)].iter() {
+            let rolls: Vec<u32> = (0..500).map(|_| spawn_count.roll(0., &mut rng)).collect();
+            let max = spawn_count.max_at(0.);
+
+            assert!(
+                rolls
+                    .iter()
+                    .all(|roll| *roll >= spawn_count.min && *roll <= max),
+                "{:?}",
+                spawn_count
+            );
+            assert!(rolls.contains(&spawn_count.min), "{:?}", spawn_count);
+            assert!(rolls.contains(&max), "{:?}", spawn_count);
+        }
+    }
+
+    #[test]
+    fn spawn_points_keep_clear_of_the_player() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let clear_wave = wave(count(5, 5, None), region(0., 600., 0., 600.), 150.);
+
+        for _ in 0..100 {
+            for point in clear_wave.spawn_points(0., Some((300., 300.)), &mut rng) {
+                assert!(distance_between_points(point.x, point.y, 300., 300.) > 150.);
+            }
+        }
+    }
+
+    #[test]
+    fn spawn_points_are_rerolled_before_being_dropped() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        // Half of the region is too close to the player, so most points need a reroll but almost none get dropped.
+        let half_blocked_wave = wave(count(10, 10, None), region(0., 100., 0., 10.), 50.);
+
+        let spawned: usize = (0..100)
+            .map(|_| {
+                half_blocked_wave
+                    .spawn_points(0., Some((0., 5.)), &mut rng)
+                    .len()
+            })
+            .sum();
+
+        assert!(spawned > 990, "only {} of 1000 points spawned", spawned);
+    }
+
+    #[test]
+    fn spawn_points_are_dropped_when_the_player_blocks_the_region() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let blocked_wave = wave(count(5, 5, None), region(250., 350., 250., 350.), 200.);
+
+        assert!(blocked_wave
+            .spawn_points(0., Some((300., 300.)), &mut rng)
+            .is_empty());
+        assert_eq!(blocked_wave.spawn_points(0., None, &mut rng).len(), 5);
+    }
+
+    #[test]
+    fn line_regions_spawn_on_the_line() {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let line_wave = wave(count(5, 5, None), region(100., 100., 0., 600.), 0.);
+
+        for point in line_wave.spawn_points(0., None, &mut rng) {
+            assert_eq!(point.x, 100.);
+        }
+    }
+
+    #[test]
+    fn inside_out_regions_fail_to_load() {
+        let line = SpawnTimeline {
+            waves: vec![wave(count(1, 1, None), region(100., 100., 0., 600.), 0.)],
+        };
+        let inside_out = SpawnTimeline {
+            waves: vec![wave(count(1, 1, None), region(600., 0., 0., 600.), 0.)],
+        };
+
+        assert!(loads(line));
+        assert!(!loads(inside_out));
+    }
+
+    #[test]
+    fn scaled_applies_each_difficultys_modifiers() {
+        for difficulty in DIFFICULTIES {
+            let difficulty_modifiers = difficulty.modifiers();
+            let modifiers = SpawnModifiers {
+                interval: difficulty_modifiers.fire_interval,
+                count: difficulty_modifiers.bees_per_spawn,
+                ramp: difficulty_modifiers.spreader_ramp,
+            };
+
+            let timeline = SpawnTimeline {
+                waves: vec![
+                    wave(count(1, 5, Some(30.)), region(0., 600., 0., 600.), 0.),
+                    wave(count(2, 2, None), region(0., 600., 0., 600.), 0.),
+                ],
+            }
+            .scaled(&modifiers);
+
+            let ramping = &timeline.waves[0];
+            assert_eq!(ramping.repeat_seconds, Some(modifiers.interval));
+            assert_eq!(ramping.count.max, scale(5, modifiers.count) as u32);
+            assert_eq!(ramping.count.ramp_seconds, Some(30. / modifiers.ramp));
+
+            let fixed = &timeline.waves[1];
+            assert!(
+                fixed.count.max >= fixed.count.min,
+                "{:?}: {:?}",
+                difficulty,
+                fixed.count
+            );
+        }
+    }
+
+    #[test]
+    fn take_only_removes_the_requested_enemy_type() {
+        let point = |x: f32| SpawnPoint {
+            x,
+            y: 0.,
+            heading: 0.,
+        };
+
+        let mut requests = SpawnRequestsResource {
+            requests: vec![
+                (EnemyType::Bee, point(1.)),
+                (EnemyType::Fire, point(2.)),
+                (EnemyType::Bee, point(3.)),
+            ],
+        };
+
+        let bees: Vec<f32> = requests
+            .take(EnemyType::Bee)
+            .iter()
+            .map(|point| point.x)
+            .collect();
+
+        assert_eq!(bees, vec![1., 3.]);
+        assert!(requests.take(EnemyType::Bee).is_empty());
+        assert_eq!(requests.requests.len(), 1);
+        assert_eq!(requests.requests[0].0, EnemyType::Fire);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::resources::difficulty::{score_key, DIFFICULTIES};
+use crate::resources::high_scores::highscores_keys::{
+    COVID, HORNETS, HORNETS_ENDLESS, LOCUSTS, TOILET_PAPER, WILDFIRES, ZOOM,
+};
+use crate::resources::levels::get_level_definition;
+use crate::resources::save_data::{SaveData, SaveDataResource};
+
+use amethyst::core::ecs::{World, WorldExt};
+
+/// The levels that start out locked, each with the level that unlocks it.
+/// A level is unlocked by reaching the unlocking level's `pass_score` (on any difficulty).
+pub const LEVEL_UNLOCKS: &[(&str, &str)] = &[
+    (HORNETS, WILDFIRES),
+    (COVID, HORNETS),
+    (HORNETS_ENDLESS, HORNETS),
+    (LOCUSTS, COVID),
+    (TOILET_PAPER, LOCUSTS),
+    (ZOOM, TOILET_PAPER),
+];
+
+/// The level that has to be passed to unlock a level. None if the level is never locked.
+pub fn unlocking_level(level_key: &str) -> Option<&'static str> {
+    LEVEL_UNLOCKS
+        .iter()
+        .find(|(locked, _)| *locked == level_key)
+        .map(|(_, unlocking)| *unlocking)
+}
+
+/// Whether a level can be played.
+pub fn is_unlocked(save: &SaveData, level_key: &str) -> bool {
+    unlocking_level(level_key).is_none() || save.unlocks.iter().any(|key| key == level_key)
+}
+
+/// The best score on a level across every difficulty.
+fn best_score(save: &SaveData, level_key: &str) -> u64 {
+    DIFFICULTIES
+        .iter()
+        .map(|difficulty| save.get_score(&score_key(level_key, *difficulty)))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Unlocks every locked level whose unlocking level has a high score of at least its pass score, and saves them.
+/// The level definitions have to be loaded. Returns the keys of the newly unlocked levels.
+pub fn update_unlocks(world: &mut World) -> Vec<String> {
+    let mut newly_unlocked = Vec::new();
+
+    for (locked, unlocking) in LEVEL_UNLOCKS {
+        let pass_score = get_level_definition(world, unlocking)
+            .pass_score
+            .unwrap_or(0);
+
+        let save = world.read_resource::<SaveDataResource>();
+
+        if !is_unlocked(&save.data, locked) && best_score(&save.data, unlocking) >= pass_score {
+            newly_unlocked.push(locked.to_string());
+        }
+    }
+
+    if !newly_unlocked.is_empty() {
+        let mut save = world.write_resource::<SaveDataResource>();
+
+        save.data.unlocks.extend(newly_unlocked.iter().cloned());
+        save.save();
+    }
+
+    newly_unlocked
+}
@@ -0,0 +1,152 @@
+use crate::resources::difficulty::{score_key, Difficulty, DifficultyResource};
+use crate::resources::high_scores::highscores_keys::{
+    CAMPAIGN, COVID, HORNETS, LOCUSTS, TOILET_PAPER, WILDFIRES, ZOOM,
+};
+use crate::resources::levels::get_level_definition;
+use crate::resources::save_data::{CampaignProgress, SaveDataResource};
+use crate::states::start_level;
+
+use amethyst::prelude::*;
+
+/// The levels of the campaign, in the order they're played.
+pub const CAMPAIGN_LEVELS: &[&str] = &[WILDFIRES, HORNETS, COVID, LOCUSTS, TOILET_PAPER, ZOOM];
+
+/// Whether the levels being played are part of the campaign.
+/// Set when a campaign level is started from the main menu and cleared when the main menu opens again.
+#[derive(Default)]
+pub struct CampaignResource {
+    pub active: bool,
+}
+
+/// How a campaign level went. Shown on the results screen.
+pub struct CampaignResults {
+    /// Whether the level's score reached its pass score.
+    pub passed: bool,
+    pub pass_score: u64,
+    /// The combined score of the passed levels (including this one if it passed).
+    pub total_score: u64,
+    /// The level to play next. None once the campaign is complete.
+    pub next_level: Option<String>,
+    /// The campaign high score (for the campaign's difficulty) before this level.
+    pub previous_high_score: u64,
+}
+
+impl CampaignResults {
+    pub fn is_complete(&self) -> bool {
+        self.passed && self.next_level.is_none()
+    }
+
+    pub fn is_new_high_score(&self) -> bool {
+        self.is_complete() && self.total_score > self.previous_high_score
+    }
+}
+
+/// Starts the level the saved campaign is up to, on the difficulty the campaign was started on.
+fn play_campaign(world: &mut World, progress: &CampaignProgress) -> SimpleTrans {
+    world.write_resource::<DifficultyResource>().difficulty =
+        Difficulty::from_name(&progress.difficulty).unwrap_or_default();
+    world.write_resource::<CampaignResource>().active = true;
+
+    start_level(CAMPAIGN_LEVELS[progress.level_index as usize])
+}
+
+/// Starts a new campaign on the selected difficulty (replacing any saved progress).
+pub fn start_new_campaign(world: &mut World) -> SimpleTrans {
+    let progress = CampaignProgress {
+        level_index: 0,
+        score: 0,
+        difficulty: world
+            .read_resource::<DifficultyResource>()
+            .difficulty
+            .name()
+            .to_string(),
+    };
+
+    {
+        let mut save = world.write_resource::<SaveDataResource>();
+
+        save.data.campaign = Some(progress.clone());
+        save.save();
+    }
+
+    play_campaign(world, &progress)
+}
+
+/// Resumes the saved campaign, or starts a new one if there isn't one.
+pub fn continue_campaign(world: &mut World) -> SimpleTrans {
+    let progress = world
+        .read_resource::<SaveDataResource>()
+        .data
+        .campaign
+        .clone();
+
+    match progress {
+        Some(progress) if (progress.level_index as usize) < CAMPAIGN_LEVELS.len() => {
+            play_campaign(world, &progress)
+        }
+        _ => start_new_campaign(world),
+    }
+}
+
+/// Records a finished level in the campaign (if it's being played as part of one).
+/// Passing moves the campaign on to the next level and adds the level's score to the campaign score.
+/// Finishing the last level saves the campaign high score and clears the saved progress.
+pub fn record_campaign_level(
+    world: &mut World,
+    level_key: &str,
+    score: u64,
+) -> Option<CampaignResults> {
+    if !world.read_resource::<CampaignResource>().active {
+        return None;
+    }
+
+    let pass_score = get_level_definition(world, level_key)
+        .pass_score
+        .unwrap_or(0);
+
+    let mut save = world.write_resource::<SaveDataResource>();
+
+    let mut progress = save.data.campaign.clone()?;
+
+    // The level has to be the one the campaign is up to.
+    if CAMPAIGN_LEVELS.get(progress.level_index as usize) != Some(&level_key) {
+        return None;
+    }
+
+    let campaign_key = score_key(
+        CAMPAIGN,
+        Difficulty::from_name(&progress.difficulty).unwrap_or_default(),
+    );
+    let previous_high_score = save.data.get_score(&campaign_key);
+
+    let passed = score >= pass_score;
+
+    if passed {
+        progress.score += score;
+        progress.level_index += 1;
+    }
+
+    let next_level = CAMPAIGN_LEVELS
+        .get(progress.level_index as usize)
+        .map(|key| key.to_string());
+
+    if passed && next_level.is_none() {
+        if progress.score > previous_high_score {
+            save.data.scores.insert(campaign_key, progress.score);
+        }
+
+        save.data.campaign = None;
+    } else {
+        save.data.campaign = Some(progress.clone());
+    }
+
+    save.save();
+
+    Some(CampaignResults {
+        passed,
+        pass_score,
+        total_score: progress.score,
+        next_level,
+        previous_high_score,
+    })
+}
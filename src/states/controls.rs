@@ -0,0 +1,371 @@
+use crate::input::{
+    button_name, control_buttons, rebind_control, save_user_bindings, DefaultBindingsResource,
+    TextEntryActiveResource, CONTROLS,
+};
+use crate::resources::data_dir::DataDirResource;
+use crate::states::main_menu::MainMenuState;
+use crate::*;
+
+use amethyst::core::ecs::DenseVecStorage;
+use amethyst::input::{Button, InputHandler, StringBindings};
+use amethyst::ui::{Anchor, LineMode, UiButton, UiEventType, UiText, UiTransform};
+use amethyst::winit::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+/// Tags the controls menu's title and status texts so they can be deleted when it stops.
+pub struct ControlsMenuComponent;
+impl Component for ControlsMenuComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Tags the text that shows conflicts and hints in the controls menu.
+pub struct ControlsStatusTextComponent;
+impl Component for ControlsStatusTextComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Lists every control and lets the player remap it by clicking it and pressing a new key or mouse button.
+/// Changes are saved to `bindings.ron` right away.
+#[derive(Default)]
+pub struct ControlsState {
+    /// One button per entry in `CONTROLS`.
+    control_buttons: Vec<UiButton>,
+    reset_button: Option<UiButton>,
+    back_button: Option<UiButton>,
+    /// Index of the control waiting for a new button.
+    waiting_for: Option<usize>,
+    /// Set when a mouse button gets bound, so the click it causes when released doesn't start another remap.
+    swallow_click: bool,
+}
+
+/// Creates a button in the controls menu. The y position is relative to the top of the screen.
+fn create_controls_button(
+    world: &mut World,
+    text: &str,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+) -> UiButton {
+    let font = get_main_font(world);
+
+    let (_, button) = UiButtonBuilder::<(), u32>::new(text)
+        .with_font(font)
+        .with_font_size(18.0)
+        .with_position(x, y)
+        .with_size(width, height)
+        .with_anchor(Anchor::TopMiddle)
+        .with_image(create_ui_color_from_rgba(195, 130, 51, 1.0))
+        .with_hover_image(create_ui_color_from_rgba(195, 130, 51, 0.5))
+        .build_from_world(&world);
+
+    button
+}
+
+/// Creates the title and the (empty) status text.
+fn init_controls_text(world: &mut World) {
+    let font = get_main_font(world);
+
+    world
+        .create_entity()
+        .with(ControlsMenuComponent)
+        .with(UiTransform::new(
+            "controls_title".to_string(),
+            Anchor::TopMiddle,
+            Anchor::TopMiddle,
+            0.0,
+            -20.0,
+            1.0,
+            600.0,
+            50.0,
+        ))
+        .with(UiText::new(
+            font.clone(),
+            "CONTROLS".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            35.0,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build();
+
+    world
+        .create_entity()
+        .with(ControlsMenuComponent)
+        .with(ControlsStatusTextComponent)
+        .with(UiTransform::new(
+            "controls_status".to_string(),
+            Anchor::TopMiddle,
+            Anchor::TopMiddle,
+            0.0,
+            -475.0,
+            1.0,
+            600.0,
+            30.0,
+        ))
+        .with(UiText::new(
+            font,
+            "Click a control to change it.".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            18.0,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build();
+}
+
+/// Sets the text that shows conflicts and hints.
+fn set_status_text(world: &mut World, text: &str) {
+    let mut ui_texts = world.write_storage::<UiText>();
+    let status_texts = world.read_storage::<ControlsStatusTextComponent>();
+
+    for (ui_text, _) in (&mut ui_texts, &status_texts).join() {
+        ui_text.text = text.to_string();
+    }
+}
+
+/// The text on a control's button, like "Move up: W".
+fn control_text(world: &World, index: usize) -> String {
+    let (name, control) = CONTROLS[index];
+
+    let input = world.read_resource::<InputHandler<StringBindings>>();
+
+    let buttons: Vec<String> = control_buttons(&input.bindings, control)
+        .iter()
+        .map(button_name)
+        .collect();
+
+    if buttons.is_empty() {
+        format!("{}: (unbound)", name)
+    } else {
+        format!("{}: {}", name, buttons.join(" / "))
+    }
+}
+
+/// Gets the button pressed in a window event, if any.
+fn pressed_button(event: &Event) -> Option<Button> {
+    match event {
+        Event::WindowEvent {
+            event:
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(key),
+                            ..
+                        },
+                    ..
+                },
+            ..
+        } => Some(Button::Key(*key)),
+        Event::WindowEvent {
+            event:
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button,
+                    ..
+                },
+            ..
+        } => Some(Button::Mouse(*button)),
+        _ => None,
+    }
+}
+
+impl ControlsState {
+    /// Updates the text of every control's button.
+    fn refresh_control_buttons(&self, world: &mut World) {
+        for (index, button) in self.control_buttons.iter().enumerate() {
+            let text = control_text(world, index);
+
+            if let Some(ui_text) = world.write_storage::<UiText>().get_mut(button.text_entity) {
+                ui_text.text = text;
+            }
+        }
+    }
+
+    /// Binds the control being remapped to a button, or shows why it can't be.
+    fn finish_remap(&mut self, world: &mut World, index: usize, button: Button) {
+        self.waiting_for = None;
+
+        let result = {
+            let mut input = world.write_resource::<InputHandler<StringBindings>>();
+
+            let result = rebind_control(&mut input.bindings, CONTROLS[index].1, button);
+
+            if result.is_ok() {
+                save_user_bindings(
+                    &input.bindings,
+                    &world.read_resource::<DataDirResource>().path,
+                );
+            }
+
+            result
+        };
+
+        match result {
+            Ok(()) => set_status_text(world, "Saved!"),
+            Err(error) => set_status_text(world, &error),
+        }
+
+        self.refresh_control_buttons(world);
+    }
+}
+
+impl SimpleState for ControlsState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        world.register::<ControlsMenuComponent>();
+        world.register::<ControlsStatusTextComponent>();
+
+        init_controls_text(world);
+
+        for index in 0..CONTROLS.len() {
+            let text = control_text(world, index);
+            let y = -70.0 - index as f32 * 34.0;
+
+            let button = create_controls_button(world, &text, 0.0, y, 420.0, 30.0);
+            self.control_buttons.push(button);
+        }
+
+        self.reset_button = Some(create_controls_button(
+            world,
+            "Reset to defaults",
+            -110.0,
+            -530.0,
+            200.0,
+            38.0,
+        ));
+        self.back_button = Some(create_controls_button(
+            world, "Back", 110.0, -530.0, 200.0, 38.0,
+        ));
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        world.write_resource::<TextEntryActiveResource>().active = false;
+
+        delete_all_entities_with_component::<ControlsMenuComponent>(world);
+
+        for button in self.control_buttons.drain(..) {
+            delete_ui_button(world, &button);
+        }
+
+        for button in [&self.reset_button, &self.back_button]
+            .iter()
+            .copied()
+            .flatten()
+        {
+            delete_ui_button(world, button);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let world = data.world;
+
+        match event {
+            StateEvent::Window(event) => {
+                let pressed = pressed_button(&event);
+
+                if let Some(Button::Mouse(_)) = pressed {
+                    // A new press means any click from the last bound mouse button has already happened.
+                    self.swallow_click = false;
+                }
+
+                if let Some(index) = self.waiting_for {
+                    match pressed {
+                        Some(Button::Key(VirtualKeyCode::Escape)) => {
+                            self.waiting_for = None;
+                            set_status_text(world, "Cancelled.");
+                            self.refresh_control_buttons(world);
+                        }
+                        Some(button) => {
+                            if let Button::Mouse(_) = button {
+                                self.swallow_click = true;
+                            }
+
+                            self.finish_remap(world, index, button);
+                        }
+                        None => {}
+                    }
+
+                    Trans::None
+                } else if let Some(Button::Key(VirtualKeyCode::Escape)) = pressed {
+                    Trans::Replace(Box::new(MainMenuState::default()))
+                } else {
+                    Trans::None
+                }
+            }
+            StateEvent::Ui(ui_event) => {
+                if ui_event.event_type != UiEventType::Click || self.waiting_for.is_some() {
+                    return Trans::None;
+                }
+
+                if self.swallow_click {
+                    self.swallow_click = false;
+                    return Trans::None;
+                }
+
+                let is_target = |button: &Option<UiButton>| {
+                    button.as_ref().unwrap().image_entity == ui_event.target
+                };
+
+                if is_target(&self.back_button) {
+                    return Trans::Replace(Box::new(MainMenuState::default()));
+                }
+
+                if is_target(&self.reset_button) {
+                    {
+                        let defaults = world.read_resource::<DefaultBindingsResource>();
+                        let mut input = world.write_resource::<InputHandler<StringBindings>>();
+
+                        input.bindings = defaults.bindings.clone();
+                        save_user_bindings(
+                            &input.bindings,
+                            &world.read_resource::<DataDirResource>().path,
+                        );
+                    }
+
+                    set_status_text(world, "Controls reset to defaults.");
+                    self.refresh_control_buttons(world);
+
+                    return Trans::None;
+                }
+
+                if let Some(index) = self
+                    .control_buttons
+                    .iter()
+                    .position(|button| button.image_entity == ui_event.target)
+                {
+                    self.waiting_for = Some(index);
+
+                    let text = format!("{}: press a key...", CONTROLS[index].0);
+                    if let Some(ui_text) = world
+                        .write_storage::<UiText>()
+                        .get_mut(self.control_buttons[index].text_entity)
+                    {
+                        ui_text.text = text;
+                    }
+
+                    set_status_text(world, "Press a key or mouse button (Escape to cancel).");
+                }
+
+                Trans::None
+            }
+            _ => Trans::None,
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        // Pressing the mute hotkey to remap a control shouldn't mute the game.
+        data.world
+            .write_resource::<TextEntryActiveResource>()
+            .active = self.waiting_for.is_some();
+
+        Trans::None
+    }
+}
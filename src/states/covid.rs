@@ -2,23 +2,25 @@ use crate::*;
 
 use crate::resources::high_scores::highscores_keys::COVID;
 
-use crate::resources::high_scores::{update_high_score_if_greater, CurrentLevelScoreResource};
-use crate::states::main_menu::MainMenuState;
+use crate::resources::high_scores::CurrentLevelScoreResource;
+use crate::states::results::finish_level;
 use crate::states::{
-    create_optional_systems_dispatcher, init_level_background, init_level_title,
-    return_to_main_menu_on_escape, run_systems, LevelComponent, LevelSecondsResource,
+    create_optional_systems_dispatcher, hud_text_layout, init_level_from_definition,
+    init_spawn_timeline, pause_on_escape_or_focus_lost, playfield_ui_transform, run_systems,
+    LevelComponent, LevelSecondsResource,
 };
 
-use crate::resources::abilities::{
-    AbilitiesResource, Ability, AbilityInfo, AbilityState, AbilityType,
-};
-use crate::systems::ability_bar::init_abilities_bar;
+use crate::resources::difficulty::{scale, DifficultyResource};
+use crate::resources::levels::get_level_definition;
+use crate::resources::rng::init_level_rng;
+use crate::resources::spawn_timeline::SpawnModifiers;
+use crate::systems::boss::BossSystem;
 use crate::systems::covid::CovidSystem;
+use crate::systems::mega_spreader::MegaSpreaderSystem;
+use crate::systems::spawner::SpawnerSystem;
 use amethyst::core::ecs::DenseVecStorage;
 use amethyst::shred::Dispatcher;
-use amethyst::ui::{Anchor, LineMode, UiText, UiTransform};
-
-pub const HEALTH_POOL: u64 = 100;
+use amethyst::ui::{Anchor, LineMode, UiText};
 
 /// Tags a component as the covid state text.
 pub struct CovidStateTextComponent;
@@ -29,16 +31,8 @@ impl Component for CovidStateTextComponent {
 fn init_covid_state_text(world: &mut World, health_pool: u64) {
     let font = get_main_font(world);
 
-    let transform = UiTransform::new(
-        "covid_state".to_string(),
-        Anchor::TopMiddle,
-        Anchor::TopMiddle,
-        0.0,
-        -55.0,
-        0.0,
-        600.0,
-        50.0,
-    );
+    let layout = hud_text_layout();
+    let transform = playfield_ui_transform(world, "covid_state", &layout);
     let ui_text = UiText::new(
         font,
         format!("{} HP / {} MAX HEALTH", health_pool, health_pool),
@@ -52,6 +46,7 @@ fn init_covid_state_text(world: &mut World, health_pool: u64) {
         .create_entity()
         .with(CovidStateTextComponent)
         .with(LevelComponent)
+        .with(layout)
         .with(transform)
         .with(ui_text)
         .build();
@@ -67,16 +62,45 @@ fn update_covid_state(world: &mut World, current_health: u64, health_pool: u64)
 }
 
 /// A resource for storing some level state for the COVID level.
+#[derive(Default)]
 pub struct CovidStateResource {
     pub current_health: u64,
+    /// The max health of the player.
+    pub health_pool: u64,
+    /// How much health the player has lost over the whole level.
+    pub hp_lost: u64,
+    /// Seconds into the level when the Mega Spreader appears. None if it never does.
+    pub boss_seconds: Option<f32>,
+    /// The Mega Spreader's health (scaled by the difficulty).
+    pub boss_health: u64,
+    pub bosses_defeated: u64,
 }
 
-impl Default for CovidStateResource {
-    fn default() -> Self {
+impl CovidStateResource {
+    pub fn new(health_pool: u64) -> Self {
         CovidStateResource {
-            current_health: HEALTH_POOL,
+            current_health: health_pool,
+            health_pool,
+            hp_lost: 0,
+            boss_seconds: None,
+            boss_health: 0,
+            bosses_defeated: 0,
         }
     }
+
+    /// Counts a defeated boss. Beating it heals the player back to full health.
+    pub fn defeat_boss(&mut self) {
+        self.bosses_defeated += 1;
+        self.current_health = self.health_pool;
+    }
+
+    /// Lowers the player's health and keeps track of how much was lost.
+    pub fn take_damage(&mut self, damage: u64) {
+        let new_health = self.current_health.saturating_sub(damage);
+
+        self.hp_lost += self.current_health - new_health;
+        self.current_health = new_health;
+    }
 }
 
 #[derive(Default)]
@@ -88,49 +112,58 @@ impl<'a, 'b> SimpleState for CovidState<'a, 'b> {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
 
-        init_covid_state_text(world, HEALTH_POOL);
+        let definition = get_level_definition(world, COVID);
+
+        let modifiers = world
+            .read_resource::<DifficultyResource>()
+            .difficulty
+            .modifiers();
 
-        init_level_background(world, "covid_background.png");
+        let health_pool = scale(
+            definition
+                .health_pool
+                .expect("The COVID-19 level definition is missing health_pool!"),
+            modifiers.health_pool,
+        );
+
+        init_covid_state_text(world, health_pool);
+
+        init_level_from_definition(world, &definition);
 
-        init_level_title(world, "covid_title.png");
+        init_level_rng(world, &definition);
 
         world.insert(CurrentLevelScoreResource::default());
         world.insert(LevelSecondsResource::default());
 
         // Init the resource storing data about the player's progress on the level
-        world.insert(CovidStateResource::default());
+        let mut state = CovidStateResource::new(health_pool);
+
+        state.boss_seconds = definition.boss_seconds;
+        if state.boss_seconds.is_some() {
+            state.boss_health = scale(
+                definition
+                    .boss_health
+                    .expect("The COVID-19 level definition has boss_seconds but no boss_health!"),
+                modifiers.boss_health,
+            );
+        }
 
-        let mask_sprite = load_sprite(world, "mask_ability.png", 0);
-        let spray_bottle = load_sprite(world, "spray_bottle_ability.png", 0);
+        world.insert(state);
 
-        init_abilities_bar(
+        init_spawn_timeline(
             world,
-            AbilitiesResource::new(vec![
-                Ability {
-                    info: AbilityInfo {
-                        ability_type: AbilityType::Mask,
-                        seconds_to_charge: 17,
-                        duration: Some(5),
-                        icon: mask_sprite,
-                        max_uses: None,
-                    },
-                    current_state: AbilityState::default(),
-                },
-                Ability {
-                    info: AbilityInfo {
-                        ability_type: AbilityType::SprayBottle,
-                        seconds_to_charge: 17,
-                        duration: Some(7),
-                        icon: spray_bottle,
-                        max_uses: None,
-                    },
-                    current_state: AbilityState::default(),
-                },
-            ]),
+            COVID,
+            SpawnModifiers {
+                ramp: modifiers.spreader_ramp,
+                ..Default::default()
+            },
         );
 
         self.dispatcher = create_optional_systems_dispatcher(world, |builder, _| {
-            builder.add(CovidSystem::default(), "covid", &[])
+            builder.add(SpawnerSystem::default(), "spawner", &[]);
+            builder.add(CovidSystem::default(), "covid", &["spawner"]);
+            builder.add(MegaSpreaderSystem::default(), "mega_spreader", &["covid"]);
+            builder.add(BossSystem::default(), "boss", &["mega_spreader"]);
         });
     }
 
@@ -138,8 +171,6 @@ impl<'a, 'b> SimpleState for CovidState<'a, 'b> {
         let world = data.world;
 
         delete_all_entities_with_component::<LevelComponent>(world);
-
-        update_high_score_if_greater(world, COVID);
     }
 
     fn handle_event(
@@ -147,23 +178,18 @@ impl<'a, 'b> SimpleState for CovidState<'a, 'b> {
         _data: StateData<'_, GameData<'_, '_>>,
         event: StateEvent,
     ) -> SimpleTrans {
-        return_to_main_menu_on_escape(event)
+        pause_on_escape_or_focus_lost(event, COVID)
     }
 
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
         let world = &mut data.world;
 
-        // Update seconds elapsed
-        let seconds_elapsed = {
-            let mut seconds = world.write_resource::<LevelSecondsResource>();
-
-            seconds.seconds_elapsed += world.read_resource::<Time>().delta_seconds();
-
-            seconds.seconds_elapsed
-        };
+        let seconds_elapsed = world
+            .read_resource::<LevelSecondsResource>()
+            .seconds_elapsed;
 
         // Update the score
-        let current_health = {
+        let (current_health, health_pool) = {
             let state = world.read_resource::<CovidStateResource>();
 
             let mut score = world.write_resource::<CurrentLevelScoreResource>();
@@ -171,15 +197,25 @@ impl<'a, 'b> SimpleState for CovidState<'a, 'b> {
             // Update the level score based on seconds elapsed.
             score.score = seconds_elapsed as u64;
 
-            state.current_health
+            (state.current_health, state.health_pool)
         };
 
         // End the level if the player has not put out enough fires
         if current_health <= 0 {
-            Trans::Replace(Box::new(MainMenuState::default()))
+            let breakdown = {
+                let state = world.read_resource::<CovidStateResource>();
+
+                vec![
+                    ("Seconds survived".to_string(), seconds_elapsed as u64),
+                    ("HP lost".to_string(), state.hp_lost),
+                    ("Mega Spreaders defeated".to_string(), state.bosses_defeated),
+                ]
+            };
+
+            finish_level(world, COVID, breakdown)
         } else {
             run_systems(world, &mut self.dispatcher);
-            update_covid_state(world, current_health, HEALTH_POOL);
+            update_covid_state(world, current_health, health_pool);
             Trans::None
         }
     }
@@ -0,0 +1,139 @@
+use crate::states::{LevelComponent, LevelSecondsResource};
+use amethyst::core::ecs::{Entities, ReadStorage, WriteStorage};
+use amethyst::core::{Time, Transform};
+use amethyst::{
+    ecs::prelude::Join,
+    ecs::{Component, DenseVecStorage, Dispatcher},
+    prelude::*,
+};
+
+/// Seconds simulated by each step of a level's systems.
+pub const FIXED_TIMESTEP_SECONDS: f32 = 1. / 60.;
+
+/// The most steps simulated in one frame. Time past that is dropped so one long hitch can't snowball.
+pub const MAX_STEPS_PER_FRAME: u32 = 8;
+
+/// The last two simulated transforms of a level entity, so it can be drawn in between them
+/// when the frame rate doesn't line up with the timestep.
+pub struct InterpolationComponent {
+    pub previous: Transform,
+    pub simulated: Transform,
+}
+impl Component for InterpolationComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+type InterpolationData<'s> = (
+    Entities<'s>,
+    ReadStorage<'s, LevelComponent>,
+    WriteStorage<'s, Transform>,
+    WriteStorage<'s, InterpolationComponent>,
+);
+
+/// Runs a level's systems once for every fixed timestep that has passed, then interpolates level
+/// transforms for rendering.
+/// Driven by `Time::delta_seconds` (rather than `State::fixed_update`, which uses real time)
+/// so replays step exactly like the recorded run.
+pub fn run_fixed_steps(world: &mut World, dispatcher: &mut Dispatcher) {
+    let frame_seconds = world.read_resource::<Time>().delta_seconds();
+
+    let steps = world
+        .write_resource::<LevelSecondsResource>()
+        .accumulate(frame_seconds);
+
+    // Systems only ever see simulated transforms, never interpolated ones.
+    restore_simulated_transforms(world);
+
+    world
+        .write_resource::<Time>()
+        .set_delta_seconds(FIXED_TIMESTEP_SECONDS);
+
+    for _ in 0..steps {
+        store_previous_transforms(world);
+
+        dispatcher.dispatch(world);
+        world.maintain();
+
+        world
+            .write_resource::<LevelSecondsResource>()
+            .advance(FIXED_TIMESTEP_SECONDS);
+    }
+
+    world
+        .write_resource::<Time>()
+        .set_delta_seconds(frame_seconds);
+
+    let alpha = world
+        .read_resource::<LevelSecondsResource>()
+        .unsimulated_seconds
+        / FIXED_TIMESTEP_SECONDS;
+
+    interpolate_transforms(world, alpha.min(1.).max(0.));
+}
+
+fn restore_simulated_transforms(world: &mut World) {
+    let (_, _, mut transforms, interpolations): InterpolationData = world.system_data();
+
+    for (transform, interpolation) in (&mut transforms, &interpolations).join() {
+        *transform = interpolation.simulated.clone();
+    }
+}
+
+fn store_previous_transforms(world: &mut World) {
+    let (entities, level_components, transforms, mut interpolations): InterpolationData =
+        world.system_data();
+
+    for (entity, transform, _) in (&entities, &transforms, &level_components).join() {
+        if let Some(interpolation) = interpolations.get_mut(entity) {
+            interpolation.previous = transform.clone();
+        } else {
+            interpolations
+                .insert(
+                    entity,
+                    InterpolationComponent {
+                        previous: transform.clone(),
+                        simulated: transform.clone(),
+                    },
+                )
+                .expect("Couldn't add interpolation to a level entity!");
+        }
+    }
+}
+
+/// Moves level transforms `alpha` of the way from their previous to their simulated step.
+fn interpolate_transforms(world: &mut World, alpha: f32) {
+    let (entities, level_components, mut transforms, mut interpolations): InterpolationData =
+        world.system_data();
+
+    for (entity, transform, _) in (&entities, &mut transforms, &level_components).join() {
+        if !interpolations.contains(entity) {
+            // Created during the last step, so there's nothing to interpolate from yet.
+            interpolations
+                .insert(
+                    entity,
+                    InterpolationComponent {
+                        previous: transform.clone(),
+                        simulated: transform.clone(),
+                    },
+                )
+                .expect("Couldn't add interpolation to a level entity!");
+
+            continue;
+        }
+
+        let interpolation = interpolations.get_mut(entity).unwrap();
+
+        interpolation.simulated = transform.clone();
+
+        let previous = &interpolation.previous;
+        let simulated = &interpolation.simulated;
+
+        transform.set_translation(previous.translation().lerp(simulated.translation(), alpha));
+        transform.set_rotation(
+            previous
+                .rotation()
+                .try_slerp(simulated.rotation(), alpha, 1.0e-6)
+                .unwrap_or(*simulated.rotation()),
+        );
+    }
+}
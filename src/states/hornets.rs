@@ -1,80 +1,217 @@
 use amethyst::ecs::Dispatcher;
 
-use crate::systems::ability_bar::init_abilities_bar;
 use crate::systems::hornets::HornetsSystemDesc;
+use crate::systems::spawner::SpawnerSystem;
 
-use crate::resources::abilities::{
-    AbilitiesResource, Ability, AbilityInfo, AbilityState, AbilityType,
-};
-use crate::resources::high_scores::highscores_keys::HORNETS;
+use crate::resources::difficulty::{scale, DifficultyResource};
+use crate::resources::high_scores::highscores_keys::{HORNETS, HORNETS_ENDLESS};
 use crate::resources::high_scores::CurrentLevelScoreResource;
+use crate::resources::levels::get_level_definition;
+use crate::resources::rng::init_level_rng;
+use crate::resources::spawn_timeline::SpawnModifiers;
+use crate::states::results::finish_level;
 use crate::states::{
-    create_optional_systems_dispatcher, init_level_background, init_level_title,
-    init_timer_and_score_text, return_to_main_menu_on_escape, run_systems,
-    update_timer_and_set_high_score, LevelComponent, LevelSecondsResource,
+    create_optional_systems_dispatcher, hud_text_layout, init_level_from_definition,
+    init_spawn_timeline, init_timer_and_score_text, pause_on_escape_or_focus_lost,
+    playfield_ui_transform, run_systems, update_timer_and_score_text, LevelComponent,
+    LevelSecondsResource,
 };
 use crate::*;
 
-pub const MAX_SECONDS: f32 = 60.0 * 2.5;
+use amethyst::core::ecs::DenseVecStorage;
+use amethyst::ui::{Anchor, LineMode, UiText};
 
+/// A resource for storing some level state for the Murder Hornets level.
 #[derive(Default)]
+pub struct HornetStateResource {
+    /// Bees tapped by the player.
+    pub bees_tapped: u64,
+    /// Bees killed by the fly swatter or bug spray.
+    pub bees_killed_by_abilities: u64,
+    /// Bees that expired without being tapped.
+    pub bees_escaped: u64,
+    /// Lives left in endless mode, where every escaped bee costs a life. None in the timed level.
+    pub lives: Option<u64>,
+}
+
+impl HornetStateResource {
+    /// Counts a bee that expired without being tapped (losing a life in endless mode).
+    pub fn bee_escaped(&mut self) {
+        self.bees_escaped += 1;
+        self.lives = self.lives.map(|lives| lives.saturating_sub(1));
+    }
+}
+
+/// Tags a component as the endless Murder Hornets state text.
+pub struct EndlessHornetsStateTextComponent;
+impl Component for EndlessHornetsStateTextComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+fn endless_hornets_state_text(lives: u64, seconds_elapsed: f32, score: u64) -> String {
+    format!(
+        "{} LIVES - {}s - Score: {}",
+        lives,
+        (seconds_elapsed * 10.0).round() / 10.0,
+        score
+    )
+}
+
+fn init_endless_hornets_state_text(world: &mut World, lives: u64) {
+    let font = get_main_font(world);
+
+    let layout = hud_text_layout();
+    let transform = playfield_ui_transform(world, "endless_hornets_state", &layout);
+    let ui_text = UiText::new(
+        font,
+        endless_hornets_state_text(lives, 0.0, 0),
+        [1.0, 1.0, 1.0, 1.0],
+        25.0,
+        LineMode::Single,
+        Anchor::Middle,
+    );
+
+    world
+        .create_entity()
+        .with(EndlessHornetsStateTextComponent)
+        .with(LevelComponent)
+        .with(layout)
+        .with(transform)
+        .with(ui_text)
+        .build();
+}
+
+fn update_endless_hornets_state(world: &mut World, lives: u64, seconds_elapsed: f32, score: u64) {
+    let mut ui_texts = world.write_storage::<UiText>();
+    let state_text_components = world.read_storage::<EndlessHornetsStateTextComponent>();
+
+    for (ui_text, _) in (&mut ui_texts, &state_text_components).join() {
+        ui_text.text = endless_hornets_state_text(lives, seconds_elapsed, score);
+    }
+}
+
+/// The Murder Hornets level. Either timed, or endless where it only ends once the player runs out of lives.
 pub struct HornetState<'a, 'b> {
     dispatcher: Option<Dispatcher<'a, 'b>>,
+    /// HORNETS for the timed level or HORNETS_ENDLESS for endless mode.
+    level_key: &'static str,
+    /// How long the timed level lasts (from the level definition, scaled by the difficulty).
+    max_seconds: f32,
+}
+
+impl<'a, 'b> Default for HornetState<'a, 'b> {
+    fn default() -> Self {
+        HornetState {
+            dispatcher: None,
+            level_key: HORNETS,
+            max_seconds: 0.0,
+        }
+    }
+}
+
+impl<'a, 'b> HornetState<'a, 'b> {
+    /// Creates the endless mode of the level.
+    pub fn endless() -> Self {
+        HornetState {
+            level_key: HORNETS_ENDLESS,
+            ..Default::default()
+        }
+    }
+
+    fn is_endless(&self) -> bool {
+        self.level_key == HORNETS_ENDLESS
+    }
+
+    /// Ends the level once the player has run out of lives.
+    fn update_endless(&mut self, world: &mut World) -> SimpleTrans {
+        run_systems(world, &mut self.dispatcher);
+
+        let seconds_elapsed = world
+            .read_resource::<LevelSecondsResource>()
+            .seconds_elapsed;
+
+        let score = world.read_resource::<CurrentLevelScoreResource>().score;
+
+        let (lives, breakdown) = {
+            let state = world.read_resource::<HornetStateResource>();
+
+            let breakdown = vec![
+                ("Seconds survived".to_string(), seconds_elapsed as u64),
+                ("Bees tapped".to_string(), state.bees_tapped),
+                (
+                    "Bees killed by abilities".to_string(),
+                    state.bees_killed_by_abilities,
+                ),
+                ("Bees escaped".to_string(), state.bees_escaped),
+            ];
+
+            (state.lives.unwrap_or(0), breakdown)
+        };
+
+        update_endless_hornets_state(world, lives, seconds_elapsed, score);
+
+        if lives == 0 {
+            finish_level(world, HORNETS_ENDLESS, breakdown)
+        } else {
+            Trans::None
+        }
+    }
 }
 
 impl<'a, 'b> SimpleState for HornetState<'a, 'b> {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
 
-        init_level_background(world, "hornets_background.png");
+        let definition = get_level_definition(world, self.level_key);
 
-        init_level_title(world, "hornets_title.png");
+        let modifiers = world
+            .read_resource::<DifficultyResource>()
+            .difficulty
+            .modifiers();
 
-        init_timer_and_score_text(world, MAX_SECONDS);
+        init_level_from_definition(world, &definition);
+
+        init_level_rng(world, &definition);
+
+        let mut state = HornetStateResource::default();
+
+        if self.is_endless() {
+            let lives = scale(
+                definition
+                    .lives
+                    .expect("The endless Murder Hornets level definition is missing lives!"),
+                modifiers.lives,
+            );
+
+            state.lives = Some(lives);
+
+            init_endless_hornets_state_text(world, lives);
+        } else {
+            self.max_seconds = definition
+                .max_seconds
+                .expect("The Murder Hornets level definition is missing max_seconds!")
+                * modifiers.max_seconds;
+
+            init_timer_and_score_text(world, self.max_seconds);
+        }
 
         world.insert(CurrentLevelScoreResource::default());
         world.insert(LevelSecondsResource::default());
 
-        let bug_spray_sprite = load_sprite(world, "bug_spray_ability.png", 0);
-        let swatter_sprite = load_sprite(world, "swatter_ability.png", 0);
-        let hive_trap_sprite = load_sprite(world, "hive_trap_ability.png", 0);
-        init_abilities_bar(
+        // Init the resource storing data about the player's progress on the level
+        world.insert(state);
+
+        init_spawn_timeline(
             world,
-            AbilitiesResource::new(vec![
-                Ability {
-                    info: AbilityInfo {
-                        ability_type: AbilityType::BugSpray,
-                        seconds_to_charge: 20,
-                        duration: None,
-                        icon: bug_spray_sprite,
-                        max_uses: None,
-                    },
-                    current_state: AbilityState::default(),
-                },
-                Ability {
-                    info: AbilityInfo {
-                        ability_type: AbilityType::FlySwatter,
-                        seconds_to_charge: 15,
-                        duration: Some(4),
-                        icon: swatter_sprite,
-                        max_uses: None,
-                    },
-                    current_state: AbilityState::default(),
-                },
-                Ability {
-                    info: AbilityInfo {
-                        ability_type: AbilityType::HiveTrap,
-                        seconds_to_charge: 7,
-                        duration: Some(4),
-                        icon: hive_trap_sprite,
-                        max_uses: None,
-                    },
-                    current_state: AbilityState::default(),
-                },
-            ]),
+            self.level_key,
+            SpawnModifiers {
+                count: modifiers.bees_per_spawn,
+                ..Default::default()
+            },
         );
 
         self.dispatcher = create_optional_systems_dispatcher(world, |builder, world| {
+            builder.add(SpawnerSystem::default(), "spawner", &[]);
             builder.add(
                 HornetsSystemDesc {
                     bee_texture: None,
@@ -83,7 +220,7 @@ impl<'a, 'b> SimpleState for HornetState<'a, 'b> {
                 }
                 .build(world),
                 "hornets",
-                &[],
+                &["spawner"],
             );
         });
     }
@@ -97,14 +234,34 @@ impl<'a, 'b> SimpleState for HornetState<'a, 'b> {
         _data: StateData<'_, GameData<'_, '_>>,
         event: StateEvent,
     ) -> SimpleTrans {
-        return_to_main_menu_on_escape(event)
+        pause_on_escape_or_focus_lost(event, self.level_key)
     }
 
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
         let world = &mut data.world;
 
+        if self.is_endless() {
+            return self.update_endless(world);
+        }
+
         run_systems(world, &mut self.dispatcher);
 
-        update_timer_and_set_high_score(world, MAX_SECONDS, HORNETS)
+        if update_timer_and_score_text(world, self.max_seconds) {
+            let breakdown = {
+                let state = world.read_resource::<HornetStateResource>();
+
+                vec![
+                    ("Bees tapped".to_string(), state.bees_tapped),
+                    (
+                        "Bees killed by abilities".to_string(),
+                        state.bees_killed_by_abilities,
+                    ),
+                ]
+            };
+
+            finish_level(world, HORNETS, breakdown)
+        } else {
+            Trans::None
+        }
     }
 }
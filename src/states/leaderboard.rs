@@ -0,0 +1,169 @@
+use crate::resources::save_data::{LeaderboardEntry, SaveDataResource, LEADERBOARD_SIZE};
+use crate::states::main_menu::MainMenuState;
+use crate::*;
+
+use amethyst::core::ecs::DenseVecStorage;
+use amethyst::input::{is_key_down, VirtualKeyCode};
+use amethyst::ui::{Anchor, LineMode, UiButton, UiEventType, UiText, UiTransform};
+
+/// The x position and width of each leaderboard column (relative to the middle of the screen).
+const RANK_COLUMN: (f32, f32) = (-270.0, 30.0);
+const NAME_COLUMN: (f32, f32) = (-180.0, 150.0);
+const SCORE_COLUMN: (f32, f32) = (-65.0, 80.0);
+const DATE_COLUMN: (f32, f32) = (30.0, 110.0);
+const DIFFICULTY_COLUMN: (f32, f32) = (125.0, 80.0);
+const SEED_COLUMN: (f32, f32) = (225.0, 120.0);
+
+/// Tags the leaderboard's texts so they can be deleted when it stops.
+pub struct LeaderboardComponent;
+impl Component for LeaderboardComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Shows the top runs of a level.
+pub struct LeaderboardState {
+    level_key: String,
+    /// The level's name shown in the title.
+    title: String,
+    back_button: Option<UiButton>,
+}
+
+impl LeaderboardState {
+    pub fn new(level_key: &str, title: &str) -> Self {
+        LeaderboardState {
+            level_key: level_key.to_string(),
+            title: title.to_string(),
+            back_button: None,
+        }
+    }
+}
+
+/// Creates a text in the leaderboard. The y position is relative to the top of the screen.
+fn create_leaderboard_text(
+    world: &mut World,
+    text: String,
+    (x, width): (f32, f32),
+    y: f32,
+    font_size: f32,
+) {
+    let font = get_main_font(world);
+
+    world
+        .create_entity()
+        .with(LeaderboardComponent)
+        .with(UiTransform::new(
+            format!("leaderboard_{}_{}", x, y),
+            Anchor::TopMiddle,
+            Anchor::TopMiddle,
+            x,
+            y,
+            1.0,
+            width,
+            font_size * 1.5,
+        ))
+        .with(UiText::new(
+            font,
+            text,
+            [1.0, 1.0, 1.0, 1.0],
+            font_size,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build();
+}
+
+/// Creates a row of the leaderboard.
+fn create_leaderboard_row(world: &mut World, rank: usize, entry: &LeaderboardEntry, y: f32) {
+    create_leaderboard_text(world, format!("{}.", rank), RANK_COLUMN, y, 16.0);
+    create_leaderboard_text(world, entry.name.clone(), NAME_COLUMN, y, 16.0);
+    create_leaderboard_text(world, entry.score.to_string(), SCORE_COLUMN, y, 16.0);
+    create_leaderboard_text(world, entry.date(), DATE_COLUMN, y, 16.0);
+    create_leaderboard_text(world, entry.difficulty.clone(), DIFFICULTY_COLUMN, y, 16.0);
+    create_leaderboard_text(world, entry.seed.to_string(), SEED_COLUMN, y, 11.0);
+}
+
+/// Creates the title, column headers and a row for every entry.
+fn init_leaderboard_text(world: &mut World, level_key: &str, title: &str) {
+    let entries = world
+        .read_resource::<SaveDataResource>()
+        .data
+        .leaderboard(level_key)
+        .to_vec();
+
+    create_leaderboard_text(
+        world,
+        format!("{} - Top {}", title, LEADERBOARD_SIZE),
+        (0.0, 600.0),
+        -20.0,
+        30.0,
+    );
+
+    let headers = [
+        ("#", RANK_COLUMN),
+        ("Name", NAME_COLUMN),
+        ("Score", SCORE_COLUMN),
+        ("Date", DATE_COLUMN),
+        ("Difficulty", DIFFICULTY_COLUMN),
+        ("Seed", SEED_COLUMN),
+    ];
+
+    for (header, column) in headers.iter() {
+        create_leaderboard_text(world, header.to_string(), *column, -80.0, 16.0);
+    }
+
+    if entries.is_empty() {
+        create_leaderboard_text(
+            world,
+            "No runs yet!".to_string(),
+            (0.0, 600.0),
+            -130.0,
+            21.5,
+        );
+    }
+
+    for (i, entry) in entries.iter().enumerate() {
+        create_leaderboard_row(world, i + 1, entry, -115.0 - i as f32 * 36.0);
+    }
+}
+
+impl SimpleState for LeaderboardState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        world.register::<LeaderboardComponent>();
+
+        init_leaderboard_text(world, &self.level_key, &self.title);
+
+        self.back_button = Some(create_centered_button(world, "Back", -250.0, 1.0));
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        delete_all_entities_with_component::<LeaderboardComponent>(world);
+
+        if let Some(button) = &self.back_button {
+            delete_ui_button(world, button);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match event {
+            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => {
+                Trans::Replace(Box::new(MainMenuState::default()))
+            }
+            StateEvent::Ui(ui_event)
+                if ui_event.event_type == UiEventType::Click
+                    && self.back_button.as_ref().map(|button| button.image_entity)
+                        == Some(ui_event.target) =>
+            {
+                Trans::Replace(Box::new(MainMenuState::default()))
+            }
+            _ => Trans::None,
+        }
+    }
+}
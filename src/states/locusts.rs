@@ -0,0 +1,190 @@
+use crate::*;
+
+use crate::systems::locusts::{Crop, LocustsSystem};
+
+use crate::resources::high_scores::highscores_keys::LOCUSTS;
+
+use crate::resources::high_scores::CurrentLevelScoreResource;
+use crate::states::results::finish_level;
+use crate::states::{
+    create_optional_systems_dispatcher, init_level_from_definition, init_timer_and_score_text,
+    pause_on_escape_or_focus_lost, run_systems, update_timer_and_score_text, LevelComponent,
+    LevelSecondsResource,
+};
+
+use crate::resources::difficulty::{scale, DifficultyResource};
+use crate::resources::levels::get_level_definition;
+use crate::resources::rng::init_level_rng;
+use amethyst::core::Transform;
+use amethyst::renderer::Transparent;
+use amethyst::shred::Dispatcher;
+
+/// A resource for storing some level state for the Locust Swarms level.
+/// Filled in from the level definition when the level starts.
+#[derive(Default)]
+pub struct LocustStateResource {
+    /// Crops that haven't been eaten. This is the score.
+    pub crops_standing: u64,
+    pub crops_eaten: u64,
+    pub locusts_sprayed: u64,
+    /// Locusts killed by the pesticide.
+    pub locusts_killed_by_abilities: u64,
+    /// How many locusts fly in each flock at the start of the level (scaled by the difficulty).
+    pub locusts_per_flock: u64,
+    pub flock_spawn_seconds: f32,
+    /// Seconds it takes for flocks to grow by one locust.
+    pub flock_growth_seconds: f32,
+}
+
+impl LocustStateResource {
+    /// How many locusts fly in a flock after the level has been running for some seconds.
+    pub fn locusts_per_flock_at(&self, seconds_elapsed: f32) -> u64 {
+        self.locusts_per_flock + (seconds_elapsed / self.flock_growth_seconds) as u64
+    }
+
+    /// Counts a crop as eaten.
+    pub fn eat_crop(&mut self) {
+        self.crops_standing = self.crops_standing.saturating_sub(1);
+        self.crops_eaten += 1;
+    }
+}
+
+/// Plants the crops in rows across the field.
+fn init_crops(world: &mut World, crops: u64, crops_per_row: u64) {
+    let sprite = load_sprite(world, "crop.png", 0);
+
+    let rows = (crops + crops_per_row - 1) / crops_per_row;
+
+    for index in 0..crops {
+        let column = index % crops_per_row;
+        let row = index / crops_per_row;
+
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(
+            75.0 + column as f32 * 90.0,
+            300.0 + (row as f32 - (rows - 1) as f32 * 0.5) * 100.0,
+            1.0,
+        );
+
+        world
+            .create_entity()
+            .with(LevelComponent)
+            .with(Crop::default())
+            .with(sprite.clone())
+            .with(transform)
+            .with(Transparent)
+            .build();
+    }
+}
+
+#[derive(Default)]
+pub struct LocustState<'a, 'b> {
+    dispatcher: Option<Dispatcher<'a, 'b>>,
+    max_seconds: f32,
+}
+
+impl<'a, 'b> SimpleState for LocustState<'a, 'b> {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        let definition = get_level_definition(world, LOCUSTS);
+
+        let modifiers = world
+            .read_resource::<DifficultyResource>()
+            .difficulty
+            .modifiers();
+
+        self.max_seconds = definition
+            .max_seconds
+            .expect("The Locust Swarms level definition is missing max_seconds!");
+
+        let crops = definition
+            .crops
+            .expect("The Locust Swarms level definition is missing crops!");
+
+        let crops_per_row = definition
+            .crops_per_row
+            .expect("The Locust Swarms level definition is missing crops_per_row!");
+
+        let flock_spawn_seconds = definition
+            .flock_spawn_seconds
+            .expect("The Locust Swarms level definition is missing flock_spawn_seconds!");
+
+        let locusts_per_flock = definition
+            .locusts_per_flock
+            .expect("The Locust Swarms level definition is missing locusts_per_flock!");
+
+        let flock_growth_seconds = definition
+            .flock_growth_seconds
+            .expect("The Locust Swarms level definition is missing flock_growth_seconds!");
+
+        init_level_from_definition(world, &definition);
+
+        init_level_rng(world, &definition);
+
+        init_timer_and_score_text(world, self.max_seconds);
+
+        world.insert(CurrentLevelScoreResource { score: crops });
+        world.insert(LevelSecondsResource::default());
+
+        // Init the resource storing data about the player's progress on the level
+        world.insert(LocustStateResource {
+            crops_standing: crops,
+            locusts_per_flock: scale(locusts_per_flock, modifiers.locusts_per_flock),
+            flock_spawn_seconds,
+            flock_growth_seconds,
+            ..Default::default()
+        });
+
+        self.dispatcher = create_optional_systems_dispatcher(world, |builder, _| {
+            builder.add(LocustsSystem::default(), "locusts", &[])
+        });
+
+        // Planted after the dispatcher is set up so the crop storage is registered.
+        init_crops(world, crops, crops_per_row);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        delete_all_entities_with_component::<LevelComponent>(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        pause_on_escape_or_focus_lost(event, LOCUSTS)
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let world = &mut data.world;
+
+        run_systems(world, &mut self.dispatcher);
+
+        let (crops_standing, breakdown) = {
+            let state = world.read_resource::<LocustStateResource>();
+
+            // The score is the crops still standing.
+            world.write_resource::<CurrentLevelScoreResource>().score = state.crops_standing;
+
+            let breakdown = vec![
+                ("Crops standing".to_string(), state.crops_standing),
+                ("Crops eaten".to_string(), state.crops_eaten),
+                ("Locusts sprayed".to_string(), state.locusts_sprayed),
+                (
+                    "Locusts killed by pesticide".to_string(),
+                    state.locusts_killed_by_abilities,
+                ),
+            ];
+
+            (state.crops_standing, breakdown)
+        };
+
+        // The level ends when time runs out or every crop has been eaten.
+        if update_timer_and_score_text(world, self.max_seconds) || crops_standing == 0 {
+            finish_level(world, LOCUSTS, breakdown)
+        } else {
+            Trans::None
+        }
+    }
+}
@@ -1,87 +1,302 @@
-use crate::resources::high_scores::highscores_keys::{COVID, HORNETS, WILDFIRES};
-use crate::resources::high_scores::load_scores;
-use crate::states::hornets::HornetState;
-use crate::states::wildfires::{WildfireState, WildfiresStateTextComponent};
+use crate::resources::difficulty::{current_score_key, DifficultyResource};
+use crate::resources::display::apply_display_settings;
+use crate::resources::high_scores::highscores_keys::{
+    CAMPAIGN, COVID, HORNETS, HORNETS_ENDLESS, LOCUSTS, TOILET_PAPER, WILDFIRES, ZOOM,
+};
+use crate::resources::levels::{
+    get_level_definition, level_name, load_level_definitions, LevelDefinitionsResource,
+};
+use crate::resources::replay::PendingReplayResource;
+use crate::resources::save_data::SaveDataResource;
+use crate::resources::unlocks::{is_unlocked, unlocking_level, update_unlocks};
+use crate::states::campaign::{continue_campaign, start_new_campaign, CampaignResource};
+use crate::states::controls::ControlsState;
+use crate::states::fixed_timestep::InterpolationComponent;
+use crate::states::leaderboard::LeaderboardState;
+use crate::states::replay::ReplayState;
+use crate::states::settings::SettingsState;
+use crate::states::wildfires::WildfiresStateTextComponent;
 use crate::*;
 
 use crate::audio::initialise_audio;
-use crate::states::{init_camera, init_level_title, LevelComponent, TimerComponent};
+use crate::states::{init_camera, init_level_title, start_level, LevelComponent, TimerComponent};
 
-use crate::states::covid::{CovidState, CovidStateTextComponent};
-use amethyst::ui::{Anchor, UiButton, UiButtonBuilder, UiEventType};
+use crate::states::covid::CovidStateTextComponent;
+use crate::states::hornets::EndlessHornetsStateTextComponent;
+use crate::states::toilet_paper::ToiletPaperStateTextComponent;
+use amethyst::ui::{Anchor, LineMode, UiButton, UiButtonBuilder, UiEventType, UiText, UiTransform};
+
+/// The levels on the menu (key and button text), one row each from the top.
+const MENU_LEVELS: &[(&str, &str)] = &[
+    (WILDFIRES, "Level 1: Wildfires"),
+    (HORNETS, "Level 2: Murder Hornets"),
+    (COVID, "Level 3: Covid-19"),
+    (LOCUSTS, "Level 4: Locust Swarms"),
+    (TOILET_PAPER, "Level 5: Toilet Paper Panic"),
+    (ZOOM, "Level 6: Zoom Meeting"),
+    (HORNETS_ENDLESS, "Endless: Murder Hornets"),
+];
+
+/// Vertical distance between the tops of the menu rows.
+const MENU_ROW_SPACING: f32 = 54.0;
+const MENU_ROW_HEIGHT: f32 = 46.0;
+
+/// The y position of the top of a menu row (relative to the top of the screen).
+fn menu_row_y(row: u32) -> f32 {
+    -45.0 - (row as f32 * MENU_ROW_SPACING)
+}
+
+/// A level's button and the button next to it showing its high score (or what unlocks it).
+struct LevelRow {
+    level_key: &'static str,
+    level_button: UiButton,
+    high_score_button: UiButton,
+    locked: bool,
+}
 
 #[derive(Default)]
 pub struct MainMenuState {
-    hornets_and_highscore_button: Option<(UiButton, UiButton)>,
-    wildfires_and_highscore_button: Option<(UiButton, UiButton)>,
-    covid_and_highscore_button: Option<(UiButton, UiButton)>,
+    /// Created once the level definitions are loaded, since the pass scores decide what's unlocked.
+    level_rows: Vec<LevelRow>,
+    /// Starts or continues the campaign. Its high score button shows the campaign high score.
+    campaign_and_highscore_button: Option<(UiButton, UiButton)>,
+    difficulty_button: Option<UiButton>,
+    controls_button: Option<UiButton>,
+    settings_button: Option<UiButton>,
+    new_campaign_button: Option<UiButton>,
 }
 
-/// Creates a button for a level and displays that level's highscore.
-/// Vertical padding determined by level number.
-/// Level number should be >0
+impl MainMenuState {
+    /// Creates a row for every level in `MENU_LEVELS`, greying out the locked ones.
+    fn init_level_rows(&mut self, world: &mut World) {
+        // Catches up on levels earned before unlocks were saved.
+        update_unlocks(world);
+
+        for (index, &(level_key, text)) in MENU_LEVELS.iter().enumerate() {
+            let locked = !is_unlocked(&world.read_resource::<SaveDataResource>().data, level_key);
+
+            let (level_button, high_score_button) =
+                create_level_button_with_highscore(world, text, index as u32 + 1, locked);
+
+            self.level_rows.push(LevelRow {
+                level_key,
+                level_button,
+                high_score_button,
+                locked,
+            });
+        }
+
+        self.refresh_difficulty_texts(world);
+    }
+
+    /// Shows the selected difficulty and the high scores for it.
+    fn refresh_difficulty_texts(&self, world: &mut World) {
+        let difficulty = world.read_resource::<DifficultyResource>().difficulty;
+
+        if let Some(button) = &self.difficulty_button {
+            set_button_text(world, button, format!("Difficulty: {}", difficulty.name()));
+        }
+
+        // A saved campaign is continued on the difficulty it was started on.
+        let campaign = world
+            .read_resource::<SaveDataResource>()
+            .data
+            .campaign
+            .clone();
+        if let Some((campaign_button, high_score_button)) = &self.campaign_and_highscore_button {
+            let text = match campaign {
+                Some(progress) => format!("Continue Campaign: Level {}", progress.level_index + 1),
+                None => "Campaign".to_string(),
+            };
+
+            set_button_text(world, campaign_button, text);
+
+            let text = format!("High Score: {}", get_current_high_score(world, CAMPAIGN));
+            set_button_text(world, high_score_button, text);
+        }
+
+        for row in self.level_rows.iter() {
+            let text = if row.locked {
+                unlock_requirement_text(world, row.level_key)
+            } else {
+                format!(
+                    "High Score: {}",
+                    get_current_high_score(world, row.level_key)
+                )
+            };
+
+            set_button_text(world, &row.high_score_button, text);
+        }
+    }
+
+    /// Opens a level's leaderboard for the selected difficulty.
+    fn open_leaderboard(world: &World, level_key: &str) -> SimpleTrans {
+        let difficulty = world.read_resource::<DifficultyResource>().difficulty;
+
+        Trans::Replace(Box::new(LeaderboardState::new(
+            &current_score_key(world, level_key),
+            &format!("{} ({})", level_name(level_key), difficulty.name()),
+        )))
+    }
+}
+
+/// The high score of a level on the selected difficulty.
+fn get_current_high_score(world: &World, level_key: &str) -> u64 {
+    world
+        .read_resource::<SaveDataResource>()
+        .data
+        .get_score(&current_score_key(world, level_key))
+}
+
+/// What unlocks a locked level, like "Score 60 in Wildfires".
+fn unlock_requirement_text(world: &World, level_key: &str) -> String {
+    match unlocking_level(level_key) {
+        Some(unlocking) => format!(
+            "Score {} in {}",
+            get_level_definition(world, unlocking)
+                .pass_score
+                .unwrap_or(0),
+            level_name(unlocking)
+        ),
+        None => String::new(),
+    }
+}
+
+/// Changes the text of a button.
+fn set_button_text(world: &mut World, button: &UiButton, text: String) {
+    if let Some(ui_text) = world.write_storage::<UiText>().get_mut(button.text_entity) {
+        ui_text.text = text;
+    }
+}
+
+/// Creates a button for a level and a button for that level's highscore (filled in by `refresh_difficulty_texts`).
+/// The high score button opens the level's leaderboard.
+/// Locked levels are grey and don't change when hovered.
+/// Vertical padding determined by row number.
+/// Row should be >0
 fn create_level_button_with_highscore(
     world: &mut World,
-    title: &str,
-    level_number: u32,
-    high_score: u64,
+    text: &str,
+    row: u32,
+    locked: bool,
 ) -> (UiButton, UiButton) {
     let dimensions = (*world.read_resource::<ScreenDimensions>()).clone();
 
-    let y_spacing = -45.0 - (level_number as f32 * 75.0);
+    let y_spacing = menu_row_y(row);
 
-    let height = 64.0;
+    let height = MENU_ROW_HEIGHT;
 
-    let color = create_ui_color_from_rgba(195, 130, 51, 1.0);
-    let hover_color = create_ui_color_from_rgba(195, 130, 51, 0.5);
-    let alt_color = create_ui_color_from_rgba(243, 180, 73, 1.0);
+    let (color, hover_color, alt_color, alt_hover_color) = if locked {
+        (
+            create_ui_color_from_rgba(110, 110, 110, 1.0),
+            create_ui_color_from_rgba(110, 110, 110, 1.0),
+            create_ui_color_from_rgba(140, 140, 140, 1.0),
+            create_ui_color_from_rgba(140, 140, 140, 1.0),
+        )
+    } else {
+        (
+            create_ui_color_from_rgba(195, 130, 51, 1.0),
+            create_ui_color_from_rgba(195, 130, 51, 0.5),
+            create_ui_color_from_rgba(243, 180, 73, 1.0),
+            create_ui_color_from_rgba(243, 180, 73, 0.5),
+        )
+    };
 
     let font = get_main_font(world);
 
-    let (_, button) = UiButtonBuilder::<(), u32>::new(format!("Level {}: {}", level_number, title))
+    let (_, button) = UiButtonBuilder::<(), u32>::new(text)
         .with_font(font.clone())
         .with_font_size(21.5)
         .with_position(((dimensions.width() * 0.6) / 2.0) + 2.5, y_spacing)
         .with_size((dimensions.width() * 0.6) - 15.0, height)
         .with_anchor(Anchor::TopLeft)
-        .with_image(color.clone())
+        .with_image(color)
         .with_hover_image(hover_color)
         .build_from_world(&world);
 
-    let (_, high_score) = UiButtonBuilder::<(), u32>::new(format!("High Score: {}", high_score))
+    let (_, high_score) = UiButtonBuilder::<(), u32>::new("High Score")
         .with_font_size(18.5)
         .with_font(font)
         .with_position(((dimensions.width() * -0.4) / 2.0) - 2.5, y_spacing)
         .with_size((dimensions.width() * 0.4) - 15.0, height)
         .with_anchor(Anchor::TopRight)
         .with_image(alt_color)
+        .with_hover_image(alt_hover_color)
         .build_from_world(&world);
 
     (button, high_score)
 }
 
+/// Creates a menu button below the level buttons. The row is split into equal columns.
+/// Vertical padding determined by row number, continuing from the level rows.
+fn create_menu_button(
+    world: &mut World,
+    text: &str,
+    row: u32,
+    column: u32,
+    columns: u32,
+) -> UiButton {
+    let dimensions = (*world.read_resource::<ScreenDimensions>()).clone();
+
+    let font = get_main_font(world);
+
+    let column_width = (dimensions.width() - 10.0) / columns as f32;
+    let x = -dimensions.width() / 2.0 + 5.0 + column_width * (column as f32 + 0.5);
+
+    // Smaller text so it fits in narrow columns.
+    let font_size = if columns > 2 { 16.0 } else { 21.5 };
+
+    let (_, button) = UiButtonBuilder::<(), u32>::new(text)
+        .with_font(font)
+        .with_font_size(font_size)
+        .with_position(x, menu_row_y(row))
+        .with_size(column_width - 15.0, MENU_ROW_HEIGHT)
+        .with_anchor(Anchor::TopMiddle)
+        .with_image(create_ui_color_from_rgba(195, 130, 51, 1.0))
+        .with_hover_image(create_ui_color_from_rgba(195, 130, 51, 0.5))
+        .build_from_world(&world);
+
+    button
+}
+
+/// Shows a problem found while loading the save (like a corrupt file) at the bottom of the menu.
+/// Tagged with LevelComponent so it's deleted with the menu.
+fn init_save_report_text(world: &mut World, report: String) {
+    let font = get_main_font(world);
+
+    world
+        .create_entity()
+        .with(LevelComponent)
+        .with(UiTransform::new(
+            "save_report".to_string(),
+            Anchor::BottomMiddle,
+            Anchor::BottomMiddle,
+            0.0,
+            10.0,
+            1.0,
+            580.0,
+            40.0,
+        ))
+        .with(UiText::new(
+            font,
+            report,
+            [1.0, 0.4, 0.4, 1.0],
+            15.0,
+            LineMode::Wrap,
+            Anchor::Middle,
+        ))
+        .build();
+}
+
 /// Deletes level and highscore buttons passed in.
 fn delete_level_and_highscore_buttons(world: &mut World, buttons: &Option<(UiButton, UiButton)>) {
-    // Delete level button
-    world
-        .entities()
-        .delete(buttons.as_ref().unwrap().0.image_entity)
-        .expect("Cannot delete UiButton's image entity.");
+    let (level_button, high_score_button) = buttons.as_ref().unwrap();
 
-    world
-        .entities()
-        .delete(buttons.as_ref().unwrap().0.text_entity)
-        .expect("Cannot delete UiButton's text entity.");
+    // Delete level button
+    delete_ui_button(world, level_button);
 
     // Delete high score button
-    world
-        .entities()
-        .delete(buttons.as_ref().unwrap().1.image_entity)
-        .expect("Cannot delete UiButton's image entity.");
-    world
-        .entities()
-        .delete(buttons.as_ref().unwrap().1.text_entity)
-        .expect("Cannot delete UiButton's text entity.");
+    delete_ui_button(world, high_score_button);
 }
 
 impl SimpleState for MainMenuState {
@@ -91,44 +306,91 @@ impl SimpleState for MainMenuState {
         // Register the components we won't use in any systems
         world.register::<LevelComponent>();
         world.register::<TimerComponent>();
+        world.register::<InterpolationComponent>();
         world.register::<WildfiresStateTextComponent>();
         world.register::<CovidStateTextComponent>();
+        world.register::<EndlessHornetsStateTextComponent>();
+        world.register::<ToiletPaperStateTextComponent>();
 
         // Init 2d camera
         init_camera(world);
 
+        // Apply the saved display settings (only does anything at startup or after they change).
+        apply_display_settings(world);
+
         // Init audio
         initialise_audio(world);
 
         // Init level title
         init_level_title(world, "logo.png");
 
+        // Start loading the level definitions (only happens the first time the menu opens).
+        load_level_definitions(world);
+
         // Create the fonts resource.
         let font = load_font(world, "main_font.ttf");
         world.insert(FontsResource { main_font: font });
 
-        let high_scores = load_scores();
+        // The campaign and menu buttons go below the level rows.
+        let campaign_row = MENU_LEVELS.len() as u32 + 1;
 
-        self.wildfires_and_highscore_button = Some(create_level_button_with_highscore(
+        self.campaign_and_highscore_button = Some(create_level_button_with_highscore(
+            world,
+            "Campaign",
+            campaign_row,
+            false,
+        ));
+
+        self.difficulty_button = Some(create_menu_button(
+            world,
+            "Difficulty",
+            campaign_row + 1,
+            0,
+            4,
+        ));
+        self.controls_button = Some(create_menu_button(
             world,
-            "Wildfires",
+            "Controls",
+            campaign_row + 1,
             1,
-            high_scores.get_score(WILDFIRES),
+            4,
         ));
-        self.hornets_and_highscore_button = Some(create_level_button_with_highscore(
+        self.settings_button = Some(create_menu_button(
             world,
-            "Murder Hornets",
+            "Settings",
+            campaign_row + 1,
             2,
-            high_scores.get_score(HORNETS),
+            4,
         ));
-        self.covid_and_highscore_button = Some(create_level_button_with_highscore(
+        self.new_campaign_button = Some(create_menu_button(
             world,
-            "Covid-19",
+            "New Campaign",
+            campaign_row + 1,
             3,
-            high_scores.get_score(COVID),
+            4,
         ));
 
-        world.insert(high_scores);
+        // Levels started from here aren't part of the campaign until the campaign is picked.
+        world.write_resource::<CampaignResource>().active = false;
+
+        // If the level definitions are still loading, the level rows are created in update.
+        if world
+            .read_resource::<LevelDefinitionsResource>()
+            .is_loaded()
+        {
+            self.init_level_rows(world);
+        } else {
+            self.refresh_difficulty_texts(world);
+        }
+
+        // Only show the report the first time the menu opens.
+        let load_report = world
+            .write_resource::<SaveDataResource>()
+            .load_report
+            .take();
+        if let Some(report) = load_report {
+            init_save_report_text(world, report);
+        }
     }
 
     fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
@@ -136,45 +398,85 @@ impl SimpleState for MainMenuState {
 
         delete_all_entities_with_component::<LevelComponent>(world);
 
-        delete_level_and_highscore_buttons(world, &self.hornets_and_highscore_button);
-        delete_level_and_highscore_buttons(world, &self.wildfires_and_highscore_button);
-        delete_level_and_highscore_buttons(world, &self.covid_and_highscore_button);
+        for row in self.level_rows.drain(..) {
+            delete_ui_button(world, &row.level_button);
+            delete_ui_button(world, &row.high_score_button);
+        }
+
+        delete_level_and_highscore_buttons(world, &self.campaign_and_highscore_button);
+
+        for button in [
+            &self.difficulty_button,
+            &self.controls_button,
+            &self.settings_button,
+            &self.new_campaign_button,
+        ]
+        .iter()
+        .copied()
+        .flatten()
+        {
+            delete_ui_button(world, button);
+        }
     }
 
     fn handle_event(
         &mut self,
-        mut _data: StateData<'_, GameData<'_, '_>>,
+        data: StateData<'_, GameData<'_, '_>>,
         event: StateEvent,
     ) -> SimpleTrans {
+        // Levels can't be started until their definitions are loaded.
+        let definitions_loaded = data
+            .world
+            .read_resource::<LevelDefinitionsResource>()
+            .is_loaded();
+
         match event {
             StateEvent::Ui(ui_event) => {
-                if ui_event.event_type == UiEventType::Click {
-                    let hornets_button = self
-                        .hornets_and_highscore_button
-                        .as_ref()
-                        .unwrap()
-                        .0
-                        .image_entity;
-                    let wildfires_button = self
-                        .wildfires_and_highscore_button
-                        .as_ref()
-                        .unwrap()
-                        .0
-                        .image_entity;
-
-                    let covid_button = self
-                        .covid_and_highscore_button
-                        .as_ref()
-                        .unwrap()
-                        .0
-                        .image_entity;
-
-                    if ui_event.target == wildfires_button {
-                        Trans::Replace(Box::new(WildfireState::default()))
-                    } else if ui_event.target == hornets_button {
-                        Trans::Replace(Box::new(HornetState::default()))
-                    } else if ui_event.target == covid_button {
-                        Trans::Replace(Box::new(CovidState::default()))
+                if ui_event.event_type == UiEventType::Click && definitions_loaded {
+                    let target = ui_event.target;
+
+                    let is_target = |button: Option<&UiButton>| {
+                        button.map(|button| button.image_entity) == Some(target)
+                    };
+
+                    let level_row = self
+                        .level_rows
+                        .iter()
+                        .find(|row| row.level_button.image_entity == target);
+                    let high_score_row = self
+                        .level_rows
+                        .iter()
+                        .find(|row| row.high_score_button.image_entity == target);
+
+                    if let Some(row) = level_row {
+                        if row.locked {
+                            Trans::None
+                        } else {
+                            start_level(row.level_key)
+                        }
+                    } else if let Some(row) = high_score_row {
+                        Self::open_leaderboard(data.world, row.level_key)
+                    } else if is_target(
+                        self.campaign_and_highscore_button
+                            .as_ref()
+                            .map(|(button, _)| button),
+                    ) {
+                        continue_campaign(data.world)
+                    } else if is_target(self.new_campaign_button.as_ref()) {
+                        start_new_campaign(data.world)
+                    } else if is_target(self.difficulty_button.as_ref()) {
+                        {
+                            let mut resource = data.world.write_resource::<DifficultyResource>();
+                            resource.difficulty = resource.difficulty.next();
+                        }
+
+                        self.refresh_difficulty_texts(data.world);
+
+                        Trans::None
+                    } else if is_target(self.controls_button.as_ref()) {
+                        Trans::Replace(Box::new(ControlsState::default()))
+                    } else if is_target(self.settings_button.as_ref()) {
+                        Trans::Replace(Box::new(SettingsState::default()))
                     } else {
                         Trans::None
                     }
@@ -185,4 +487,27 @@ impl SimpleState for MainMenuState {
             _ => Trans::None,
         }
     }
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let world = &mut data.world;
+
+        if world
+            .read_resource::<LevelDefinitionsResource>()
+            .is_loaded()
+        {
+            if self.level_rows.is_empty() {
+                self.init_level_rows(world);
+            }
+
+            // Play the replay passed on the command line once the level definitions are loaded.
+            if let Some(replay) = world
+                .write_resource::<PendingReplayResource>()
+                .replay
+                .take()
+            {
+                return Trans::Replace(Box::new(ReplayState::new(replay)));
+            }
+        }
+
+        Trans::None
+    }
 }
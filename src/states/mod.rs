@@ -1,13 +1,43 @@
+pub mod campaign;
+pub mod controls;
 pub mod covid;
+pub mod fixed_timestep;
 pub mod hornets;
+pub mod leaderboard;
+pub mod locusts;
 pub mod main_menu;
+pub mod pause;
+pub mod replay;
+pub mod results;
+pub mod settings;
+pub mod toilet_paper;
 pub mod wildfires;
+pub mod zoom;
 
-use crate::resources::high_scores::{update_high_score_if_greater, CurrentLevelScoreResource};
-use crate::states::main_menu::MainMenuState;
+use crate::playfield::{Playfield, PlayfieldUiComponent, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+use crate::resources::high_scores::highscores_keys::{
+    COVID, HORNETS, HORNETS_ENDLESS, LOCUSTS, TOILET_PAPER, WILDFIRES, ZOOM,
+};
+use crate::resources::high_scores::CurrentLevelScoreResource;
+use crate::resources::levels::{
+    create_abilities_from_definition, get_spawn_timeline, LevelDefinition,
+};
+use crate::resources::spawn_timeline::{
+    SpawnModifiers, SpawnRequestsResource, SpawnTimelineResource,
+};
+use crate::states::covid::CovidState;
+use crate::states::fixed_timestep::{run_fixed_steps, FIXED_TIMESTEP_SECONDS, MAX_STEPS_PER_FRAME};
+use crate::states::hornets::HornetState;
+use crate::states::locusts::LocustState;
+use crate::states::pause::PauseState;
+use crate::states::replay::RecordingState;
+use crate::states::toilet_paper::ToiletPaperState;
+use crate::states::wildfires::WildfireState;
+use crate::states::zoom::ZoomState;
+use crate::systems::ability_bar::init_abilities_bar;
 
-use amethyst::core::Time;
 use amethyst::input::{is_key_down, VirtualKeyCode};
+use amethyst::winit::{Event, WindowEvent};
 
 use crate::{get_main_font, load_sprite};
 use amethyst::renderer::Transparent;
@@ -22,108 +52,155 @@ use amethyst::{
     window::ScreenDimensions,
 };
 
-/// A component to tag a timer text component.
+/// A boxed state that can be used in a `SimpleTrans`.
+pub type BoxedState = Box<dyn State<GameData<'static, 'static>, StateEvent>>;
+
+/// Creates the state for a level based on its key.
+pub fn create_level_state(level_key: &str) -> BoxedState {
+    match level_key {
+        WILDFIRES => Box::new(WildfireState::default()),
+        HORNETS => Box::new(HornetState::default()),
+        HORNETS_ENDLESS => Box::new(HornetState::endless()),
+        COVID => Box::new(CovidState::default()),
+        LOCUSTS => Box::new(LocustState::default()),
+        TOILET_PAPER => Box::new(ToiletPaperState::default()),
+        ZOOM => Box::new(ZoomState::default()),
+        _ => panic!("No level with the key {}!", level_key),
+    }
+}
+
+/// Starts a level and records the run.
+pub fn start_level(level_key: &str) -> SimpleTrans {
+    Trans::Replace(Box::new(RecordingState::new(
+        level_key,
+        create_level_state(level_key),
+    )))
+}
+
+/// Tracks how long the current level has been running.
 #[derive(Default)]
 pub struct LevelSecondsResource {
+    /// Seconds simulated so far. Always a whole number of fixed timesteps.
     pub(crate) seconds_elapsed: f32,
+    /// Frame time that hasn't been simulated yet (less than one fixed timestep).
+    pub(crate) unsimulated_seconds: f32,
+}
+
+impl LevelSecondsResource {
+    /// Adds a frame's time to be simulated. Returns how many fixed timesteps are due.
+    pub fn accumulate(&mut self, frame_seconds: f32) -> u32 {
+        self.unsimulated_seconds += frame_seconds;
+
+        let steps = (self.unsimulated_seconds / FIXED_TIMESTEP_SECONDS).floor() as u32;
+
+        self.unsimulated_seconds =
+            (self.unsimulated_seconds - steps as f32 * FIXED_TIMESTEP_SECONDS).max(0.);
+
+        steps.min(MAX_STEPS_PER_FRAME)
+    }
+
+    /// Advances the level by one fixed timestep.
+    pub fn advance(&mut self, delta_seconds: f32) {
+        self.seconds_elapsed += delta_seconds;
+    }
 }
 impl Component for LevelSecondsResource {
     type Storage = DenseVecStorage<Self>;
 }
 
 /// A component to tag a timer text component.
-pub struct TimerComponent;
+#[derive(Default)]
+pub struct TimerComponent {
+    /// The rounded time and the score the text shows, so it's only rewritten when one of them changes.
+    shown: Option<(f32, u64)>,
+}
 impl Component for TimerComponent {
     type Storage = DenseVecStorage<Self>;
 }
 
-/// Update the elapsed time using delta seconds and set the high score if max time is passed and the score is the highest.
-/// It also updates the score counter when updating the time.
-pub fn update_timer_and_set_high_score(
-    world: &mut World,
-    max_time: f32,
-    high_score_key: &str,
-) -> SimpleTrans {
-    // Update seconds elapsed
-    let (new_time, old_time) = {
-        let mut seconds = world.write_resource::<LevelSecondsResource>();
-
-        let old_time = seconds.seconds_elapsed;
-
-        seconds.seconds_elapsed += world.read_resource::<Time>().delta_seconds();
-
-        (seconds.seconds_elapsed, old_time)
-    };
+/// Updates the timer text with the elapsed time and the score. Returns true once max time is passed.
+pub fn update_timer_and_score_text(world: &mut World, max_time: f32) -> bool {
+    let new_time = world
+        .read_resource::<LevelSecondsResource>()
+        .seconds_elapsed;
 
     let rounded_new_time = (new_time * 10.0).round() / 10.0;
-    let rounded_old_time = (old_time * 10.0).round() / 10.0;
-
-    // Whether or not the rounded times changed
-    let rounded_times_changed = rounded_new_time > rounded_old_time.floor();
 
     // If the timer is maxed out.
     let level_is_over = new_time >= max_time;
 
     let timer_entity = {
-        if rounded_times_changed || level_is_over {
-            let mut ui_texts = world.write_storage::<UiText>();
-            let timer_components = world.read_storage::<TimerComponent>();
+        let mut ui_texts = world.write_storage::<UiText>();
+        let mut timer_components = world.write_storage::<TimerComponent>();
 
-            let score = world.read_resource::<CurrentLevelScoreResource>();
+        let score = world.read_resource::<CurrentLevelScoreResource>();
 
-            let entities = world.entities();
+        let entities = world.entities();
 
-            let mut timer_entity = None;
+        let mut timer_entity = None;
 
-            for (ui_text, _, entity) in (&mut ui_texts, &timer_components, &entities).join() {
-                ui_text.text = format!(
-                    "{}s / {}s - Score: {}",
-                    rounded_new_time, max_time, score.score
-                );
+        for (timer, entity) in (&mut timer_components, &entities).join() {
+            // Rewriting the text every frame would make the UI lay it out again every frame.
+            let shown = Some((rounded_new_time, score.score));
 
-                if level_is_over {
-                    timer_entity = Some(entity);
+            if timer.shown != shown {
+                if let Some(ui_text) = ui_texts.get_mut(entity) {
+                    ui_text.text = format!(
+                        "{}s / {}s - Score: {}",
+                        rounded_new_time, max_time, score.score
+                    );
                 }
+
+                timer.shown = shown;
             }
 
-            timer_entity
-        } else {
-            None
+            if level_is_over {
+                timer_entity = Some(entity);
+            }
         }
+
+        timer_entity
     };
 
-    if level_is_over {
-        update_high_score_if_greater(world, high_score_key);
+    // Delete the timer entity.
+    if let Some(entity) = timer_entity {
+        world
+            .delete_entity(entity)
+            .expect("Couldn't delete timer text entity!");
+    }
 
-        // Delete the timer entity.
-        if let Some(entity) = timer_entity {
-            world
-                .delete_entity(entity)
-                .expect("Couldn't delete timer text entity!");
-        }
+    level_is_over
+}
 
-        Trans::Replace(Box::new(MainMenuState::default()))
-    } else {
-        Trans::None
-    }
+/// Where a level's HUD text goes: a line near the top of the playfield, under the title.
+pub fn hud_text_layout() -> PlayfieldUiComponent {
+    PlayfieldUiComponent::new(
+        PLAYFIELD_WIDTH * 0.5,
+        PLAYFIELD_HEIGHT - 80.0,
+        PLAYFIELD_WIDTH,
+        50.0,
+    )
+    .with_font_size(25.0)
+}
+
+/// Creates a UI transform for an element laid out on the playfield, placed for the current window size.
+pub fn playfield_ui_transform(
+    world: &World,
+    id: &str,
+    layout: &PlayfieldUiComponent,
+) -> UiTransform {
+    Playfield::from_dimensions(&world.read_resource::<ScreenDimensions>())
+        .ui_transform(id.to_string(), layout)
 }
 
 /// Create timer/score text with default value of "0s / {max_seconds}s - Score: 0"
 /// Tagged with TimerComponent.
-/// It will automatically get deleted when used with `update_timer_and_set_high_score` when the timer ends.
+/// It will automatically get deleted when used with `update_timer_and_score_text` when the timer ends.
 pub fn init_timer_and_score_text(world: &mut World, max_seconds: f32) {
     let font = get_main_font(world);
 
-    let transform = UiTransform::new(
-        "timer_text".to_string(),
-        Anchor::TopMiddle,
-        Anchor::TopMiddle,
-        0.0,
-        -55.0,
-        0.0,
-        600.0,
-        50.0,
-    );
+    let layout = hud_text_layout();
+    let transform = playfield_ui_transform(world, "timer_text", &layout);
     let ui_text = UiText::new(
         font,
         format!("0s /{}s - Score: 0", max_seconds),
@@ -135,23 +212,28 @@ pub fn init_timer_and_score_text(world: &mut World, max_seconds: f32) {
 
     world
         .create_entity()
-        .with(TimerComponent)
+        .with(TimerComponent::default())
         .with(LevelComponent)
+        .with(layout)
         .with(transform)
         .with(ui_text)
         .build();
 }
 
-/// Creates the 2D camera.
+/// Creates the 2D camera, centered on the playfield and fitted to the window.
+/// The `PlayfieldLayoutSystem` rebuilds it when the window is resized.
 pub fn init_camera(world: &mut World) {
-    let dimensions = (*world.read_resource::<ScreenDimensions>()).clone();
+    let playfield = Playfield::from_dimensions(&world.read_resource::<ScreenDimensions>());
 
     let mut transform = Transform::default();
-    transform.set_translation_xyz(dimensions.width() * 0.5, dimensions.height() * 0.5, 10.);
+    transform.set_translation_xyz(PLAYFIELD_WIDTH * 0.5, PLAYFIELD_HEIGHT * 0.5, 10.);
 
     world
         .create_entity()
-        .with(Camera::standard_2d(dimensions.width(), dimensions.height()))
+        .with(Camera::standard_2d(
+            playfield.view_width(),
+            playfield.view_height(),
+        ))
         .with(transform)
         .build();
 }
@@ -181,18 +263,26 @@ pub fn create_optional_systems_dispatcher<'a, 'b>(
     Some(create_systems_dispatcher(world, add_systems))
 }
 
-/// Take's a state's dispatcher and if it exists, runs all of its systems.
-pub fn run_systems(world: &World, dispatcher: &mut Option<Dispatcher>) {
+/// Take's a state's dispatcher and if it exists, runs all of its systems on a fixed timestep.
+pub fn run_systems(world: &mut World, dispatcher: &mut Option<Dispatcher>) {
     if let Some(dispatcher) = dispatcher.as_mut() {
-        dispatcher.dispatch(world);
+        run_fixed_steps(world, dispatcher);
     }
 }
 
-/// Return to main menu on escape.
-pub fn return_to_main_menu_on_escape(event: StateEvent) -> SimpleTrans {
+/// Pause the level on escape or when the window loses focus.
+pub fn pause_on_escape_or_focus_lost(event: StateEvent, level_key: &str) -> SimpleTrans {
     if let StateEvent::Window(event) = &event {
-        if is_key_down(event, VirtualKeyCode::Escape) {
-            Trans::Replace(Box::new(MainMenuState::default()))
+        let focus_lost = match event {
+            Event::WindowEvent {
+                event: WindowEvent::Focused(false),
+                ..
+            } => true,
+            _ => false,
+        };
+
+        if is_key_down(event, VirtualKeyCode::Escape) || focus_lost {
+            Trans::Push(Box::new(PauseState::new(level_key)))
         } else {
             Trans::None
         }
@@ -207,22 +297,13 @@ impl Component for LevelComponent {
     type Storage = DenseVecStorage<Self>;
 }
 
-/// Displays the level background in the center of the screen.
+/// Displays the level background in the center of the playfield.
 pub fn init_level_background(world: &mut World, filename: &str) {
     let background = load_sprite(world, filename, 0);
 
-    let (width, height) = {
-        let dimensions = world.read_resource::<ScreenDimensions>();
-
-        let width = dimensions.width();
-        let height = dimensions.height();
-
-        (width, height)
-    };
-
     let mut transform = Transform::default();
 
-    transform.set_translation_xyz(width * 0.5, height * 0.5, -10.0);
+    transform.set_translation_xyz(PLAYFIELD_WIDTH * 0.5, PLAYFIELD_HEIGHT * 0.5, -10.0);
 
     world
         .create_entity()
@@ -232,14 +313,12 @@ pub fn init_level_background(world: &mut World, filename: &str) {
         .build();
 }
 
-/// Displays the level title at the top of the screen.
+/// Displays the level title at the top of the playfield.
 pub fn init_level_title(world: &mut World, filename: &str) {
-    let dimensions = (*world.read_resource::<ScreenDimensions>()).clone();
-
     let sprite = load_sprite(world, filename, 0);
 
     let mut transform = Transform::default();
-    transform.set_translation_xyz(dimensions.width() * 0.5, dimensions.height() * 0.93, 0.);
+    transform.set_translation_xyz(PLAYFIELD_WIDTH * 0.5, PLAYFIELD_HEIGHT * 0.93, 0.);
 
     world
         .create_entity()
@@ -249,3 +328,21 @@ pub fn init_level_title(world: &mut World, filename: &str) {
         .with(Transparent)
         .build();
 }
+
+/// Loads a level's spawn timeline (scaled for the difficulty) for the `SpawnerSystem`.
+pub fn init_spawn_timeline(world: &mut World, level_key: &str, modifiers: SpawnModifiers) {
+    let timeline = get_spawn_timeline(world, level_key).scaled(&modifiers);
+
+    world.insert(SpawnTimelineResource::new(timeline));
+    world.insert(SpawnRequestsResource::default());
+}
+
+/// Displays the level background and title from a level definition and creates its ability bar.
+pub fn init_level_from_definition(world: &mut World, definition: &LevelDefinition) {
+    init_level_background(world, &definition.background);
+
+    init_level_title(world, &definition.title);
+
+    let abilities = create_abilities_from_definition(world, definition);
+    init_abilities_bar(world, abilities);
+}
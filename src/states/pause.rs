@@ -0,0 +1,168 @@
+use crate::states::main_menu::MainMenuState;
+use crate::states::start_level;
+use crate::*;
+
+use amethyst::core::ecs::DenseVecStorage;
+use amethyst::input::{is_key_down, VirtualKeyCode};
+use amethyst::ui::{Anchor, LineMode, Stretch, UiButton, UiEventType, UiText, UiTransform};
+
+/// The UI layer the pause menu is drawn on (above everything in the levels).
+const PAUSE_MENU_LAYER: f32 = 20.0;
+
+/// Whether a level is paused. Systems outside of the level dispatchers (like the ability bar) check this.
+#[derive(Default)]
+pub struct PausedResource {
+    pub paused: bool,
+}
+
+/// Tags the pause menu's background and title so they can be deleted on resume.
+pub struct PauseMenuComponent;
+impl Component for PauseMenuComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Pushed on top of a level to pause it.
+/// The level's dispatcher and timer are frozen because the level stops getting updated.
+pub struct PauseState {
+    /// The key of the paused level, used to restart it.
+    level_key: String,
+    resume_button: Option<UiButton>,
+    restart_button: Option<UiButton>,
+    quit_button: Option<UiButton>,
+}
+
+impl PauseState {
+    pub fn new(level_key: &str) -> Self {
+        PauseState {
+            level_key: level_key.to_string(),
+            resume_button: None,
+            restart_button: None,
+            quit_button: None,
+        }
+    }
+}
+
+/// Creates the dimmed background and "Paused" title.
+fn init_pause_menu_background(world: &mut World) {
+    let font = get_main_font(world);
+
+    // Stretched so it still covers the whole window after a resize.
+    world
+        .create_entity()
+        .with(PauseMenuComponent)
+        .with(
+            UiTransform::new(
+                "pause_background".to_string(),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.0,
+                0.0,
+                PAUSE_MENU_LAYER,
+                0.0,
+                0.0,
+            )
+            .with_stretch(Stretch::XY {
+                x_margin: 0.0,
+                y_margin: 0.0,
+                keep_aspect_ratio: false,
+            }),
+        )
+        .with(create_ui_color_from_rgba(0, 0, 0, 0.6))
+        .build();
+
+    world
+        .create_entity()
+        .with(PauseMenuComponent)
+        .with(UiTransform::new(
+            "pause_title".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            0.0,
+            150.0,
+            PAUSE_MENU_LAYER + 1.0,
+            600.0,
+            50.0,
+        ))
+        .with(UiText::new(
+            font,
+            "PAUSED".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            40.0,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build();
+}
+
+impl SimpleState for PauseState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        world.register::<PauseMenuComponent>();
+
+        world.write_resource::<PausedResource>().paused = true;
+
+        init_pause_menu_background(world);
+
+        let layer = PAUSE_MENU_LAYER + 1.0;
+
+        self.resume_button = Some(create_centered_button(world, "Resume", 60.0, layer));
+        self.restart_button = Some(create_centered_button(world, "Restart", -20.0, layer));
+        self.quit_button = Some(create_centered_button(world, "Quit to menu", -100.0, layer));
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        world.write_resource::<PausedResource>().paused = false;
+
+        delete_all_entities_with_component::<PauseMenuComponent>(world);
+
+        for button in [&self.resume_button, &self.restart_button, &self.quit_button]
+            .iter()
+            .copied()
+            .flatten()
+        {
+            delete_ui_button(world, button);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match event {
+            StateEvent::Window(event) => {
+                if is_key_down(&event, VirtualKeyCode::Escape) {
+                    Trans::Pop
+                } else {
+                    Trans::None
+                }
+            }
+            StateEvent::Ui(ui_event) => {
+                if ui_event.event_type != UiEventType::Click {
+                    return Trans::None;
+                }
+
+                let is_target = |button: &Option<UiButton>| {
+                    button.as_ref().unwrap().image_entity == ui_event.target
+                };
+
+                if is_target(&self.resume_button) {
+                    Trans::Pop
+                } else if is_target(&self.restart_button) {
+                    Trans::Sequence(vec![Trans::Pop, start_level(&self.level_key)])
+                } else if is_target(&self.quit_button) {
+                    Trans::Sequence(vec![
+                        Trans::Pop,
+                        Trans::Replace(Box::new(MainMenuState::default())),
+                    ])
+                } else {
+                    Trans::None
+                }
+            }
+            _ => Trans::None,
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use crate::playfield::Playfield;
+use crate::resources::abilities::AbilitiesResource;
+use crate::resources::data_dir::DataDirResource;
+use crate::resources::difficulty::{Difficulty, DifficultyResource};
+use crate::resources::replay::{
+    deserialize_bindings, last_replay_path, recorded_keys, save_replay, serialize_bindings,
+    RecordedFrame, Replay, ReplayPlaybackResource, RECORDED_MOUSE_BUTTONS,
+};
+use crate::resources::rng::{RngResource, SeedResource};
+use crate::states::main_menu::MainMenuState;
+use crate::states::{create_level_state, BoxedState};
+use crate::systems::ability_bar::clicked_ability_index;
+
+use amethyst::core::shrev::EventChannel;
+use amethyst::core::Time;
+use amethyst::input::{is_key_down, Bindings, InputEvent, InputHandler, StringBindings};
+use amethyst::prelude::*;
+use amethyst::ui::UiEventType;
+use amethyst::window::ScreenDimensions;
+use amethyst::winit::dpi::LogicalPosition;
+use amethyst::winit::{
+    DeviceId, ElementState, Event, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent,
+    WindowId,
+};
+
+/// Returns the index of the clicked ability if the event is a click on the ability bar.
+fn ability_click(world: &World, event: &StateEvent) -> Option<u8> {
+    if let StateEvent::Ui(ui_event) = event {
+        if ui_event.event_type == UiEventType::Click {
+            if let Some(abilities) = world.try_fetch::<AbilitiesResource>() {
+                return clicked_ability_index(&*abilities, ui_event.target).map(|i| i as u8);
+            }
+        }
+    }
+
+    None
+}
+
+/// Wraps a level state and records the player's input every frame.
+/// The recording is saved to `replays/last_{level_key}.replay` in the data directory when the level stops.
+pub struct RecordingState {
+    inner: BoxedState,
+    replay: Replay,
+    /// The keys recorded every frame (see `recorded_keys`).
+    keys: Vec<VirtualKeyCode>,
+    /// Ability clicks since the last recorded frame.
+    ability_clicks: Vec<u8>,
+}
+
+impl RecordingState {
+    pub fn new(level_key: &str, inner: BoxedState) -> Self {
+        RecordingState {
+            inner,
+            replay: Replay {
+                level_key: level_key.to_string(),
+                ..Default::default()
+            },
+            keys: Vec::new(),
+            ability_clicks: Vec::new(),
+        }
+    }
+}
+
+impl State<GameData<'static, 'static>, StateEvent> for RecordingState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        let StateData { world, data } = data;
+
+        self.inner.on_start(StateData::new(world, data));
+
+        // The level creates its RNG when it starts.
+        self.replay.seed = world.read_resource::<RngResource>().seed;
+        self.replay.difficulty = world
+            .read_resource::<DifficultyResource>()
+            .difficulty
+            .name()
+            .to_string();
+
+        let input = world.read_resource::<InputHandler<StringBindings>>();
+        self.replay.bindings = serialize_bindings(&input.bindings);
+        self.keys = recorded_keys(&input.bindings);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        let StateData { world, data } = data;
+
+        self.inner.on_stop(StateData::new(world, data));
+
+        let path = last_replay_path(
+            &world.read_resource::<DataDirResource>().path,
+            &self.replay.level_key,
+        );
+
+        match save_replay(&path, &self.replay) {
+            Ok(()) => log::info!("Saved replay to {}", path.display()),
+            Err(error) => log::error!("Couldn't save replay to {}: {:?}", path.display(), error),
+        }
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        self.inner.on_pause(data);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        self.inner.on_resume(data);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'static, 'static>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let Some(index) = ability_click(data.world, &event) {
+            self.ability_clicks.push(index);
+        }
+
+        self.inner.handle_event(data, event)
+    }
+
+    fn fixed_update(&mut self, data: StateData<'_, GameData<'static, 'static>>) -> SimpleTrans {
+        self.inner.fixed_update(data)
+    }
+
+    fn update(&mut self, data: StateData<'_, GameData<'static, 'static>>) -> SimpleTrans {
+        let StateData { world, data } = data;
+
+        let mut frame = RecordedFrame::capture(
+            &*world.read_resource::<InputHandler<StringBindings>>(),
+            &self.keys,
+            &Playfield::from_dimensions(&world.read_resource::<ScreenDimensions>()),
+            world.read_resource::<Time>().delta_seconds(),
+        );
+        frame.ability_clicks = std::mem::replace(&mut self.ability_clicks, Vec::new());
+
+        self.replay.frames.push(frame);
+
+        self.inner.update(StateData::new(world, data))
+    }
+
+    fn shadow_fixed_update(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        self.inner.shadow_fixed_update(data);
+    }
+
+    fn shadow_update(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        self.inner.shadow_update(data);
+    }
+}
+
+/// Creates the window events that turn the input handler's state into the recorded frame's state.
+fn frame_events(
+    input: &InputHandler<StringBindings>,
+    keys: &[VirtualKeyCode],
+    playfield: &Playfield,
+    frame: &RecordedFrame,
+) -> Vec<Event> {
+    // These ids are never compared by the input handler.
+    let window_id = unsafe { WindowId::dummy() };
+    let device_id = unsafe { DeviceId::dummy() };
+
+    let element_state = |down: bool| {
+        if down {
+            ElementState::Pressed
+        } else {
+            ElementState::Released
+        }
+    };
+
+    let mut events = Vec::new();
+
+    // A frame recorded with the mouse outside the window (after the window lost focus, or with the cursor left over
+    // from the main menu) has to take the cursor away. Losing focus is the only event that does, but it also
+    // releases every key and button, so the recorded ones are pressed again below.
+    let focus_lost = frame.mouse_position.is_none() && input.mouse_position().is_some();
+    if focus_lost {
+        events.push(WindowEvent::Focused(false));
+    }
+
+    if let Some((x, y)) = frame.window_mouse_position(playfield) {
+        events.push(WindowEvent::CursorMoved {
+            device_id,
+            position: LogicalPosition::new(x as f64, y as f64),
+            modifiers: ModifiersState::default(),
+        });
+    }
+
+    for (i, key) in keys.iter().enumerate() {
+        if frame.key_is_down(i) != (!focus_lost && input.key_is_down(*key)) {
+            events.push(WindowEvent::KeyboardInput {
+                device_id,
+                input: KeyboardInput {
+                    scancode: 0,
+                    state: element_state(frame.key_is_down(i)),
+                    virtual_keycode: Some(*key),
+                    modifiers: ModifiersState::default(),
+                },
+            });
+        }
+    }
+
+    for (i, button) in RECORDED_MOUSE_BUTTONS.iter().enumerate() {
+        if frame.mouse_button_is_down(i) != (!focus_lost && input.mouse_button_is_down(*button)) {
+            events.push(WindowEvent::MouseInput {
+                device_id,
+                state: element_state(frame.mouse_button_is_down(i)),
+                button: *button,
+                modifiers: ModifiersState::default(),
+            });
+        }
+    }
+
+    events
+        .into_iter()
+        .map(|event| Event::WindowEvent { window_id, event })
+        .collect()
+}
+
+/// Feeds a recorded frame's delta time and input into the world.
+fn play_frame(world: &mut World, keys: &[VirtualKeyCode], frame: &RecordedFrame) {
+    world
+        .write_resource::<Time>()
+        .set_delta_seconds(frame.delta_seconds);
+
+    let playfield = Playfield::from_dimensions(&world.read_resource::<ScreenDimensions>());
+
+    let mut input = world.write_resource::<InputHandler<StringBindings>>();
+    let mut input_events = world.write_resource::<EventChannel<InputEvent<StringBindings>>>();
+
+    for event in frame_events(&*input, keys, &playfield, frame) {
+        // Recorded mouse positions are already in physical pixels.
+        input.send_event(&event, &mut *input_events, 1.0);
+    }
+}
+
+/// Plays back a recorded run of a level.
+/// Every frame the recorded delta time and input are fed into the world before the level updates.
+pub struct ReplayState {
+    inner: BoxedState,
+    replay: Replay,
+    /// The keys recorded every frame (see `recorded_keys`).
+    keys: Vec<VirtualKeyCode>,
+    /// The player's bindings, put back when the replay stops.
+    player_bindings: Option<Bindings<StringBindings>>,
+    /// The difficulty the player picked, put back when the replay stops.
+    player_difficulty: Difficulty,
+    /// Index of the next frame to play back.
+    next_frame: usize,
+    /// Ability clicks since the last frame, checked against the recording.
+    ability_clicks: Vec<u8>,
+    /// Whether the playback has stopped matching the recording.
+    desynced: bool,
+}
+
+impl ReplayState {
+    pub fn new(replay: Replay) -> Self {
+        ReplayState {
+            inner: create_level_state(&replay.level_key),
+            replay,
+            keys: Vec::new(),
+            player_bindings: None,
+            player_difficulty: Difficulty::default(),
+            next_frame: 0,
+            ability_clicks: Vec::new(),
+            desynced: false,
+        }
+    }
+
+    /// Checks the ability clicks the playback caused against the ones in the recording.
+    /// Clicks show up a frame after the input that caused them, so they're compared with the previous frame.
+    fn check_ability_clicks(&mut self) {
+        if self.next_frame == 0 || self.desynced {
+            return;
+        }
+
+        let expected = &self.replay.frames[self.next_frame - 1].ability_clicks;
+
+        if *expected != self.ability_clicks {
+            log::warn!(
+                "Replay desynced at frame {}: expected ability clicks {:?} but got {:?}",
+                self.next_frame - 1,
+                expected,
+                self.ability_clicks
+            );
+
+            self.desynced = true;
+        }
+    }
+}
+
+impl State<GameData<'static, 'static>, StateEvent> for ReplayState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        let StateData { world, data } = data;
+
+        // Play the replay with the bindings it was recorded with.
+        match deserialize_bindings(&self.replay.bindings) {
+            Ok(bindings) => {
+                let mut input = world.write_resource::<InputHandler<StringBindings>>();
+
+                self.keys = recorded_keys(&bindings);
+                self.player_bindings = Some(std::mem::replace(&mut input.bindings, bindings));
+            }
+            Err(error) => log::error!("Couldn't read the replay's input bindings: {}", error),
+        }
+
+        // Play the replay on the difficulty it was recorded on.
+        let difficulty = Difficulty::from_name(&self.replay.difficulty).unwrap_or_default();
+        self.player_difficulty = std::mem::replace(
+            &mut world.write_resource::<DifficultyResource>().difficulty,
+            difficulty,
+        );
+
+        // Make the level use the recorded seed, then put back the one from the command line.
+        let command_line_seed = world.read_resource::<SeedResource>().seed;
+        world.write_resource::<SeedResource>().seed = Some(self.replay.seed);
+
+        world.write_resource::<ReplayPlaybackResource>().active = true;
+
+        self.inner.on_start(StateData::new(world, data));
+
+        world.write_resource::<SeedResource>().seed = command_line_seed;
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        let StateData { world, data } = data;
+
+        self.inner.on_stop(StateData::new(world, data));
+
+        if let Some(bindings) = self.player_bindings.take() {
+            world
+                .write_resource::<InputHandler<StringBindings>>()
+                .bindings = bindings;
+        }
+
+        world.write_resource::<DifficultyResource>().difficulty = self.player_difficulty;
+        world.write_resource::<ReplayPlaybackResource>().active = false;
+
+        if !self.desynced {
+            log::info!(
+                "Replay of {} matched the recording for {} frames",
+                self.replay.level_key,
+                self.next_frame
+            );
+        }
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        self.inner.on_pause(data);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        self.inner.on_resume(data);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'static, 'static>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let Some(index) = ability_click(data.world, &event) {
+            self.ability_clicks.push(index);
+        }
+
+        match event {
+            // Escape stops the playback.
+            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => {
+                log::info!("Replay of {} stopped", self.replay.level_key);
+
+                Trans::Replace(Box::new(MainMenuState::default()))
+            }
+            // The level only sees the UI events the recorded input causes (like ability clicks).
+            // Live input and focus changes would pause the level or change what's played back.
+            StateEvent::Ui(_) => self.inner.handle_event(data, event),
+            _ => Trans::None,
+        }
+    }
+
+    fn fixed_update(&mut self, data: StateData<'_, GameData<'static, 'static>>) -> SimpleTrans {
+        self.inner.fixed_update(data)
+    }
+
+    fn update(&mut self, data: StateData<'_, GameData<'static, 'static>>) -> SimpleTrans {
+        self.check_ability_clicks();
+        self.ability_clicks.clear();
+
+        let StateData { world, data } = data;
+
+        if let Some(frame) = self.replay.frames.get(self.next_frame) {
+            play_frame(world, &self.keys, frame);
+            self.next_frame += 1;
+
+            self.inner.update(StateData::new(world, data))
+        } else {
+            log::info!("Replay of {} finished", self.replay.level_key);
+
+            Trans::Replace(Box::new(MainMenuState::default()))
+        }
+    }
+
+    fn shadow_fixed_update(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        self.inner.shadow_fixed_update(data);
+    }
+
+    fn shadow_update(&mut self, data: StateData<'_, GameData<'static, 'static>>) {
+        self.inner.shadow_update(data);
+    }
+}
@@ -0,0 +1,491 @@
+use crate::input::TextEntryActiveResource;
+use crate::resources::difficulty::{current_score_key, DifficultyResource};
+use crate::resources::high_scores::{update_high_score_if_greater, CurrentLevelScoreResource};
+use crate::resources::levels::level_name;
+use crate::resources::replay::ReplayPlaybackResource;
+use crate::resources::rng::RngResource;
+use crate::resources::save_data::{LeaderboardEntry, SaveDataResource};
+use crate::resources::unlocks::update_unlocks;
+use crate::states::campaign::{record_campaign_level, CampaignResults};
+use crate::states::main_menu::MainMenuState;
+use crate::states::start_level;
+use crate::states::LevelSecondsResource;
+use crate::*;
+
+use amethyst::core::ecs::{DenseVecStorage, Entity};
+use amethyst::input::{is_key_down, VirtualKeyCode};
+use amethyst::ui::{Anchor, LineMode, UiButton, UiEventType, UiText, UiTransform};
+use amethyst::winit::{Event, WindowEvent};
+
+/// The longest name that can be entered for the leaderboard.
+const MAX_NAME_LENGTH: usize = 16;
+
+/// How a level run went. Shown on the results screen.
+pub struct LevelResults {
+    pub level_key: String,
+    /// The key the run's high score and leaderboard entry are saved under (depends on the difficulty).
+    pub score_key: String,
+    pub score: u64,
+    /// The level's high score before this run.
+    pub previous_high_score: u64,
+    /// Stats that explain the score, like "Fires put out". Shown in order.
+    pub breakdown: Vec<(String, u64)>,
+    /// The seed of the run's `RngResource`.
+    pub seed: u64,
+    pub difficulty: String,
+    /// How the campaign is going, if the level was played as part of one.
+    pub campaign: Option<CampaignResults>,
+    /// Keys of the levels this run unlocked.
+    pub unlocked_levels: Vec<String>,
+    /// Whether the run was a replay being played back. Nothing about it is saved.
+    pub replay: bool,
+}
+
+impl LevelResults {
+    pub fn is_new_high_score(&self) -> bool {
+        !self.replay && self.score > self.previous_high_score
+    }
+}
+
+/// Ends a level: saves the run's stats, the high score (if it's higher), any unlocked levels and the campaign progress,
+/// then moves on to the results screen.
+/// A replay being played back only shows its results, so watching a run doesn't count it again.
+pub fn finish_level(
+    world: &mut World,
+    level_key: &str,
+    breakdown: Vec<(String, u64)>,
+) -> SimpleTrans {
+    let score = world.read_resource::<CurrentLevelScoreResource>().score;
+    let seconds = world
+        .read_resource::<LevelSecondsResource>()
+        .seconds_elapsed;
+
+    let score_key = current_score_key(world, level_key);
+
+    let seed = world.read_resource::<RngResource>().seed;
+    let difficulty = world.read_resource::<DifficultyResource>().difficulty;
+
+    if world.read_resource::<ReplayPlaybackResource>().active {
+        let previous_high_score = world
+            .read_resource::<SaveDataResource>()
+            .data
+            .get_score(&score_key);
+
+        return Trans::Replace(Box::new(LevelResultsState::new(LevelResults {
+            level_key: level_key.to_string(),
+            score_key,
+            score,
+            previous_high_score,
+            breakdown,
+            seed,
+            difficulty: difficulty.name().to_string(),
+            campaign: None,
+            unlocked_levels: Vec::new(),
+            replay: true,
+        })));
+    }
+
+    let previous_high_score = {
+        let mut save = world.write_resource::<SaveDataResource>();
+
+        save.data.record_finished_run(level_key, seconds);
+        save.save();
+
+        save.data.get_score(&score_key)
+    };
+
+    update_high_score_if_greater(world, level_key);
+
+    let unlocked_levels = update_unlocks(world);
+
+    let campaign = record_campaign_level(world, level_key, score);
+
+    Trans::Replace(Box::new(LevelResultsState::new(LevelResults {
+        level_key: level_key.to_string(),
+        score_key,
+        score,
+        previous_high_score,
+        breakdown,
+        seed,
+        difficulty: difficulty.name().to_string(),
+        campaign,
+        unlocked_levels,
+        replay: false,
+    })))
+}
+
+/// Tags the results screen's texts so they can be deleted when it stops.
+pub struct LevelResultsComponent;
+impl Component for LevelResultsComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Tags the text showing the name being entered for the leaderboard.
+pub struct NameEntryTextComponent;
+impl Component for NameEntryTextComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Shown after a level ends with the final score, a breakdown of it and whether it's a new high score.
+/// If the score makes it onto the leaderboard, the player can type their name for it.
+pub struct LevelResultsState {
+    results: LevelResults,
+    /// The name being typed, if the score qualifies for the leaderboard and hasn't been saved yet.
+    name_entry: Option<String>,
+    /// Retries the level, or moves on to the next one after passing a campaign level. None once the campaign is complete.
+    retry_button: Option<UiButton>,
+    menu_button: Option<UiButton>,
+}
+
+impl LevelResultsState {
+    pub fn new(results: LevelResults) -> Self {
+        LevelResultsState {
+            results,
+            name_entry: None,
+            retry_button: None,
+            menu_button: None,
+        }
+    }
+
+    /// Sets the text showing the name being entered.
+    fn set_name_entry_text(&self, world: &mut World, text: String) {
+        let mut ui_texts = world.write_storage::<UiText>();
+        let name_entry_texts = world.read_storage::<NameEntryTextComponent>();
+
+        for (ui_text, _) in (&mut ui_texts, &name_entry_texts).join() {
+            ui_text.text = text.clone();
+        }
+    }
+
+    /// Shows the name being typed with a cursor.
+    fn refresh_name_entry(&self, world: &mut World) {
+        if let Some(name) = &self.name_entry {
+            self.set_name_entry_text(world, format!("Name: {}_", name));
+        }
+    }
+
+    /// Adds the run to the leaderboard under the entered name (if there is one to add).
+    fn submit_name(&mut self, world: &mut World) {
+        let name = match self.name_entry.take() {
+            Some(name) => name,
+            None => return,
+        };
+
+        let name = if name.trim().is_empty() {
+            world
+                .read_resource::<SaveDataResource>()
+                .data
+                .player_name()
+                .to_string()
+        } else {
+            name.trim().to_string()
+        };
+
+        {
+            let mut save = world.write_resource::<SaveDataResource>();
+
+            save.data.add_leaderboard_entry(
+                &self.results.score_key,
+                LeaderboardEntry {
+                    name: name.clone(),
+                    score: self.results.score,
+                    timestamp: chrono::Local::now().timestamp(),
+                    seed: self.results.seed,
+                    difficulty: self.results.difficulty.clone(),
+                },
+            );
+            save.data.settings.player_name = name.clone();
+            save.save();
+        }
+
+        self.set_name_entry_text(world, format!("Saved to the leaderboard as {}!", name));
+    }
+
+    /// Types into the name entry (if the player is entering a name).
+    fn handle_name_entry_event(&mut self, world: &mut World, event: &Event) {
+        let name = match self.name_entry.as_mut() {
+            Some(name) => name,
+            None => return,
+        };
+
+        if is_key_down(event, VirtualKeyCode::Return) {
+            self.submit_name(world);
+            return;
+        }
+
+        if is_key_down(event, VirtualKeyCode::Back) {
+            name.pop();
+        } else if let Event::WindowEvent {
+            event: WindowEvent::ReceivedCharacter(character),
+            ..
+        } = event
+        {
+            let allowed = character.is_alphanumeric() || " -_".contains(*character);
+
+            if !allowed || name.chars().count() >= MAX_NAME_LENGTH {
+                return;
+            }
+
+            name.push(*character);
+        } else {
+            return;
+        }
+
+        self.refresh_name_entry(world);
+    }
+}
+
+/// Creates a line of text on the results screen. The y position is relative to the middle of the screen.
+fn create_results_text(
+    world: &mut World,
+    text: String,
+    y: f32,
+    font_size: f32,
+    color: [f32; 4],
+) -> Entity {
+    let font = get_main_font(world);
+
+    world
+        .create_entity()
+        .with(LevelResultsComponent)
+        .with(UiTransform::new(
+            format!("results_text_{}", y),
+            Anchor::Middle,
+            Anchor::Middle,
+            0.0,
+            y,
+            1.0,
+            800.0,
+            font_size * 1.5,
+        ))
+        .with(UiText::new(
+            font,
+            text,
+            color,
+            font_size,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build()
+}
+
+/// Creates the score, high score and breakdown texts.
+fn init_results_text(world: &mut World, results: &LevelResults) {
+    let white = [1.0, 1.0, 1.0, 1.0];
+    let gold = [0.95, 0.71, 0.29, 1.0];
+
+    create_results_text(
+        world,
+        format!(
+            "{} ({})",
+            if results.replay {
+                "REPLAY OVER"
+            } else {
+                "LEVEL OVER"
+            },
+            results.difficulty
+        ),
+        250.0,
+        40.0,
+        white,
+    );
+
+    create_results_text(
+        world,
+        format!("Score: {}", results.score),
+        190.0,
+        30.0,
+        white,
+    );
+
+    if results.is_new_high_score() {
+        create_results_text(world, "New high score!".to_string(), 145.0, 25.0, gold);
+    } else {
+        create_results_text(
+            world,
+            format!("High Score: {}", results.previous_high_score),
+            145.0,
+            25.0,
+            white,
+        );
+    }
+
+    let mut y = 90.0;
+    for (stat, value) in results.breakdown.iter() {
+        create_results_text(world, format!("{}: {}", stat, value), y, 21.5, white);
+        y -= 35.0;
+    }
+
+    if let Some(campaign) = &results.campaign {
+        let (text, color) = if campaign.is_new_high_score() {
+            (
+                format!(
+                    "Campaign complete! Final score: {} - New high score!",
+                    campaign.total_score
+                ),
+                gold,
+            )
+        } else if campaign.is_complete() {
+            (
+                format!("Campaign complete! Final score: {}", campaign.total_score),
+                gold,
+            )
+        } else if campaign.passed {
+            (
+                format!("Passed! Campaign score: {}", campaign.total_score),
+                white,
+            )
+        } else {
+            (
+                format!(
+                    "Score {} to pass! Campaign score: {}",
+                    campaign.pass_score, campaign.total_score
+                ),
+                [1.0, 0.4, 0.4, 1.0],
+            )
+        };
+
+        create_results_text(world, text, -262.0, 20.0, color);
+    }
+
+    if !results.unlocked_levels.is_empty() {
+        let names: Vec<&str> = results
+            .unlocked_levels
+            .iter()
+            .map(|key| level_name(key))
+            .collect();
+
+        create_results_text(
+            world,
+            format!("Unlocked {}!", names.join(" and ")),
+            -290.0,
+            18.0,
+            gold,
+        );
+    }
+}
+
+/// Creates the text showing the name being entered for the leaderboard (set by `refresh_name_entry`).
+fn init_name_entry_text(world: &mut World) {
+    let gold = [0.95, 0.71, 0.29, 1.0];
+
+    let entity = create_results_text(world, String::new(), -55.0, 21.5, gold);
+
+    world
+        .write_storage::<NameEntryTextComponent>()
+        .insert(entity, NameEntryTextComponent)
+        .expect("Failed to tag the name entry text!");
+}
+
+impl SimpleState for LevelResultsState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        world.register::<LevelResultsComponent>();
+        world.register::<NameEntryTextComponent>();
+
+        init_results_text(world, &self.results);
+
+        // Replays were already offered to the leaderboard when they were played.
+        let qualifies = !self.results.replay
+            && world
+                .read_resource::<SaveDataResource>()
+                .data
+                .qualifies_for_leaderboard(&self.results.score_key, self.results.score);
+
+        if qualifies {
+            // Suggest the name entered last time.
+            self.name_entry = Some(
+                world
+                    .read_resource::<SaveDataResource>()
+                    .data
+                    .player_name()
+                    .to_string(),
+            );
+
+            init_name_entry_text(world);
+            self.refresh_name_entry(world);
+        }
+
+        self.retry_button = match &self.results.campaign {
+            // Retrying would start a normal run, not the replay.
+            _ if self.results.replay => None,
+            Some(campaign) if campaign.is_complete() => None,
+            Some(campaign) if campaign.passed => {
+                Some(create_centered_button(world, "Next level", -120.0, 1.0))
+            }
+            _ => Some(create_centered_button(world, "Retry", -120.0, 1.0)),
+        };
+        self.menu_button = Some(create_centered_button(world, "Main menu", -200.0, 1.0));
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        world.write_resource::<TextEntryActiveResource>().active = false;
+
+        delete_all_entities_with_component::<LevelResultsComponent>(world);
+
+        for button in [&self.retry_button, &self.menu_button]
+            .iter()
+            .copied()
+            .flatten()
+        {
+            delete_ui_button(world, button);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let world = data.world;
+
+        match event {
+            StateEvent::Window(event) => {
+                self.handle_name_entry_event(world, &event);
+
+                Trans::None
+            }
+            StateEvent::Ui(ui_event) => {
+                if ui_event.event_type != UiEventType::Click {
+                    return Trans::None;
+                }
+
+                let is_target = |button: &Option<UiButton>| {
+                    button.as_ref().map(|button| button.image_entity) == Some(ui_event.target)
+                };
+
+                let trans = if is_target(&self.retry_button) {
+                    // Moves on to the next level after passing a campaign level.
+                    let next_level = match &self.results.campaign {
+                        Some(campaign) if campaign.passed => campaign.next_level.as_ref(),
+                        _ => None,
+                    };
+
+                    start_level(next_level.unwrap_or(&self.results.level_key))
+                } else if is_target(&self.menu_button) {
+                    Trans::Replace(Box::new(MainMenuState::default()))
+                } else {
+                    return Trans::None;
+                };
+
+                // Leaving without pressing Enter still saves the run with the typed name.
+                self.submit_name(world);
+
+                trans
+            }
+            _ => Trans::None,
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        // Typing the mute hotkey into a name shouldn't mute the game.
+        data.world
+            .write_resource::<TextEntryActiveResource>()
+            .active = self.name_entry.is_some();
+
+        Trans::None
+    }
+}
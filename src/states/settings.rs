@@ -0,0 +1,270 @@
+use crate::audio::{apply_audio_settings, next_volume};
+use crate::resources::display::{apply_display_settings, VSYNC_REFRESH_RATE};
+use crate::resources::save_data::{AudioSettings, SaveDataResource, SettingsData};
+use crate::states::main_menu::MainMenuState;
+use crate::*;
+
+use amethyst::core::ecs::DenseVecStorage;
+use amethyst::input::{is_key_down, VirtualKeyCode};
+use amethyst::ui::{Anchor, LineMode, UiButton, UiEventType, UiText, UiTransform};
+
+/// The settings on the settings menu, one button each from the top.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Setting {
+    DisplayMode,
+    WindowSize,
+    Vsync,
+    FrameCap,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Mute,
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting::DisplayMode,
+    Setting::WindowSize,
+    Setting::Vsync,
+    Setting::FrameCap,
+    Setting::MasterVolume,
+    Setting::MusicVolume,
+    Setting::SfxVolume,
+    Setting::Mute,
+];
+
+/// A volume as a percentage, like "80%".
+fn volume_text(volume: f32) -> String {
+    format!("{}%", (volume * 100.0).round())
+}
+
+impl Setting {
+    /// The text on the setting's button, like "VSync: On".
+    fn text(self, settings: &SettingsData) -> String {
+        let display = &settings.display;
+        let audio = &settings.audio;
+
+        match self {
+            Setting::DisplayMode => {
+                if display.fullscreen {
+                    "Display: Fullscreen".to_string()
+                } else {
+                    "Display: Windowed".to_string()
+                }
+            }
+            Setting::WindowSize => format!(
+                "Window size: {}x{}",
+                display.window_width, display.window_height
+            ),
+            Setting::Vsync => format!("VSync: {}", if display.vsync { "On" } else { "Off" }),
+            Setting::FrameCap => {
+                if display.vsync {
+                    format!("Frame cap: VSync ({} FPS)", VSYNC_REFRESH_RATE)
+                } else if display.frame_cap == 0 {
+                    "Frame cap: Unlimited".to_string()
+                } else {
+                    format!("Frame cap: {} FPS", display.frame_cap)
+                }
+            }
+            Setting::MasterVolume => format!("Master volume: {}", volume_text(audio.master_volume)),
+            Setting::MusicVolume => format!("Music volume: {}", volume_text(audio.music_volume)),
+            Setting::SfxVolume => {
+                format!("Sound effects volume: {}", volume_text(audio.sfx_volume))
+            }
+            Setting::Mute => format!("Mute all: {}", if audio.muted { "On" } else { "Off" }),
+        }
+    }
+
+    /// Moves the setting on to its next value.
+    fn next(self, settings: &mut SettingsData) {
+        let display = &mut settings.display;
+        let audio = &mut settings.audio;
+
+        match self {
+            Setting::DisplayMode => display.fullscreen = !display.fullscreen,
+            Setting::WindowSize => display.next_window_size(),
+            Setting::Vsync => display.vsync = !display.vsync,
+            Setting::FrameCap => display.next_frame_cap(),
+            Setting::MasterVolume => audio.master_volume = next_volume(audio.master_volume),
+            Setting::MusicVolume => audio.music_volume = next_volume(audio.music_volume),
+            Setting::SfxVolume => audio.sfx_volume = next_volume(audio.sfx_volume),
+            Setting::Mute => audio.muted = !audio.muted,
+        }
+    }
+}
+
+/// Tags the settings menu's title so it can be deleted when it stops.
+pub struct SettingsMenuComponent;
+impl Component for SettingsMenuComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Lets the player change how the game is displayed and how loud it is. Clicking a setting moves it on to its next value.
+/// Changes are applied and saved right away.
+#[derive(Default)]
+pub struct SettingsState {
+    /// One button per entry in `SETTINGS`.
+    setting_buttons: Vec<UiButton>,
+    back_button: Option<UiButton>,
+    /// The audio settings the buttons show, so they can be refreshed when the mute hotkey is pressed.
+    shown_audio: Option<AudioSettings>,
+}
+
+/// Creates a button in the settings menu. The y position is relative to the top of the screen.
+fn create_settings_button(world: &mut World, text: &str, y: f32) -> UiButton {
+    let font = get_main_font(world);
+
+    let (_, button) = UiButtonBuilder::<(), u32>::new(text)
+        .with_font(font)
+        .with_font_size(20.0)
+        .with_position(0.0, y)
+        .with_size(360.0, 42.0)
+        .with_anchor(Anchor::TopMiddle)
+        .with_image(create_ui_color_from_rgba(195, 130, 51, 1.0))
+        .with_hover_image(create_ui_color_from_rgba(195, 130, 51, 0.5))
+        .build_from_world(&world);
+
+    button
+}
+
+/// Creates the title.
+fn init_settings_text(world: &mut World) {
+    let font = get_main_font(world);
+
+    world
+        .create_entity()
+        .with(SettingsMenuComponent)
+        .with(UiTransform::new(
+            "settings_title".to_string(),
+            Anchor::TopMiddle,
+            Anchor::TopMiddle,
+            0.0,
+            -20.0,
+            1.0,
+            600.0,
+            50.0,
+        ))
+        .with(UiText::new(
+            font,
+            "SETTINGS".to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            35.0,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build();
+}
+
+impl SettingsState {
+    /// Updates the text of every setting's button.
+    fn refresh_setting_buttons(&mut self, world: &mut World) {
+        let settings = world
+            .read_resource::<SaveDataResource>()
+            .data
+            .settings
+            .clone();
+
+        for (setting, button) in SETTINGS.iter().zip(self.setting_buttons.iter()) {
+            if let Some(ui_text) = world.write_storage::<UiText>().get_mut(button.text_entity) {
+                ui_text.text = setting.text(&settings);
+            }
+        }
+
+        self.shown_audio = Some(settings.audio);
+    }
+}
+
+impl SimpleState for SettingsState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        world.register::<SettingsMenuComponent>();
+
+        init_settings_text(world);
+
+        for index in 0..SETTINGS.len() {
+            let y = -80.0 - index as f32 * 50.0;
+
+            let button = create_settings_button(world, "", y);
+            self.setting_buttons.push(button);
+        }
+
+        self.back_button = Some(create_settings_button(world, "Back", -530.0));
+
+        self.refresh_setting_buttons(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        delete_all_entities_with_component::<SettingsMenuComponent>(world);
+
+        for button in self.setting_buttons.drain(..) {
+            delete_ui_button(world, &button);
+        }
+
+        if let Some(button) = &self.back_button {
+            delete_ui_button(world, button);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let world = data.world;
+
+        match event {
+            StateEvent::Window(event) if is_key_down(&event, VirtualKeyCode::Escape) => {
+                Trans::Replace(Box::new(MainMenuState::default()))
+            }
+            StateEvent::Ui(ui_event) if ui_event.event_type == UiEventType::Click => {
+                if self.back_button.as_ref().map(|button| button.image_entity)
+                    == Some(ui_event.target)
+                {
+                    return Trans::Replace(Box::new(MainMenuState::default()));
+                }
+
+                if let Some(index) = self
+                    .setting_buttons
+                    .iter()
+                    .position(|button| button.image_entity == ui_event.target)
+                {
+                    {
+                        let mut save_data = world.write_resource::<SaveDataResource>();
+
+                        SETTINGS[index].next(&mut save_data.data.settings);
+                        save_data.save();
+                    }
+
+                    apply_display_settings(world);
+                    apply_audio_settings(world);
+                    self.refresh_setting_buttons(world);
+                }
+
+                Trans::None
+            }
+            _ => Trans::None,
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let world = &mut data.world;
+
+        // The mute hotkey works here too.
+        let audio_changed = self.shown_audio.as_ref()
+            != Some(
+                &world
+                    .read_resource::<SaveDataResource>()
+                    .data
+                    .settings
+                    .audio,
+            );
+
+        if audio_changed {
+            self.refresh_setting_buttons(world);
+        }
+
+        Trans::None
+    }
+}
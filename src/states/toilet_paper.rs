@@ -0,0 +1,230 @@
+use crate::*;
+
+use crate::resources::high_scores::highscores_keys::TOILET_PAPER;
+
+use crate::resources::high_scores::CurrentLevelScoreResource;
+use crate::states::results::finish_level;
+use crate::states::{
+    create_optional_systems_dispatcher, hud_text_layout, init_level_from_definition,
+    pause_on_escape_or_focus_lost, playfield_ui_transform, run_systems, LevelComponent,
+    LevelSecondsResource,
+};
+
+use crate::resources::difficulty::{scale, DifficultyResource};
+use crate::resources::levels::get_level_definition;
+use crate::resources::rng::init_level_rng;
+use crate::systems::toilet_paper::ToiletPaperSystem;
+use amethyst::core::ecs::DenseVecStorage;
+use amethyst::shred::Dispatcher;
+use amethyst::ui::{Anchor, LineMode, UiText};
+
+/// Tags a component as the toilet paper state text.
+pub struct ToiletPaperStateTextComponent;
+impl Component for ToiletPaperStateTextComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+fn init_toilet_paper_state_text(world: &mut World, max_stock: u64) {
+    let font = get_main_font(world);
+
+    let layout = hud_text_layout();
+    let transform = playfield_ui_transform(world, "toilet_paper_state", &layout);
+    let ui_text = UiText::new(
+        font,
+        format!("{} ROLLS / {} MAX STOCK", max_stock, max_stock),
+        [1.0, 1.0, 1.0, 1.0],
+        25.0,
+        LineMode::Single,
+        Anchor::Middle,
+    );
+
+    world
+        .create_entity()
+        .with(ToiletPaperStateTextComponent)
+        .with(LevelComponent)
+        .with(layout)
+        .with(transform)
+        .with(ui_text)
+        .build();
+}
+
+fn update_toilet_paper_state(world: &mut World, stock: u64, max_stock: u64) {
+    let mut ui_texts = world.write_storage::<UiText>();
+    let state_text_components = world.read_storage::<ToiletPaperStateTextComponent>();
+
+    for (ui_text, _) in (&mut ui_texts, &state_text_components).join() {
+        ui_text.text = format!("{} ROLLS / {} MAX STOCK", stock, max_stock);
+    }
+}
+
+/// A resource for storing some level state for the Toilet Paper Panic level.
+#[derive(Default)]
+pub struct ToiletPaperStateResource {
+    /// Rolls left on the shelves. The level ends when they run out.
+    pub stock: u64,
+    /// The most rolls the shelves can hold.
+    pub max_stock: u64,
+    /// Rolls grabbed by shoppers over the whole level.
+    pub rolls_taken: u64,
+    pub shoppers_turned_away: u64,
+    pub restock_packs_picked_up: u64,
+    /// Seconds between shopper spawns (scaled by the difficulty).
+    pub shopper_spawn_seconds: f32,
+    /// Seconds between restock packs appearing.
+    pub restock_pack_spawn_seconds: f32,
+    /// Seconds it takes for one more shopper to be able to spawn at a time.
+    pub shopper_ramp_seconds: f32,
+    /// Seconds it takes for the hoarder chance to go up by 100% (it's capped at 50%).
+    pub hoarder_ramp_seconds: f32,
+}
+
+impl ToiletPaperStateResource {
+    pub fn new(
+        max_stock: u64,
+        shopper_spawn_seconds: f32,
+        restock_pack_spawn_seconds: f32,
+        shopper_ramp_seconds: f32,
+        hoarder_ramp_seconds: f32,
+    ) -> Self {
+        ToiletPaperStateResource {
+            stock: max_stock,
+            max_stock,
+            shopper_spawn_seconds,
+            restock_pack_spawn_seconds,
+            shopper_ramp_seconds,
+            hoarder_ramp_seconds,
+            ..Default::default()
+        }
+    }
+
+    /// Takes rolls off the shelves and keeps track of how many were taken.
+    pub fn take_rolls(&mut self, rolls: u64) {
+        let new_stock = self.stock.saturating_sub(rolls);
+
+        self.rolls_taken += self.stock - new_stock;
+        self.stock = new_stock;
+    }
+
+    /// Puts rolls back on the shelves (up to the max stock).
+    pub fn restock(&mut self, rolls: u64) {
+        self.stock = (self.stock + rolls).min(self.max_stock);
+    }
+}
+
+#[derive(Default)]
+pub struct ToiletPaperState<'a, 'b> {
+    dispatcher: Option<Dispatcher<'a, 'b>>,
+}
+
+impl<'a, 'b> SimpleState for ToiletPaperState<'a, 'b> {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        let definition = get_level_definition(world, TOILET_PAPER);
+
+        let modifiers = world
+            .read_resource::<DifficultyResource>()
+            .difficulty
+            .modifiers();
+
+        let max_stock = scale(
+            definition
+                .max_stock
+                .expect("The Toilet Paper Panic level definition is missing max_stock!"),
+            modifiers.health_pool,
+        );
+
+        let shopper_spawn_seconds = definition
+            .shopper_spawn_seconds
+            .expect("The Toilet Paper Panic level definition is missing shopper_spawn_seconds!");
+
+        let restock_pack_spawn_seconds = definition.restock_pack_spawn_seconds.expect(
+            "The Toilet Paper Panic level definition is missing restock_pack_spawn_seconds!",
+        );
+
+        let shopper_ramp_seconds = definition
+            .shopper_ramp_seconds
+            .expect("The Toilet Paper Panic level definition is missing shopper_ramp_seconds!");
+
+        let hoarder_ramp_seconds = definition
+            .hoarder_ramp_seconds
+            .expect("The Toilet Paper Panic level definition is missing hoarder_ramp_seconds!");
+
+        init_toilet_paper_state_text(world, max_stock);
+
+        init_level_from_definition(world, &definition);
+
+        init_level_rng(world, &definition);
+
+        world.insert(CurrentLevelScoreResource::default());
+        world.insert(LevelSecondsResource::default());
+
+        // Init the resource storing data about the player's progress on the level
+        world.insert(ToiletPaperStateResource::new(
+            max_stock,
+            shopper_spawn_seconds * modifiers.shopper_interval,
+            restock_pack_spawn_seconds,
+            shopper_ramp_seconds,
+            hoarder_ramp_seconds,
+        ));
+
+        self.dispatcher = create_optional_systems_dispatcher(world, |builder, _| {
+            builder.add(ToiletPaperSystem::default(), "toilet_paper", &[])
+        });
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        delete_all_entities_with_component::<LevelComponent>(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        pause_on_escape_or_focus_lost(event, TOILET_PAPER)
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let world = &mut data.world;
+
+        let seconds_elapsed = world
+            .read_resource::<LevelSecondsResource>()
+            .seconds_elapsed;
+
+        // Update the score based on seconds elapsed.
+        world.write_resource::<CurrentLevelScoreResource>().score = seconds_elapsed as u64;
+
+        let (stock, max_stock) = {
+            let state = world.read_resource::<ToiletPaperStateResource>();
+
+            (state.stock, state.max_stock)
+        };
+
+        // End the level once the shelves are empty
+        if stock == 0 {
+            let breakdown = {
+                let state = world.read_resource::<ToiletPaperStateResource>();
+
+                vec![
+                    ("Seconds survived".to_string(), seconds_elapsed as u64),
+                    (
+                        "Shoppers turned away".to_string(),
+                        state.shoppers_turned_away,
+                    ),
+                    ("Rolls sold".to_string(), state.rolls_taken),
+                    (
+                        "Restock packs picked up".to_string(),
+                        state.restock_packs_picked_up,
+                    ),
+                ]
+            };
+
+            finish_level(world, TOILET_PAPER, breakdown)
+        } else {
+            run_systems(world, &mut self.dispatcher);
+            update_toilet_paper_state(world, stock, max_stock);
+            Trans::None
+        }
+    }
+}
@@ -4,22 +4,22 @@ use crate::systems::wildfires::WildfiresSystem;
 
 use crate::resources::high_scores::highscores_keys::WILDFIRES;
 
-use crate::resources::high_scores::{update_high_score_if_greater, CurrentLevelScoreResource};
-use crate::states::main_menu::MainMenuState;
+use crate::resources::high_scores::CurrentLevelScoreResource;
+use crate::states::results::finish_level;
 use crate::states::{
-    create_optional_systems_dispatcher, init_level_background, init_level_title,
-    return_to_main_menu_on_escape, run_systems, LevelComponent, LevelSecondsResource,
+    create_optional_systems_dispatcher, hud_text_layout, init_level_from_definition,
+    init_spawn_timeline, pause_on_escape_or_focus_lost, playfield_ui_transform, run_systems,
+    LevelComponent, LevelSecondsResource,
 };
 
-use crate::resources::abilities::{
-    AbilitiesResource, Ability, AbilityInfo, AbilityState, AbilityType,
-};
-use crate::systems::ability_bar::init_abilities_bar;
+use crate::resources::difficulty::{scale, DifficultyResource};
+use crate::resources::levels::get_level_definition;
+use crate::resources::rng::init_level_rng;
+use crate::resources::spawn_timeline::SpawnModifiers;
+use crate::systems::spawner::SpawnerSystem;
 use amethyst::core::ecs::DenseVecStorage;
 use amethyst::shred::Dispatcher;
-use amethyst::ui::{Anchor, LineMode, UiText, UiTransform};
-
-pub const MAX_FIRES: u64 = 60;
+use amethyst::ui::{Anchor, LineMode, UiText};
 
 /// Tags a component as the wildfire state text.
 pub struct WildfiresStateTextComponent;
@@ -30,16 +30,8 @@ impl Component for WildfiresStateTextComponent {
 fn init_wildfires_state_text(world: &mut World, max_fires: u64) {
     let font = get_main_font(world);
 
-    let transform = UiTransform::new(
-        "wildfire_state".to_string(),
-        Anchor::TopMiddle,
-        Anchor::TopMiddle,
-        0.0,
-        -55.0,
-        0.0,
-        600.0,
-        50.0,
-    );
+    let layout = hud_text_layout();
+    let transform = playfield_ui_transform(world, "wildfire_state", &layout);
     let ui_text = UiText::new(
         font,
         format!("0 FIRES / {} MAX", max_fires),
@@ -53,6 +45,7 @@ fn init_wildfires_state_text(world: &mut World, max_fires: u64) {
         .create_entity()
         .with(WildfiresStateTextComponent)
         .with(LevelComponent)
+        .with(layout)
         .with(transform)
         .with(ui_text)
         .build();
@@ -68,42 +61,52 @@ fn update_wildfire_state(world: &mut World, current_fires: u64, max_fires: u64)
 }
 
 /// A resource for storing some level state for the Wildfires level.
+#[derive(Default)]
 pub struct WildfireStateResource {
     pub current_fires: u64,
     pub stepped_in_fire_times: u64,
+    pub fires_put_out: u64,
 }
 
-impl Default for WildfireStateResource {
-    fn default() -> Self {
-        WildfireStateResource {
-            current_fires: 0,
-            stepped_in_fire_times: 0,
-        }
+impl WildfireStateResource {
+    /// Removes a fire from the current fires and counts it as put out.
+    pub fn put_out_fire(&mut self) {
+        self.current_fires = self.current_fires.saturating_sub(1);
+        self.fires_put_out += 1;
     }
 }
 
+#[derive(Default)]
 pub struct WildfireState<'a, 'b> {
     dispatcher: Option<Dispatcher<'a, 'b>>,
+    /// The max amount of fires before the player has stepped in any (from the level definition, scaled by the difficulty).
+    starting_max_fires: u64,
     max_fires: u64,
 }
-impl<'a, 'b> Default for WildfireState<'a, 'b> {
-    fn default() -> Self {
-        WildfireState {
-            dispatcher: None,
-            max_fires: MAX_FIRES,
-        }
-    }
-}
 
 impl<'a, 'b> SimpleState for WildfireState<'a, 'b> {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
 
+        let definition = get_level_definition(world, WILDFIRES);
+        let modifiers = world
+            .read_resource::<DifficultyResource>()
+            .difficulty
+            .modifiers();
+
+        self.starting_max_fires = scale(
+            definition
+                .max_fires
+                .expect("The Wildfires level definition is missing max_fires!"),
+            modifiers.max_fires,
+        );
+        self.max_fires = self.starting_max_fires;
+
         init_wildfires_state_text(world, self.max_fires);
 
-        init_level_background(world, "wildfires_background.png");
+        init_level_from_definition(world, &definition);
 
-        init_level_title(world, "wildfires_title.png");
+        init_level_rng(world, &definition);
 
         world.insert(CurrentLevelScoreResource::default());
         world.insert(LevelSecondsResource::default());
@@ -111,47 +114,18 @@ impl<'a, 'b> SimpleState for WildfireState<'a, 'b> {
         // Init the resource storing data about the player's progress on the level
         world.insert(WildfireStateResource::default());
 
-        let bucket_sprite = load_sprite(world, "bucket_ability.png", 0);
-        let tri_shot_sprite = load_sprite(world, "tri_shot_ability.png", 0);
-        let range_boost_sprite = load_sprite(world, "range_boost_ability.png", 0);
-        init_abilities_bar(
+        init_spawn_timeline(
             world,
-            AbilitiesResource::new(vec![
-                Ability {
-                    info: AbilityInfo {
-                        ability_type: AbilityType::Bucket,
-                        seconds_to_charge: 5,
-                        duration: Some(5),
-                        icon: bucket_sprite,
-                        max_uses: None,
-                    },
-                    current_state: AbilityState::default(),
-                },
-                Ability {
-                    info: AbilityInfo {
-                        ability_type: AbilityType::TriShot,
-                        seconds_to_charge: 8,
-                        duration: Some(6),
-                        icon: tri_shot_sprite,
-                        max_uses: None,
-                    },
-                    current_state: AbilityState::default(),
-                },
-                Ability {
-                    info: AbilityInfo {
-                        ability_type: AbilityType::RangeBoost,
-                        seconds_to_charge: 10,
-                        duration: Some(7),
-                        icon: range_boost_sprite,
-                        max_uses: None,
-                    },
-                    current_state: AbilityState::default(),
-                },
-            ]),
+            WILDFIRES,
+            SpawnModifiers {
+                interval: modifiers.fire_interval,
+                ..Default::default()
+            },
         );
 
         self.dispatcher = create_optional_systems_dispatcher(world, |builder, _| {
-            builder.add(WildfiresSystem::default(), "wildfires", &[])
+            builder.add(SpawnerSystem::default(), "spawner", &[]);
+            builder.add(WildfiresSystem::default(), "wildfires", &["spawner"]);
         });
     }
 
@@ -159,8 +133,6 @@ impl<'a, 'b> SimpleState for WildfireState<'a, 'b> {
         let world = data.world;
 
         delete_all_entities_with_component::<LevelComponent>(world);
-
-        update_high_score_if_greater(world, WILDFIRES);
     }
 
     fn handle_event(
@@ -168,38 +140,44 @@ impl<'a, 'b> SimpleState for WildfireState<'a, 'b> {
         _data: StateData<'_, GameData<'_, '_>>,
         event: StateEvent,
     ) -> SimpleTrans {
-        return_to_main_menu_on_escape(event)
+        pause_on_escape_or_focus_lost(event, WILDFIRES)
     }
 
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
         let world = &mut data.world;
 
-        // Update seconds elapsed
-        let seconds_elapsed = {
-            let mut seconds = world.write_resource::<LevelSecondsResource>();
-
-            seconds.seconds_elapsed += world.read_resource::<Time>().delta_seconds();
-
-            seconds.seconds_elapsed
-        };
+        let seconds_elapsed = world
+            .read_resource::<LevelSecondsResource>()
+            .seconds_elapsed;
 
         // Update the max_fires field and the score
-        let current_fires = {
+        let (current_fires, breakdown) = {
             let state = world.read_resource::<WildfireStateResource>();
 
             // Update the max amount of fires based on how many times the user has stepped in a fire
-            self.max_fires = MAX_FIRES.saturating_sub(state.stepped_in_fire_times);
+            self.max_fires = self
+                .starting_max_fires
+                .saturating_sub(state.stepped_in_fire_times);
 
             let mut score = world.write_resource::<CurrentLevelScoreResource>();
             // Update the level score based on seconds elapsed and fires stepped in
             score.score = (seconds_elapsed as u64).saturating_sub(state.stepped_in_fire_times);
 
-            state.current_fires
+            let breakdown = vec![
+                ("Seconds survived".to_string(), seconds_elapsed as u64),
+                ("Fires put out".to_string(), state.fires_put_out),
+                (
+                    "Times stepped in fire".to_string(),
+                    state.stepped_in_fire_times,
+                ),
+            ];
+
+            (state.current_fires, breakdown)
         };
 
         // End the level if the player has not put out enough fires
         if current_fires > self.max_fires {
-            Trans::Replace(Box::new(MainMenuState::default()))
+            finish_level(world, WILDFIRES, breakdown)
         } else {
             run_systems(world, &mut self.dispatcher);
             update_wildfire_state(world, current_fires, self.max_fires);
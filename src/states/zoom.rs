@@ -0,0 +1,235 @@
+use crate::*;
+
+use crate::playfield::PlayfieldUiComponent;
+use crate::systems::zoom::{
+    response_button_position, tile_position, ResponseButton, VideoTile, ZoomSystemDesc, GRID_SIZE,
+    INCIDENTS, PARTICIPANT_SPRITES, RESPONSE_BUTTON_HEIGHT, RESPONSE_BUTTON_WIDTH, TILE_HEIGHT,
+    TILE_WIDTH,
+};
+
+use crate::resources::high_scores::highscores_keys::ZOOM;
+
+use crate::resources::high_scores::CurrentLevelScoreResource;
+use crate::states::results::finish_level;
+use crate::states::{
+    create_optional_systems_dispatcher, init_level_from_definition, init_timer_and_score_text,
+    pause_on_escape_or_focus_lost, playfield_ui_transform, run_systems,
+    update_timer_and_score_text, LevelComponent, LevelSecondsResource,
+};
+
+use crate::resources::difficulty::DifficultyResource;
+use crate::resources::levels::get_level_definition;
+use crate::resources::rng::init_level_rng;
+use amethyst::ecs::Dispatcher;
+use amethyst::ui::UiImage;
+
+/// A resource for storing some level state for the Zoom Meeting Survival level.
+#[derive(Default)]
+pub struct ZoomStateResource {
+    /// Incidents resolved by clicking on them or with Mute All.
+    pub incidents_resolved: u64,
+    /// Unmuted participants muted by Mute All.
+    pub incidents_muted_by_ability: u64,
+    /// Incidents that ran out of time.
+    pub incidents_missed: u64,
+    /// Clicks on a tile with the wrong response selected (or without an incident).
+    pub wrong_clicks: u64,
+    /// Seconds between incidents at the start of the level (scaled by the difficulty).
+    pub incident_spawn_seconds: f32,
+    /// Seconds the player has to respond to an incident (scaled by the difficulty).
+    pub incident_seconds: f32,
+    /// Incidents never happen more often than this many seconds apart.
+    pub min_incident_spawn_seconds: f32,
+    /// Every this many seconds incidents happen `incident_ramp_step_seconds` sooner.
+    pub incident_ramp_seconds: f32,
+    pub incident_ramp_step_seconds: f32,
+    /// Points lost for clicking on a tile with the wrong response.
+    pub wrong_click_penalty: u64,
+}
+
+impl ZoomStateResource {
+    /// Seconds between incidents after the level has been running for some seconds.
+    pub fn incident_spawn_seconds_at(&self, seconds_elapsed: f32) -> f32 {
+        let ramp = (seconds_elapsed / self.incident_ramp_seconds).floor()
+            * self.incident_ramp_step_seconds;
+
+        (self.incident_spawn_seconds - ramp).max(self.min_incident_spawn_seconds)
+    }
+
+    /// Incidents resolved minus the penalties for wrong clicks.
+    pub fn score(&self) -> u64 {
+        self.incidents_resolved
+            .saturating_sub(self.wrong_clicks * self.wrong_click_penalty)
+    }
+}
+
+/// Creates the grid of video tiles and the response buttons below it.
+fn init_meeting(world: &mut World) {
+    let tile_sprite = load_sprite(world, "zoom_tile.png", 0);
+
+    for index in 0..GRID_SIZE * GRID_SIZE {
+        let (x, y) = tile_position(index);
+
+        let mut sprite = tile_sprite.clone();
+        sprite.sprite_number = index % PARTICIPANT_SPRITES;
+
+        let layout = PlayfieldUiComponent::new(x, y, TILE_WIDTH, TILE_HEIGHT).with_z(1.0);
+        let transform = playfield_ui_transform(world, &format!("zoom_tile_{}", index), &layout);
+
+        world
+            .create_entity()
+            .with(LevelComponent)
+            .with(VideoTile::new(index))
+            .with(UiImage::Sprite(sprite))
+            .with(transform)
+            .with(layout)
+            .build();
+    }
+
+    let response_sprite = load_sprite(world, "zoom_responses.png", 0);
+
+    for (index, incident) in INCIDENTS.iter().enumerate() {
+        let (x, y) = response_button_position(index);
+
+        let mut sprite = response_sprite.clone();
+        sprite.sprite_number = incident.response_sprite_number(index == 0);
+
+        let layout = PlayfieldUiComponent::new(x, y, RESPONSE_BUTTON_WIDTH, RESPONSE_BUTTON_HEIGHT)
+            .with_z(1.0);
+        let transform = playfield_ui_transform(world, &format!("zoom_response_{}", index), &layout);
+
+        world
+            .create_entity()
+            .with(LevelComponent)
+            .with(ResponseButton {
+                incident: *incident,
+            })
+            .with(UiImage::Sprite(sprite))
+            .with(transform)
+            .with(layout)
+            .build();
+    }
+}
+
+#[derive(Default)]
+pub struct ZoomState<'a, 'b> {
+    dispatcher: Option<Dispatcher<'a, 'b>>,
+    max_seconds: f32,
+}
+
+impl<'a, 'b> SimpleState for ZoomState<'a, 'b> {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        let definition = get_level_definition(world, ZOOM);
+
+        let modifiers = world
+            .read_resource::<DifficultyResource>()
+            .difficulty
+            .modifiers();
+
+        self.max_seconds = definition
+            .max_seconds
+            .expect("The Zoom Meeting Survival level definition is missing max_seconds!");
+
+        let incident_seconds = definition
+            .incident_seconds
+            .expect("The Zoom Meeting Survival level definition is missing incident_seconds!");
+
+        let incident_spawn_seconds = definition.incident_spawn_seconds.expect(
+            "The Zoom Meeting Survival level definition is missing incident_spawn_seconds!",
+        );
+
+        let min_incident_spawn_seconds = definition.min_incident_spawn_seconds.expect(
+            "The Zoom Meeting Survival level definition is missing min_incident_spawn_seconds!",
+        );
+
+        let incident_ramp_seconds = definition
+            .incident_ramp_seconds
+            .expect("The Zoom Meeting Survival level definition is missing incident_ramp_seconds!");
+
+        let incident_ramp_step_seconds = definition.incident_ramp_step_seconds.expect(
+            "The Zoom Meeting Survival level definition is missing incident_ramp_step_seconds!",
+        );
+
+        let wrong_click_penalty = definition
+            .wrong_click_penalty
+            .expect("The Zoom Meeting Survival level definition is missing wrong_click_penalty!");
+
+        init_level_from_definition(world, &definition);
+
+        init_level_rng(world, &definition);
+
+        init_timer_and_score_text(world, self.max_seconds);
+
+        world.insert(CurrentLevelScoreResource::default());
+        world.insert(LevelSecondsResource::default());
+
+        // Init the resource storing data about the player's progress on the level
+        world.insert(ZoomStateResource {
+            incident_spawn_seconds: incident_spawn_seconds * modifiers.incident_interval,
+            incident_seconds: incident_seconds * modifiers.incident_seconds,
+            min_incident_spawn_seconds,
+            incident_ramp_seconds,
+            incident_ramp_step_seconds,
+            wrong_click_penalty,
+            ..Default::default()
+        });
+
+        self.dispatcher = create_optional_systems_dispatcher(world, |builder, world| {
+            builder.add(
+                ZoomSystemDesc {
+                    incident_icons: None,
+                    selected_response: INCIDENTS[0],
+                    incident_timer: None,
+                }
+                .build(world),
+                "zoom",
+                &[],
+            );
+        });
+
+        // Created after the dispatcher is set up so the tile and button storages are registered.
+        init_meeting(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        delete_all_entities_with_component::<LevelComponent>(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        pause_on_escape_or_focus_lost(event, ZOOM)
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let world = &mut data.world;
+
+        run_systems(world, &mut self.dispatcher);
+
+        let breakdown = {
+            let state = world.read_resource::<ZoomStateResource>();
+
+            world.write_resource::<CurrentLevelScoreResource>().score = state.score();
+
+            vec![
+                ("Incidents resolved".to_string(), state.incidents_resolved),
+                (
+                    "Muted by Mute All".to_string(),
+                    state.incidents_muted_by_ability,
+                ),
+                ("Incidents missed".to_string(), state.incidents_missed),
+                ("Wrong clicks".to_string(), state.wrong_clicks),
+            ]
+        };
+
+        if update_timer_and_score_text(world, self.max_seconds) {
+            finish_level(world, ZOOM, breakdown)
+        } else {
+            Trans::None
+        }
+    }
+}
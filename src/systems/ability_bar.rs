@@ -1,11 +1,18 @@
+use crate::input::action_names::ABILITY_HOTKEYS;
+use crate::input::{action_is_down, button_name, control_buttons, Control};
+use crate::playfield::{Playfield, PlayfieldUiComponent, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
 use crate::resources::abilities::AbilitiesResource;
+use crate::states::pause::PausedResource;
 use crate::states::LevelComponent;
 use crate::*;
 use amethyst::core::ecs::{Component, DenseVecStorage, World};
 use amethyst::core::shrev::EventChannel;
 use amethyst::core::Transform;
+use amethyst::input::{InputHandler, StringBindings};
 use amethyst::renderer::{SpriteRender, Transparent};
-use amethyst::ui::{Anchor, UiButton, UiButtonBuilder, UiEvent, UiEventType, UiImage, UiTransform};
+use amethyst::ui::{
+    Anchor, LineMode, UiButton, UiButtonBuilder, UiEvent, UiEventType, UiImage, UiText,
+};
 use amethyst::window::ScreenDimensions;
 use amethyst::{core::timing::Time, derive::SystemDesc, ecs::prelude::*};
 
@@ -14,6 +21,8 @@ pub const PROGRESS_BAR_MAX_WIDTH: f32 = 47.;
 pub const PROGRESS_BAR_HEIGHT: f32 = 7.;
 /// The extra spacing between ability frames.
 pub const ABILITY_FRAME_SPACING: f32 = 10.;
+/// Where the middle of the ability frames sits on the playfield.
+pub const ABILITY_BAR_Y: f32 = PLAYFIELD_HEIGHT * 0.05;
 
 pub trait RemoveItem<T> {
     fn remove_first_found_item(&mut self, item: &T) -> Option<T>;
@@ -40,12 +49,10 @@ impl Component for ProgressBar {
 
 /// Creates an ability bar based off of a vector of abilities. Updates the Abilities resource with the new abilities.
 pub fn init_abilities_bar(world: &mut World, mut abilities: AbilitiesResource) {
-    let dimensions = (*world.read_resource::<ScreenDimensions>()).clone();
-
     let mut base_offset = 0.0;
 
     while base_offset
-        != dimensions.width()
+        != PLAYFIELD_WIDTH
             - (((abilities.available_abilities.len() - 1) as f32
                 * (ABILITY_FRAME_HEIGHT_AND_WITH + ABILITY_FRAME_SPACING))
                 + base_offset)
@@ -66,24 +73,29 @@ pub fn init_abilities_bar(world: &mut World, mut abilities: AbilitiesResource) {
     world.insert(abilities);
 }
 
-/// Creates a UI transform for a progress bar.
-pub fn create_progress_bar_transform(
-    x_padding: f32,
-    percent: f32,
-    arena_height: f32,
-) -> UiTransform {
-    UiTransform::new(
-        percent.to_string(),
-        Anchor::BottomLeft,
-        Anchor::MiddleLeft,
-        x_padding - (0.5 * PROGRESS_BAR_MAX_WIDTH),
-        (arena_height * 0.05) - ABILITY_FRAME_HEIGHT_AND_WITH / 2.5,
-        0.,
-        PROGRESS_BAR_MAX_WIDTH * percent,
+/// Lays out a progress bar on the playfield. It fills up from the left of its ability frame.
+pub fn create_progress_bar_layout(x_padding: f32, percent: f32) -> PlayfieldUiComponent {
+    let width = PROGRESS_BAR_MAX_WIDTH * percent;
+
+    PlayfieldUiComponent::new(
+        x_padding - (0.5 * PROGRESS_BAR_MAX_WIDTH) + (0.5 * width),
+        ABILITY_BAR_Y - ABILITY_FRAME_HEIGHT_AND_WITH / 2.5,
+        width,
         PROGRESS_BAR_HEIGHT,
     )
 }
 
+/// Gets the label for an ability slot's hotkey (like "1"). Returns None if the slot has no hotkey bound.
+pub fn ability_hotkey_label(world: &World, index: usize) -> Option<String> {
+    let action = ABILITY_HOTKEYS.get(index)?;
+
+    let input = world.read_resource::<InputHandler<StringBindings>>();
+
+    control_buttons(&input.bindings, Control::Action(action))
+        .first()
+        .map(button_name)
+}
+
 /// Creates an ability item button at the padding location with the associated index.
 pub fn create_ability_item(
     world: &mut World,
@@ -91,7 +103,7 @@ pub fn create_ability_item(
     x_padding: f32,
     index: usize,
 ) -> UiButton {
-    let dimensions = (*world.read_resource::<ScreenDimensions>()).clone();
+    let playfield = Playfield::from_dimensions(&world.read_resource::<ScreenDimensions>());
 
     let ability_frame = load_sprite(world, "ability_frame.png", 0);
     let selected_ability_frame = load_sprite(world, "selected_ability_frame.png", 0);
@@ -99,14 +111,13 @@ pub fn create_ability_item(
 
     let progress_bar = load_sprite(world, "progress_bar.png", 0);
 
+    let progress_bar_layout = create_progress_bar_layout(x_padding, 1.0);
+
     world
         .create_entity()
         .with(UiImage::Sprite(progress_bar))
-        .with(create_progress_bar_transform(
-            x_padding,
-            1.0,
-            dimensions.height(),
-        ))
+        .with(playfield.ui_transform(format!("progress_bar_{}", index), &progress_bar_layout))
+        .with(progress_bar_layout)
         .with(ProgressBar {
             ability_index: index,
             x_offset: x_padding,
@@ -116,22 +127,35 @@ pub fn create_ability_item(
 
     let button_parent = world.create_entity().with(LevelComponent).build();
 
+    let button_layout =
+        PlayfieldUiComponent::square(x_padding, ABILITY_BAR_Y, ABILITY_FRAME_HEIGHT_AND_WITH)
+            .with_z(3.0);
+    let (button_x, button_y) = playfield.to_screen(x_padding, ABILITY_BAR_Y);
+
     let (_id, button) = UiButtonBuilder::<(), u32>::new(String::new())
         .with_anchor(Anchor::BottomLeft)
         .with_layer(3.0)
-        .with_position(x_padding, dimensions.height() * 0.05)
-        .with_size(52., 52.)
+        .with_position(button_x, button_y)
+        .with_size(
+            ABILITY_FRAME_HEIGHT_AND_WITH * playfield.scale,
+            ABILITY_FRAME_HEIGHT_AND_WITH * playfield.scale,
+        )
         .with_image(UiImage::Sprite(ability_frame))
         .with_hover_image(UiImage::Sprite(selected_ability_frame))
         .with_press_image(UiImage::Sprite(tapped_ability_frame))
         .with_parent(button_parent)
         .build_from_world(&world);
 
+    world
+        .write_storage::<PlayfieldUiComponent>()
+        .insert(button.image_entity, button_layout)
+        .expect("Couldn't lay out the ability button!");
+
     let background = load_sprite(world, "ability_frame_background.png", 0);
 
     // Create the background in the center of the frame.
     let mut background_transform = Transform::default();
-    background_transform.set_translation_xyz(x_padding, dimensions.height() * 0.05, 1.0);
+    background_transform.set_translation_xyz(x_padding, ABILITY_BAR_Y, 1.0);
 
     world
         .create_entity()
@@ -142,11 +166,7 @@ pub fn create_ability_item(
 
     // Create the icon in the upper center of the frame.
     let mut icon_transform = Transform::default();
-    icon_transform.set_translation_xyz(
-        x_padding,
-        (dimensions.height() * 0.05) + PROGRESS_BAR_HEIGHT / 2.5,
-        2.0,
-    );
+    icon_transform.set_translation_xyz(x_padding, ABILITY_BAR_Y + PROGRESS_BAR_HEIGHT / 2.5, 2.0);
 
     world
         .create_entity()
@@ -156,9 +176,71 @@ pub fn create_ability_item(
         .with(LevelComponent)
         .build();
 
+    // Show the hotkey in the upper right corner of the frame.
+    if let Some(label) = ability_hotkey_label(world, index) {
+        let font = get_main_font(world);
+
+        let label_layout = PlayfieldUiComponent::square(
+            x_padding + ABILITY_FRAME_HEIGHT_AND_WITH * 0.3,
+            ABILITY_BAR_Y + ABILITY_FRAME_HEIGHT_AND_WITH * 0.3,
+            20.0,
+        )
+        .with_z(4.0)
+        .with_font_size(14.0);
+
+        world
+            .create_entity()
+            .with(playfield.ui_transform(format!("ability_hotkey_{}", index), &label_layout))
+            .with(UiText::new(
+                font,
+                label,
+                [1.0, 1.0, 1.0, 1.0],
+                14.0 * playfield.scale,
+                LineMode::Single,
+                Anchor::Middle,
+            ))
+            .with(label_layout)
+            .with(LevelComponent)
+            .build();
+    }
+
     button
 }
 
+/// Returns the indexes of the abilities whose hotkey was pressed this frame.
+/// Holding a hotkey only counts as one press.
+pub fn pressed_ability_hotkeys(
+    abilities: &mut AbilitiesResource,
+    input: &InputHandler<StringBindings>,
+) -> Vec<usize> {
+    let mut pressed = Vec::new();
+
+    for (index, ability) in abilities.available_abilities.iter_mut().enumerate() {
+        let down = ABILITY_HOTKEYS
+            .get(index)
+            .map_or(false, |action| action_is_down(input, action));
+
+        if down && !ability.current_state.hotkey_down {
+            pressed.push(index);
+        }
+
+        ability.current_state.hotkey_down = down;
+    }
+
+    pressed
+}
+
+/// Returns the index of the ability whose button is the target entity (if any).
+pub fn clicked_ability_index(abilities: &AbilitiesResource, target: Entity) -> Option<usize> {
+    abilities.available_abilities.iter().position(|ability| {
+        ability
+            .current_state
+            .ui_button
+            .as_ref()
+            .map_or(false, |button| button.image_entity == target)
+    })
+}
+
 /// Updates a progress bar and the ability at the index.
 /// It will increase the progress bar at the speed specified if the ability is on cooldown.
 /// It will decrease the progress bar if the ability has a duration and is active.
@@ -166,10 +248,9 @@ pub fn create_ability_item(
 /// If the ability has a duration and that duration is over, it will remove the ability from the active abilities vector.
 pub fn update_progress_bar(
     progress_bar: &ProgressBar,
-    transform: &mut UiTransform,
+    layout: &mut PlayfieldUiComponent,
     abilities: &mut AbilitiesResource,
     time: &Time,
-    arena_height: f32,
 ) {
     let ability = &mut abilities.available_abilities[progress_bar.ability_index];
 
@@ -193,8 +274,7 @@ pub fn update_progress_bar(
 
         ability.current_state.percentage = new_percentage;
 
-        *transform =
-            create_progress_bar_transform(progress_bar.x_offset, new_percentage, arena_height);
+        *layout = create_progress_bar_layout(progress_bar.x_offset, new_percentage);
     } else {
         let mut new_percentage = ability.current_state.percentage
             + (time.delta_seconds() / ability.info.seconds_to_charge as f32);
@@ -214,8 +294,7 @@ pub fn update_progress_bar(
 
         ability.current_state.percentage = new_percentage;
 
-        *transform =
-            create_progress_bar_transform(progress_bar.x_offset, new_percentage, arena_height);
+        *layout = create_progress_bar_layout(progress_bar.x_offset, new_percentage);
     }
 }
 
@@ -224,10 +303,8 @@ pub fn update_progress_bar(
 /// Adds the index of the ability to the `active_abilities` vector.
 pub fn use_ability(
     progress_bar: &ProgressBar,
-    transform: &mut UiTransform,
+    layout: &mut PlayfieldUiComponent,
     abilities: &mut AbilitiesResource,
-
-    arena_height: f32,
 ) {
     let ability = &mut abilities.available_abilities[progress_bar.ability_index];
 
@@ -241,7 +318,7 @@ pub fn use_ability(
             // Set the charge percentage to 0 and update the uses.
             ability.current_state.percentage = 0.0;
             ability.current_state.uses += 1;
-            *transform = create_progress_bar_transform(progress_bar.x_offset, 0.0, arena_height);
+            *layout = create_progress_bar_layout(progress_bar.x_offset, 0.0);
         }
 
         // Level-specific systems will do as they please with the ability being active:
@@ -267,52 +344,46 @@ impl<'s> System<'s> for AbilityBarSystem {
     type SystemData = (
         Read<'s, EventChannel<UiEvent>>,
         ReadStorage<'s, ProgressBar>,
-        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, PlayfieldUiComponent>,
         Read<'s, Time>,
-        ReadExpect<'s, ScreenDimensions>,
         Option<Write<'s, AbilitiesResource>>,
+        Read<'s, PausedResource>,
+        Read<'s, InputHandler<StringBindings>>,
     );
 
     fn run(
         &mut self,
-        (events, progress_bars, mut transforms, time, dimensions, abilities): Self::SystemData,
+        (events, progress_bars, mut layouts, time, abilities, paused, input): Self::SystemData,
     ) {
+        if paused.paused {
+            // Throw away clicks made while paused and freeze the charge of every ability.
+            events.read(&mut self.reader_id).count();
+            return;
+        }
+
         if let Some(mut abilities) = abilities {
             let mut clicked_abilities: Vec<usize> = Vec::new();
 
             for ui_event in events.read(&mut self.reader_id) {
                 if ui_event.event_type == UiEventType::Click {
-                    for (i, ability) in abilities.available_abilities.iter_mut().enumerate() {
-                        let button = ability
-                            .current_state
-                            .ui_button
-                            .as_ref()
-                            .unwrap()
-                            .image_entity;
-
-                        if ui_event.target == button {
-                            clicked_abilities.push(i);
-                        }
+                    if let Some(index) = clicked_ability_index(&*abilities, ui_event.target) {
+                        clicked_abilities.push(index);
                     }
                 }
             }
 
-            for (progress_bar, transform) in (&progress_bars, &mut transforms).join() {
+            // Hotkeys follow the same rules as clicks.
+            for index in pressed_ability_hotkeys(&mut *abilities, &*input) {
+                if !clicked_abilities.contains(&index) {
+                    clicked_abilities.push(index);
+                }
+            }
+
+            for (progress_bar, layout) in (&progress_bars, &mut layouts).join() {
                 if clicked_abilities.contains(&progress_bar.ability_index) {
-                    use_ability(
-                        progress_bar,
-                        transform,
-                        &mut *abilities,
-                        dimensions.height(),
-                    );
+                    use_ability(progress_bar, layout, &mut *abilities);
                 } else {
-                    update_progress_bar(
-                        progress_bar,
-                        transform,
-                        &mut *abilities,
-                        &*time,
-                        dimensions.height(),
-                    );
+                    update_progress_bar(progress_bar, layout, &mut *abilities, &*time);
                 }
             }
         }
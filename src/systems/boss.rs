@@ -0,0 +1,358 @@
+use crate::playfield::{Playfield, PlayfieldUiComponent, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+use crate::states::LevelComponent;
+use crate::timers::IntervalTimer;
+use crate::{create_ui_color_from_rgba, FontsResource};
+use amethyst::core::ecs::{
+    Component, DenseVecStorage, Entities, Entity, Join, LazyUpdate, Read, ReadStorage, WriteStorage,
+};
+use amethyst::core::Time;
+use amethyst::prelude::Builder;
+use amethyst::renderer::SpriteRender;
+use amethyst::ui::{Anchor, LineMode, UiText};
+use amethyst::{
+    derive::SystemDesc,
+    ecs::prelude::{System, SystemData},
+};
+
+/// How many seconds a boss shows its hit sprite after being damaged.
+pub const HIT_REACTION_SECONDS: f32 = 0.13;
+
+/// Boss sprite sheets have a normal frame, a hit frame and a shielded frame.
+pub const BOSS_SPRITE_NORMAL: usize = 0;
+pub const BOSS_SPRITE_HIT: usize = 1;
+pub const BOSS_SPRITE_SHIELDED: usize = 2;
+
+pub const HEALTH_BAR_WIDTH: f32 = 300.0;
+pub const HEALTH_BAR_HEIGHT: f32 = 12.0;
+/// Where the middle of the health bar sits on the playfield.
+pub const HEALTH_BAR_Y: f32 = PLAYFIELD_HEIGHT - 122.0;
+
+/// What a boss does during a phase.
+#[derive(Clone, Copy, Debug)]
+pub enum BossAttack {
+    /// Fires projectiles in a ring around the boss every `interval_seconds`.
+    RadialBurst {
+        projectiles: u32,
+        interval_seconds: f32,
+    },
+    /// The boss can't be damaged.
+    Shield,
+}
+
+/// A timed phase of a boss fight.
+#[derive(Clone, Copy, Debug)]
+pub struct BossPhase {
+    pub attack: BossAttack,
+    /// How long the phase lasts before the boss moves on to the next one.
+    pub seconds: f32,
+}
+
+impl BossPhase {
+    /// A timer for the phase's radial bursts, first going off one interval into the phase.
+    fn burst_timer(&self) -> Option<IntervalTimer> {
+        match self.attack {
+            BossAttack::RadialBurst {
+                interval_seconds, ..
+            } => Some(IntervalTimer::new(interval_seconds)),
+            _ => None,
+        }
+    }
+}
+
+/// A boss with a health bar that cycles through attack phases.
+/// The level's system moves the boss, fires its attacks and damages it; the `BossSystem` handles the rest.
+pub struct BossComponent {
+    pub health: u64,
+    pub max_health: u64,
+    /// The phases the boss cycles through, in order.
+    pub phases: Vec<BossPhase>,
+    pub phase_index: usize,
+    /// Goes off when the current phase is over.
+    pub phase_timer: IntervalTimer,
+    /// Goes off whenever the current phase's radial burst is due. None if the phase has no burst.
+    pub burst_timer: Option<IntervalTimer>,
+    /// Seconds left showing the hit sprite.
+    pub hit_seconds_left: f32,
+}
+impl Component for BossComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl BossComponent {
+    pub fn new(max_health: u64, phases: Vec<BossPhase>) -> Self {
+        assert!(!phases.is_empty(), "A boss needs at least one phase!");
+
+        let first_phase = phases[0];
+
+        BossComponent {
+            health: max_health,
+            max_health,
+            phases,
+            phase_index: 0,
+            phase_timer: IntervalTimer::starting_in(first_phase.seconds, None),
+            burst_timer: first_phase.burst_timer(),
+            hit_seconds_left: 0.,
+        }
+    }
+
+    pub fn current_attack(&self) -> BossAttack {
+        self.phases[self.phase_index].attack
+    }
+
+    pub fn is_shielded(&self) -> bool {
+        matches!(self.current_attack(), BossAttack::Shield)
+    }
+
+    pub fn is_defeated(&self) -> bool {
+        self.health == 0
+    }
+
+    /// Damages the boss. Returns false if the hit was blocked by its shield.
+    pub fn take_hit(&mut self, damage: u64) -> bool {
+        if self.is_shielded() {
+            return false;
+        }
+
+        self.health = self.health.saturating_sub(damage);
+        self.hit_seconds_left = HIT_REACTION_SECONDS;
+
+        true
+    }
+
+    /// Moves on to the next phase (looping back to the first) once the current one is over.
+    pub fn update_phase(&mut self, delta_seconds: f32) {
+        self.hit_seconds_left = (self.hit_seconds_left - delta_seconds).max(0.);
+
+        if self.phase_timer.tick(delta_seconds) > 0 {
+            self.phase_index = (self.phase_index + 1) % self.phases.len();
+
+            let phase = self.phases[self.phase_index];
+
+            self.phase_timer = IntervalTimer::starting_in(phase.seconds, None);
+            self.burst_timer = phase.burst_timer();
+        }
+    }
+
+    /// How many projectiles to fire this frame if the current phase has a radial burst due.
+    pub fn radial_burst_due(&mut self, delta_seconds: f32) -> Option<u32> {
+        let bursts_due = self
+            .burst_timer
+            .as_mut()
+            .map_or(0, |burst_timer| burst_timer.tick(delta_seconds));
+
+        match self.current_attack() {
+            BossAttack::RadialBurst { projectiles, .. } if bursts_due > 0 => Some(projectiles),
+            _ => None,
+        }
+    }
+
+    /// The frame of the boss's sprite sheet to show.
+    pub fn sprite_number(&self) -> usize {
+        if self.is_shielded() {
+            BOSS_SPRITE_SHIELDED
+        } else if self.hit_seconds_left > 0. {
+            BOSS_SPRITE_HIT
+        } else {
+            BOSS_SPRITE_NORMAL
+        }
+    }
+}
+
+/// Tags an entity as part of a boss's health bar. Deleted along with the boss.
+pub struct BossHealthBarComponent {
+    pub boss: Entity,
+    /// Width of the bar at full health. None for the parts of the bar that don't shrink.
+    pub full_width: Option<f32>,
+}
+impl Component for BossHealthBarComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Creates a health bar with the boss's name below the level's state text.
+pub fn create_boss_health_bar(
+    entities: &Entities,
+    lazy: &LazyUpdate,
+    fonts: &FontsResource,
+    playfield: &Playfield,
+    boss: Entity,
+    name: &str,
+) {
+    let name_layout = PlayfieldUiComponent::new(
+        PLAYFIELD_WIDTH * 0.5,
+        PLAYFIELD_HEIGHT - 102.0,
+        HEALTH_BAR_WIDTH,
+        20.0,
+    )
+    .with_font_size(18.0);
+
+    lazy.create_entity(entities)
+        .with(LevelComponent)
+        .with(BossHealthBarComponent {
+            boss,
+            full_width: None,
+        })
+        .with(playfield.ui_transform("boss_name".to_string(), &name_layout))
+        .with(name_layout)
+        .with(UiText::new(
+            fonts.main_font.clone(),
+            name.to_string(),
+            [1.0, 1.0, 1.0, 1.0],
+            18.0 * playfield.scale,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build();
+
+    let bar_layout = PlayfieldUiComponent::new(
+        PLAYFIELD_WIDTH * 0.5,
+        HEALTH_BAR_Y,
+        HEALTH_BAR_WIDTH,
+        HEALTH_BAR_HEIGHT,
+    );
+
+    lazy.create_entity(entities)
+        .with(LevelComponent)
+        .with(BossHealthBarComponent {
+            boss,
+            full_width: None,
+        })
+        .with(playfield.ui_transform("boss_health_bar".to_string(), &bar_layout))
+        .with(bar_layout.clone())
+        .with(create_ui_color_from_rgba(40, 10, 15, 0.8))
+        .build();
+
+    let fill_layout = bar_layout.with_z(1.0);
+
+    lazy.create_entity(entities)
+        .with(LevelComponent)
+        .with(BossHealthBarComponent {
+            boss,
+            full_width: Some(HEALTH_BAR_WIDTH),
+        })
+        .with(playfield.ui_transform("boss_health_bar_fill".to_string(), &fill_layout))
+        .with(fill_layout)
+        .with(create_ui_color_from_rgba(220, 40, 60, 1.0))
+        .build();
+}
+
+/// Runs boss phases, shows hit reactions and keeps boss health bars up to date.
+#[derive(SystemDesc, Default)]
+pub struct BossSystem;
+
+impl<'s> System<'s> for BossSystem {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, Time>,
+        WriteStorage<'s, BossComponent>,
+        WriteStorage<'s, SpriteRender>,
+        ReadStorage<'s, BossHealthBarComponent>,
+        WriteStorage<'s, PlayfieldUiComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            time,
+            mut boss_storage,
+            mut sprite_render_storage,
+            health_bar_storage,
+            mut playfield_ui_storage,
+        ): Self::SystemData,
+    ) {
+        for (boss, sprite) in (&mut boss_storage, &mut sprite_render_storage).join() {
+            boss.update_phase(time.delta_seconds());
+
+            sprite.sprite_number = boss.sprite_number();
+        }
+
+        for (entity, health_bar, layout) in
+            (&entities, &health_bar_storage, &mut playfield_ui_storage).join()
+        {
+            match boss_storage.get(health_bar.boss) {
+                Some(boss) if !boss.is_defeated() => {
+                    if let Some(full_width) = health_bar.full_width {
+                        layout.width = full_width * boss.health as f32 / boss.max_health as f32;
+
+                        // Keep the fill lined up with the left of the bar as it shrinks.
+                        layout.x = (PLAYFIELD_WIDTH - full_width + layout.width) * 0.5;
+                    }
+                }
+                _ => {
+                    entities
+                        .delete(entity)
+                        .expect("Couldn't delete boss health bar!");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A boss that fires a burst of 4 every second for 3 seconds, then shields itself for 2 seconds.
+    fn boss() -> BossComponent {
+        BossComponent::new(
+            10,
+            vec![
+                BossPhase {
+                    attack: BossAttack::RadialBurst {
+                        projectiles: 4,
+                        interval_seconds: 1.,
+                    },
+                    seconds: 3.,
+                },
+                BossPhase {
+                    attack: BossAttack::Shield,
+                    seconds: 2.,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn phases_loop_back_to_the_first() {
+        let mut boss = boss();
+
+        boss.update_phase(2.5);
+        assert_eq!(boss.phase_index, 0);
+
+        boss.update_phase(0.5);
+        assert_eq!(boss.phase_index, 1);
+        assert!(boss.is_shielded());
+
+        boss.update_phase(2.);
+        assert_eq!(boss.phase_index, 0);
+        assert!(!boss.is_shielded());
+    }
+
+    #[test]
+    fn shield_blocks_hits() {
+        let mut boss = boss();
+
+        assert!(boss.take_hit(3));
+        assert_eq!(boss.health, 7);
+        assert_eq!(boss.sprite_number(), BOSS_SPRITE_HIT);
+
+        boss.update_phase(3.);
+
+        assert!(!boss.take_hit(3));
+        assert_eq!(boss.health, 7);
+        assert_eq!(boss.sprite_number(), BOSS_SPRITE_SHIELDED);
+    }
+
+    #[test]
+    fn radial_burst_is_due_every_interval_and_never_while_shielded() {
+        let mut boss = boss();
+
+        assert_eq!(boss.radial_burst_due(0.5), None);
+        assert_eq!(boss.radial_burst_due(0.5), Some(4));
+        assert_eq!(boss.radial_burst_due(0.5), None);
+
+        boss.update_phase(3.);
+
+        assert_eq!(boss.radial_burst_due(5.), None);
+    }
+}
@@ -1,11 +1,19 @@
 use crate::audio::{play_sound_system, SoundsResource};
+use crate::input::axis_names::{MOVE_X, MOVE_Y, ROTATE};
+use crate::input::axis_value;
+use crate::playfield::{PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
 use crate::resources::abilities::{AbilitiesResource, AbilityType};
+use crate::resources::rng::RngResource;
+use crate::resources::spawn_timeline::{EnemyType, SpawnRequestsResource};
 use crate::states::covid::CovidStateResource;
+use crate::timers::LifetimeComponent;
 
-use crate::states::{LevelComponent, LevelSecondsResource};
+use crate::states::LevelComponent;
 
-use crate::systems::{distance_between_points, handle_collisions, load_sprite_system};
-use crate::{bound, bound_transform_x_prepend, bound_transform_y_prepend, every_n_seconds};
+use crate::systems::{
+    distance_between_points, handle_collisions, load_sprite_system, PlayerComponent,
+};
+use crate::{bound, bound_transform_x_prepend, bound_transform_y_prepend};
 use amethyst::assets::{AssetStorage, Loader};
 use amethyst::audio::output::Output;
 use amethyst::audio::Source;
@@ -16,9 +24,7 @@ use amethyst::core::ecs::{
 use amethyst::core::{Time, Transform};
 use amethyst::input::{InputHandler, StringBindings};
 use amethyst::prelude::Builder;
-use amethyst::renderer::rendy::wsi::winit::VirtualKeyCode;
 use amethyst::renderer::{SpriteRender, SpriteSheet, Texture, Transparent};
-use amethyst::window::ScreenDimensions;
 
 use crate::audio::sound_keys::{COUGH_SOUND, COVID_DIE, COVID_SQUISH, HEAL_SOUND};
 
@@ -49,26 +55,16 @@ impl Component for EnemyComponent {
     type Storage = DenseVecStorage<Self>;
 }
 
-/// Tags an entity as a super spreader.
-pub struct SuperSpreaderComponent {
-    pub expiration_frame: u64,
-}
+/// Tags an entity as a super spreader. Super spreaders leave once their `LifetimeComponent` runs out.
+pub struct SuperSpreaderComponent;
 impl Component for SuperSpreaderComponent {
     type Storage = DenseVecStorage<Self>;
 }
 
-// The direction the Covid cell will float towards.
-#[derive(Copy, Clone)]
-pub enum CovidDirection {
-    Left,
-    Right,
-    Up,
-    Down,
-}
-
 /// Tags an entity as a covid cell.
 pub struct CovidCellComponent {
-    pub direction: CovidDirection,
+    /// The direction the covid cell floats towards (in radians).
+    pub heading: f32,
 }
 impl Component for CovidCellComponent {
     type Storage = DenseVecStorage<Self>;
@@ -95,13 +91,12 @@ impl<'s> System<'s> for CovidSystem {
     type SystemData = (
         Entities<'s>,
         Write<'s, CovidStateResource>,
-        Read<'s, LevelSecondsResource>,
+        WriteStorage<'s, LifetimeComponent>,
         Read<'s, Time>,
         Read<'s, LazyUpdate>,
         Read<'s, AssetStorage<Texture>>,
         Read<'s, AssetStorage<SpriteSheet>>,
         ReadExpect<'s, Loader>,
-        ReadExpect<'s, ScreenDimensions>,
         WriteStorage<'s, Transform>,
         ReadStorage<'s, SuperSpreaderComponent>,
         WriteStorage<'s, CovidCellComponent>,
@@ -114,6 +109,8 @@ impl<'s> System<'s> for CovidSystem {
         Read<'s, AssetStorage<Source>>,
         ReadExpect<'s, SoundsResource>,
         Option<Read<'s, Output>>,
+        Write<'s, RngResource>,
+        Write<'s, SpawnRequestsResource>,
     );
 
     fn run(
@@ -121,13 +118,12 @@ impl<'s> System<'s> for CovidSystem {
         (
             entities,
             mut level_state,
-            level_seconds,
+            mut lifetime_storage,
             time,
             lazy,
             texture_storage,
             sheet_storage,
             loader,
-            dimensions,
             mut transform_storage,
             spreader_storage,
             mut covid_storage,
@@ -140,9 +136,11 @@ impl<'s> System<'s> for CovidSystem {
             audio_storage,
             sounds,
             audio_output,
+            mut rng_resource,
+            mut spawn_requests,
         ): Self::SystemData,
     ) {
-        let mut rng = rand::thread_rng();
+        let rng = &mut rng_resource.rng;
 
         if let Some(player_entity) = &self.player_entity {
             let (player_x, player_y, player_z) = {
@@ -324,8 +322,11 @@ impl<'s> System<'s> for CovidSystem {
                             entities
                                 .delete(entity)
                                 .expect("Couldn't delete health pack!");
-                            level_state.current_health =
-                                bound(level_state.current_health as f32 + 10., 0., 100.) as u64;
+                            level_state.current_health = bound(
+                                level_state.current_health as f32 + 10.,
+                                0.,
+                                level_state.health_pool as f32,
+                            ) as u64;
 
                             play_sound_system(HEAL_SOUND, &sounds, &audio_storage, &audio_output);
                         }
@@ -335,21 +336,10 @@ impl<'s> System<'s> for CovidSystem {
                 // Health pack spawning
                 {
                     if let Some(health_pack_sprite) = &self.health_pack_sprite {
-                        if every_n_seconds(6.5, &*time) {
-                            let pos_x = rng.gen_range(10., 590.);
-                            let pos_y = rng.gen_range(100., 500.);
-
-                            // Don't spawn health packs on or really close to player
-                            if distance_between_points(pos_x, pos_y, player_x, player_y)
-                                <= ((PLAYER_HEIGHT_AND_WIDTH * 0.5)
-                                    + (HEALTH_PACK_HEIGHT_AND_WIDTH * 0.5))
-                            {
-                                return;
-                            }
-
+                        for point in spawn_requests.take(EnemyType::HealthPack) {
                             let mut transform = Transform::default();
 
-                            transform.set_translation_xyz(pos_x, pos_y, 2.0);
+                            transform.set_translation_xyz(point.x, point.y, 2.0);
 
                             lazy.create_entity(&entities)
                                 .with(health_pack_sprite.clone())
@@ -386,8 +376,7 @@ impl<'s> System<'s> for CovidSystem {
                     |entity, _, _| {
                         if !mask_is_active {
                             entities.delete(entity).expect("Couldn't delete covid!");
-                            level_state.current_health =
-                                level_state.current_health.saturating_sub(10);
+                            level_state.take_damage(10);
 
                             play_sound_system(COVID_SQUISH, &sounds, &audio_storage, &audio_output);
                         }
@@ -398,23 +387,17 @@ impl<'s> System<'s> for CovidSystem {
                     for (covid, covid_transform, entity) in
                         (&covid_storage, &mut transform_storage, &entities).join()
                     {
-                        match covid.direction {
-                            CovidDirection::Up => {
-                                covid_transform.move_up(COVID_SPEED * time.delta_seconds());
-                            }
-                            CovidDirection::Down => {
-                                covid_transform.move_down(COVID_SPEED * time.delta_seconds());
-                            }
-                            CovidDirection::Left => {
-                                covid_transform.move_left(COVID_SPEED * time.delta_seconds());
-                            }
-                            CovidDirection::Right => {
-                                covid_transform.move_right(COVID_SPEED * time.delta_seconds());
-                            }
-                        }
+                        covid_transform.prepend_translation_x(
+                            covid.heading.cos() * COVID_SPEED * time.delta_seconds(),
+                        );
+                        covid_transform.prepend_translation_y(
+                            covid.heading.sin() * COVID_SPEED * time.delta_seconds(),
+                        );
 
-                        if covid_transform.translation().x >= dimensions.width()
-                            || covid_transform.translation().y >= dimensions.height()
+                        if covid_transform.translation().x >= PLAYFIELD_WIDTH
+                            || covid_transform.translation().y >= PLAYFIELD_HEIGHT
+                            || covid_transform.translation().x <= 0.
+                            || covid_transform.translation().y <= 0.
                         {
                             entities
                                 .delete(entity)
@@ -425,31 +408,10 @@ impl<'s> System<'s> for CovidSystem {
                 // Covid spawning
                 {
                     if let Some(covid_sprite) = &self.covid_sprite {
-                        if every_n_seconds(1.0, &*time) {
-                            let spawn_locations = vec![
-                                ((rng.gen_range(10., 590.), 120.), CovidDirection::Up),
-                                ((rng.gen_range(10., 590.), 480.), CovidDirection::Down),
-                                ((30., rng.gen_range(100., 500.)), CovidDirection::Right),
-                                ((570., rng.gen_range(100., 500.)), CovidDirection::Left),
-                            ];
-
-                            let chosen_location =
-                                &spawn_locations[rng.gen_range(0, spawn_locations.len())];
-
-                            let pos_x = (chosen_location.0).0;
-                            let pos_y = (chosen_location.0).1;
-
-                            // Don't spawn covid on or really close to player
-                            if distance_between_points(pos_x, pos_y, player_x, player_y)
-                                <= ((PLAYER_HEIGHT_AND_WIDTH * 0.5)
-                                    + (COVID_HEIGHT_AND_WIDTH * 0.5))
-                            {
-                                return;
-                            }
-
+                        for point in spawn_requests.take(EnemyType::CovidCell) {
                             let mut transform = Transform::default();
 
-                            transform.set_translation_xyz(pos_x, pos_y, 2.0);
+                            transform.set_translation_xyz(point.x, point.y, 2.0);
 
                             lazy.create_entity(&entities)
                                 .with(covid_sprite.clone())
@@ -457,7 +419,7 @@ impl<'s> System<'s> for CovidSystem {
                                 .with(Transparent)
                                 .with(LevelComponent)
                                 .with(CovidCellComponent {
-                                    direction: chosen_location.1,
+                                    heading: point.heading,
                                 })
                                 .with(EnemyComponent)
                                 .build();
@@ -477,11 +439,16 @@ impl<'s> System<'s> for CovidSystem {
 
             // Super spreaders
             {
-                for (spreader, spreader_transform, entity) in
-                    (&spreader_storage, &transform_storage, &entities).join()
+                for (_, lifetime, spreader_transform, entity) in (
+                    &spreader_storage,
+                    &mut lifetime_storage,
+                    &transform_storage,
+                    &entities,
+                )
+                    .join()
                 {
                     // Delete stale spreaders
-                    if time.frame_number() >= spreader.expiration_frame {
+                    if lifetime.tick(time.delta_seconds()) {
                         entities.delete(entity).expect("Could not delete spreader!");
                     }
 
@@ -495,7 +462,7 @@ impl<'s> System<'s> for CovidSystem {
                         ) <= (0.5 * SPREADER_HEIGHT_AND_WIDTH) + (0.5 * PLAYER_HEIGHT_AND_WIDTH)
                     {
                         entities.delete(entity).expect("Couldn't delete spreader!");
-                        level_state.current_health = level_state.current_health.saturating_sub(10);
+                        level_state.take_damage(10);
 
                         play_sound_system(COUGH_SOUND, &sounds, &audio_storage, &audio_output);
                     }
@@ -504,43 +471,20 @@ impl<'s> System<'s> for CovidSystem {
                 // Super spreader spawning
                 {
                     if let Some(spreader_sprite) = &self.spreader_sprite {
-                        if every_n_seconds(2., &*time) {
-                            // Spreaders to spawn is from 1 to (2 + however many chunks of 40 seconds have gone by).
-                            let spreaders_to_spawn =
-                                rng.gen_range(1, 2 + (level_seconds.seconds_elapsed / 40.) as u32);
-
-                            let mut spreaders_left_to_spawn = spreaders_to_spawn;
-
-                            while spreaders_left_to_spawn != 0 {
-                                let pos_x = rng.gen_range(10., 590.);
-                                let pos_y = rng.gen_range(100., 500.);
-
-                                // Don't spawn spreaders on or really close to player
-                                if distance_between_points(pos_x, pos_y, player_x, player_y)
-                                    <= ((PLAYER_HEIGHT_AND_WIDTH * 0.5)
-                                        + (SPREADER_HEIGHT_AND_WIDTH * 0.5))
-                                {
-                                    continue;
-                                }
-
-                                let mut transform = Transform::default();
-
-                                transform.set_translation_xyz(pos_x, pos_y, 0.0);
+                        for point in spawn_requests.take(EnemyType::SuperSpreader) {
+                            let mut transform = Transform::default();
 
-                                lazy.create_entity(&entities)
-                                    .with(spreader_sprite.clone())
-                                    .with(transform)
-                                    .with(Transparent)
-                                    .with(LevelComponent)
-                                    .with(SuperSpreaderComponent {
-                                        expiration_frame: time.frame_number()
-                                            + rng.gen_range(60, 640),
-                                    })
-                                    .with(EnemyComponent)
-                                    .build();
+                            transform.set_translation_xyz(point.x, point.y, 0.0);
 
-                                spreaders_left_to_spawn -= 1;
-                            }
+                            lazy.create_entity(&entities)
+                                .with(spreader_sprite.clone())
+                                .with(transform)
+                                .with(Transparent)
+                                .with(LevelComponent)
+                                .with(SuperSpreaderComponent)
+                                .with(LifetimeComponent::new(rng.gen_range(1., 10.7)))
+                                .with(EnemyComponent)
+                                .build();
                         }
                     } else {
                         // Load spreader texture
@@ -561,56 +505,39 @@ impl<'s> System<'s> for CovidSystem {
 
                 let min_height_and_width = PLAYER_HEIGHT_AND_WIDTH * 0.5;
 
-                let max_height = dimensions.height() - PLAYER_HEIGHT_AND_WIDTH * 0.5;
+                let max_height = PLAYFIELD_HEIGHT - PLAYER_HEIGHT_AND_WIDTH * 0.5;
 
-                let max_width = dimensions.width() - PLAYER_HEIGHT_AND_WIDTH * 0.5;
+                let max_width = PLAYFIELD_WIDTH - PLAYER_HEIGHT_AND_WIDTH * 0.5;
 
-                // Movement keys
+                // Movement axes
                 {
-                    if input.key_is_down(VirtualKeyCode::W) {
+                    let move_y = axis_value(&input, MOVE_Y);
+                    if move_y != 0.0 {
                         bound_transform_y_prepend(
                             player_transform_mut,
-                            MOVEMENT_SPEED * time.delta_seconds(),
+                            move_y * MOVEMENT_SPEED * time.delta_seconds(),
                             min_height_and_width,
                             max_height,
                         );
                     }
-                    if input.key_is_down(VirtualKeyCode::S) {
-                        bound_transform_y_prepend(
-                            player_transform_mut,
-                            -MOVEMENT_SPEED * time.delta_seconds(),
-                            min_height_and_width,
-                            max_height,
-                        );
-                    }
-
-                    if input.key_is_down(VirtualKeyCode::A) {
-                        bound_transform_x_prepend(
-                            player_transform_mut,
-                            -MOVEMENT_SPEED * time.delta_seconds(),
-                            min_height_and_width,
-                            max_width,
-                        );
-                    }
 
-                    if input.key_is_down(VirtualKeyCode::D) {
+                    let move_x = axis_value(&input, MOVE_X);
+                    if move_x != 0.0 {
                         bound_transform_x_prepend(
                             player_transform_mut,
-                            MOVEMENT_SPEED * time.delta_seconds(),
+                            move_x * MOVEMENT_SPEED * time.delta_seconds(),
                             min_height_and_width,
                             max_width,
                         );
                     }
                 }
 
-                // Rotation keys
+                // Rotation axis
                 {
-                    if input.key_is_down(VirtualKeyCode::Left) {
-                        player_transform_mut.rotate_2d(-ROTATION_SPEED * time.delta_seconds());
-                    }
-
-                    if input.key_is_down(VirtualKeyCode::Right) {
-                        player_transform_mut.rotate_2d(ROTATION_SPEED * time.delta_seconds());
+                    let rotate = axis_value(&input, ROTATE);
+                    if rotate != 0.0 {
+                        player_transform_mut
+                            .rotate_2d(rotate * ROTATION_SPEED * time.delta_seconds());
                     }
                 }
             }
@@ -625,7 +552,7 @@ impl<'s> System<'s> for CovidSystem {
 
             let mut transform = Transform::default();
 
-            transform.set_translation_xyz(dimensions.height() * 0.5, dimensions.width() * 0.5, 3.0);
+            transform.set_translation_xyz(PLAYFIELD_WIDTH * 0.5, PLAYFIELD_HEIGHT * 0.5, 3.0);
 
             self.player_entity = Some(
                 lazy.create_entity(&*entities)
@@ -633,6 +560,7 @@ impl<'s> System<'s> for CovidSystem {
                     .with(transform)
                     .with(Transparent)
                     .with(LevelComponent)
+                    .with(PlayerComponent)
                     .build(),
             );
         }
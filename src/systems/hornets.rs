@@ -2,7 +2,7 @@ use amethyst::core::ecs::{
     Component, DenseVecStorage, Entities, LazyUpdate, Read, ReadExpect, ReaderId, System, Write,
 };
 
-use crate::every_n_seconds;
+use crate::playfield::{Playfield, PlayfieldUiComponent};
 use crate::resources::high_scores::CurrentLevelScoreResource;
 use crate::systems::{distance_between_points, load_sprite_system};
 use amethyst::assets::Loader;
@@ -11,18 +11,23 @@ use amethyst::core::Time;
 use amethyst::derive::SystemDesc;
 use amethyst::ecs::prelude::*;
 use amethyst::renderer::{SpriteRender, SpriteSheet, Texture};
-use amethyst::ui::{Anchor, UiEvent, UiEventType, UiImage, UiTransform};
+use amethyst::ui::{UiEvent, UiEventType, UiImage, UiTransform};
 use rand::Rng;
 
 use crate::audio::sound_keys::{BEE_TAP_SOUND, BUG_SPRAY_SOUND, FLY_SWAT_SOUND, HIVE_TRAP_SOUND};
 use crate::audio::{play_sound_system, SoundsResource};
+use crate::input::action_is_down;
+use crate::input::action_names::USE_ABILITY;
 use crate::resources::abilities::{AbilitiesResource, AbilityType};
+use crate::resources::rng::RngResource;
+use crate::resources::spawn_timeline::{EnemyType, SpawnRequestsResource};
+use crate::states::hornets::HornetStateResource;
 use crate::states::LevelComponent;
 use crate::systems::ability_bar::RemoveItem;
+use crate::timers::LifetimeComponent;
 use amethyst::input::{InputHandler, StringBindings};
 use amethyst::prelude::Builder;
 use amethyst::window::ScreenDimensions;
-use amethyst::winit::MouseButton;
 use amethyst::{
     assets::AssetStorage,
     audio::{output::Output, Source},
@@ -34,27 +39,16 @@ pub const SWATTER_HEIGHT_AND_WIDTH: f32 = 240.0;
 
 pub const HIVE_HEIGHT_AND_WIDTH: f32 = 100.0;
 
+/// Tags an entity as a bee. Bees get away once their `LifetimeComponent` runs out.
 #[derive(Default)]
-pub struct Bee {
-    /// The frame when the bee should be removed.
-    pub expiration_frame: u64,
-}
+pub struct Bee;
 impl Component for Bee {
     type Storage = DenseVecStorage<Self>;
 }
 
-/// Create a UiTransform easily.
-fn create_ui_transform(x_pos: f32, y_pos: f32, height_and_width: f32) -> UiTransform {
-    UiTransform::new(
-        (x_pos + y_pos).to_string(),
-        Anchor::BottomLeft,
-        Anchor::Middle,
-        x_pos,
-        y_pos,
-        0.0,
-        height_and_width,
-        height_and_width,
-    )
+/// Create a UiTransform for a playfield element, placed for the current window size.
+fn create_ui_transform(playfield: &Playfield, layout: &PlayfieldUiComponent) -> UiTransform {
+    playfield.ui_transform((layout.x + layout.y).to_string(), layout)
 }
 
 #[derive(SystemDesc)]
@@ -83,13 +77,12 @@ impl HornetsSystem {
 
 impl<'s> System<'s> for HornetsSystem {
     type SystemData = (
-        Read<'s, Time>,
         Entities<'s>,
         Read<'s, AssetStorage<Texture>>,
         Read<'s, AssetStorage<SpriteSheet>>,
         ReadExpect<'s, Loader>,
-        WriteStorage<'s, Bee>,
-        WriteStorage<'s, UiTransform>,
+        ReadStorage<'s, Bee>,
+        WriteStorage<'s, PlayfieldUiComponent>,
         Write<'s, CurrentLevelScoreResource>,
         Read<'s, EventChannel<UiEvent>>,
         Read<'s, LazyUpdate>,
@@ -99,18 +92,22 @@ impl<'s> System<'s> for HornetsSystem {
         Write<'s, AbilitiesResource>,
         Read<'s, InputHandler<StringBindings>>,
         ReadExpect<'s, ScreenDimensions>,
+        Write<'s, RngResource>,
+        Write<'s, HornetStateResource>,
+        Write<'s, SpawnRequestsResource>,
+        WriteStorage<'s, LifetimeComponent>,
+        Read<'s, Time>,
     );
 
     fn run(
         &mut self,
         (
-            time,
             entities,
             texture_storage,
             sheet_storage,
             loader,
-            mut bee_storage,
-            mut ui_transform_storage,
+            bee_storage,
+            mut playfield_ui_storage,
             mut score,
             events,
             lazy,
@@ -120,9 +117,19 @@ impl<'s> System<'s> for HornetsSystem {
             mut abilities,
             input,
             dimensions,
+            mut rng_resource,
+            mut level_state,
+            mut spawn_requests,
+            mut lifetime_storage,
+            time,
         ): Self::SystemData,
     ) {
-        let mut rng = rand::thread_rng();
+        let rng = &mut rng_resource.rng;
+
+        let playfield = Playfield::from_dimensions(&dimensions);
+
+        // Bees deleted this frame. Deleted entities still show up in joins until the end of the frame.
+        let mut removed_bees: Vec<Entity> = Vec::new();
 
         // All indexes in this ability will be removed from active_abilities
         let mut should_be_deactivated_abilities: Vec<usize> = Vec::new();
@@ -131,10 +138,9 @@ impl<'s> System<'s> for HornetsSystem {
         for (index, ability) in abilities.available_abilities.iter().enumerate() {
             // If that ability is active
             if abilities.active_abilities.contains(&index) {
-                let mut mouse_pos = input.mouse_position().unwrap_or((0., 0.));
-
-                // Mouse pos height is determined from top left instead of bottom left so we have to flip this.
-                mouse_pos.1 = dimensions.height() - mouse_pos.1;
+                // The mouse is in window pixels from the top left, so convert it to a playfield point.
+                let mouse_pos =
+                    playfield.mouse_to_playfield(input.mouse_position().unwrap_or((0., 0.)));
 
                 match ability.info.ability_type {
                     AbilityType::FlySwatter => {
@@ -150,10 +156,10 @@ impl<'s> System<'s> for HornetsSystem {
                             }
                         } else if let Some(fly_swatter) = self.swatter {
                             let (swatter_x, swatter_y) = {
-                                let swatter_ui_transform =
-                                    ui_transform_storage.get_mut(fly_swatter).unwrap();
+                                let swatter_layout =
+                                    playfield_ui_storage.get_mut(fly_swatter).unwrap();
 
-                                *swatter_ui_transform = create_ui_transform(
+                                *swatter_layout = PlayfieldUiComponent::square(
                                     mouse_pos.0,
                                     mouse_pos.1,
                                     SWATTER_HEIGHT_AND_WIDTH,
@@ -162,7 +168,7 @@ impl<'s> System<'s> for HornetsSystem {
                                 (mouse_pos.0, mouse_pos.1)
                             };
 
-                            if input.mouse_button_is_down(MouseButton::Left) {
+                            if action_is_down(&input, USE_ABILITY) {
                                 // Can only use swatter once.
                                 should_be_deactivated_abilities.push(index);
                                 entities
@@ -177,21 +183,23 @@ impl<'s> System<'s> for HornetsSystem {
                                     &audio_output,
                                 );
 
-                                for (entity, _bee, bee_ui_transform) in
-                                    (&entities, &bee_storage, &ui_transform_storage).join()
+                                for (entity, _bee, bee_layout) in
+                                    (&entities, &bee_storage, &playfield_ui_storage).join()
                                 {
                                     if distance_between_points(
                                         swatter_x,
                                         swatter_y,
-                                        bee_ui_transform.pixel_x(),
-                                        bee_ui_transform.pixel_y(),
+                                        bee_layout.x,
+                                        bee_layout.y,
                                     ) <= SWATTER_HEIGHT_AND_WIDTH * 0.5
                                     {
                                         // Delete the bee
                                         entities.delete(entity).expect("Couldn't delete bee.");
+                                        removed_bees.push(entity);
 
                                         // Increase the score
                                         score.score += 1;
+                                        level_state.bees_killed_by_abilities += 1;
                                     }
                                 }
                             }
@@ -204,7 +212,7 @@ impl<'s> System<'s> for HornetsSystem {
                                 0,
                             );
 
-                            let ui_transform = create_ui_transform(
+                            let layout = PlayfieldUiComponent::square(
                                 mouse_pos.0,
                                 mouse_pos.1,
                                 SWATTER_HEIGHT_AND_WIDTH,
@@ -215,7 +223,8 @@ impl<'s> System<'s> for HornetsSystem {
                                     // Tag entity with LevelComponent so it gets deleted on close.
                                     .with(LevelComponent)
                                     .with(UiImage::Sprite(swatter_sprite))
-                                    .with(ui_transform)
+                                    .with(create_ui_transform(&playfield, &layout))
+                                    .with(layout)
                                     .build(),
                             );
                         }
@@ -229,9 +238,11 @@ impl<'s> System<'s> for HornetsSystem {
                         for (entity, _bee) in (&entities, &bee_storage).join() {
                             // Delete the bee
                             entities.delete(entity).expect("Couldn't delete bee.");
+                            removed_bees.push(entity);
 
                             // Increase the score
                             score.score += 1;
+                            level_state.bees_killed_by_abilities += 1;
                         }
                     }
                     AbilityType::HiveTrap => {
@@ -247,10 +258,10 @@ impl<'s> System<'s> for HornetsSystem {
                             }
                         } else if let Some(hive_trap) = self.hive {
                             let (hive_trap_x, hive_trap_y) = {
-                                let hive_trap_transform =
-                                    ui_transform_storage.get_mut(hive_trap).unwrap();
+                                let hive_trap_layout =
+                                    playfield_ui_storage.get_mut(hive_trap).unwrap();
 
-                                *hive_trap_transform = create_ui_transform(
+                                *hive_trap_layout = PlayfieldUiComponent::square(
                                     mouse_pos.0,
                                     mouse_pos.1,
                                     HIVE_HEIGHT_AND_WIDTH,
@@ -259,7 +270,7 @@ impl<'s> System<'s> for HornetsSystem {
                                 (mouse_pos.0, mouse_pos.1)
                             };
 
-                            if input.mouse_button_is_down(MouseButton::Left) {
+                            if action_is_down(&input, USE_ABILITY) {
                                 // Can only use hive once.
                                 should_be_deactivated_abilities.push(index);
                                 entities
@@ -274,26 +285,30 @@ impl<'s> System<'s> for HornetsSystem {
                                     &audio_output,
                                 );
 
-                                for (bee, bee_ui_transform) in
-                                    (&mut bee_storage, &mut ui_transform_storage).join()
+                                for (_, bee_lifetime, bee_layout) in (
+                                    &bee_storage,
+                                    &mut lifetime_storage,
+                                    &mut playfield_ui_storage,
+                                )
+                                    .join()
                                 {
                                     // If the bee is nearby
                                     if distance_between_points(
                                         hive_trap_x,
                                         hive_trap_y,
-                                        bee_ui_transform.pixel_x(),
-                                        bee_ui_transform.pixel_y(),
+                                        bee_layout.x,
+                                        bee_layout.y,
                                     ) <= 200.0
                                     {
                                         // Move the bee close to the hive
-                                        *bee_ui_transform = create_ui_transform(
+                                        *bee_layout = PlayfieldUiComponent::square(
                                             hive_trap_x + rng.gen_range(-10., 10.),
                                             hive_trap_y + rng.gen_range(-10., 10.),
                                             BEE_SPRITE_HEIGHT_AND_WIDTH,
                                         );
 
                                         // Extend the bee's lifetime
-                                        bee.expiration_frame += rng.gen_range(60, 120);
+                                        bee_lifetime.extend(rng.gen_range(1., 2.));
                                     }
                                 }
                             }
@@ -306,7 +321,7 @@ impl<'s> System<'s> for HornetsSystem {
                                 0,
                             );
 
-                            let ui_transform = create_ui_transform(
+                            let layout = PlayfieldUiComponent::square(
                                 mouse_pos.0,
                                 mouse_pos.1,
                                 HIVE_HEIGHT_AND_WIDTH,
@@ -317,7 +332,8 @@ impl<'s> System<'s> for HornetsSystem {
                                     // Tag entity with LevelComponent so it gets deleted on close.
                                     .with(LevelComponent)
                                     .with(UiImage::Sprite(hive_trap))
-                                    .with(ui_transform)
+                                    .with(create_ui_transform(&playfield, &layout))
+                                    .with(layout)
                                     .build(),
                             );
                         }
@@ -344,47 +360,40 @@ impl<'s> System<'s> for HornetsSystem {
                     entities
                         .delete(ui_event.target)
                         .expect("Couldn't delete bee.");
+                    removed_bees.push(ui_event.target);
 
                     // Play sound
                     play_sound_system(BEE_TAP_SOUND, &sounds, &audio_storage, &audio_output);
 
                     // Increase the score
                     score.score += 1;
+                    level_state.bees_tapped += 1;
                 }
             }
         }
 
         if let Some(bee_sprite) = &self.bee_texture {
             // Spawn new bees and delete old ones
-            if every_n_seconds(0.5, &*time) {
-                let bees_to_spawn = rng.gen_range(1, 6);
-
-                let mut bees_left_to_spawn = bees_to_spawn;
-
-                while bees_left_to_spawn != 0 {
-                    let pos_x = rng.gen_range(10., 590.);
-                    let pos_y = rng.gen_range(100., 500.);
-
-                    lazy.create_entity(&entities)
-                        .with(UiImage::Sprite(bee_sprite.clone()))
-                        .with(create_ui_transform(
-                            pos_x,
-                            pos_y,
-                            BEE_SPRITE_HEIGHT_AND_WIDTH,
-                        ))
-                        .with(LevelComponent)
-                        .with(Bee {
-                            expiration_frame: time.frame_number() + rng.gen_range(50, 180),
-                        })
-                        .build();
-
-                    bees_left_to_spawn -= 1;
-                }
+            for point in spawn_requests.take(EnemyType::Bee) {
+                let layout =
+                    PlayfieldUiComponent::square(point.x, point.y, BEE_SPRITE_HEIGHT_AND_WIDTH);
+
+                lazy.create_entity(&entities)
+                    .with(UiImage::Sprite(bee_sprite.clone()))
+                    .with(create_ui_transform(&playfield, &layout))
+                    .with(layout)
+                    .with(LevelComponent)
+                    .with(Bee)
+                    .with(LifetimeComponent::new(rng.gen_range(0.8, 3.)))
+                    .build();
             }
 
-            for (entity, bee) in (&entities, &bee_storage).join() {
-                if time.frame_number() >= bee.expiration_frame {
+            for (entity, _, lifetime) in (&entities, &bee_storage, &mut lifetime_storage).join() {
+                if lifetime.tick(time.delta_seconds()) && !removed_bees.contains(&entity) {
                     entities.delete(entity).expect("Couldn't delete bee!");
+
+                    // The bee got away without being tapped.
+                    level_state.bee_escaped();
                 }
             }
         } else {
@@ -0,0 +1,45 @@
+use crate::resources::replay::ReplayPlaybackResource;
+use amethyst::core::shrev::{EventChannel, ReaderId};
+use amethyst::input::{InputEvent, InputHandler, StringBindings};
+use amethyst::window::ScreenDimensions;
+use amethyst::winit::Event;
+use amethyst::{derive::SystemDesc, ecs::prelude::*};
+
+/// Feeds the window's input events into the `InputHandler`, like Amethyst's `InputSystem`.
+/// While a replay is playing they're dropped, so only the recorded input reaches the handler (and the UI).
+#[derive(SystemDesc)]
+#[system_desc(name(LiveInputSystemDesc))]
+pub struct LiveInputSystem {
+    #[system_desc(event_channel_reader)]
+    reader_id: ReaderId<Event>,
+}
+
+impl LiveInputSystem {
+    pub fn new(reader_id: ReaderId<Event>) -> Self {
+        Self { reader_id }
+    }
+}
+
+impl<'s> System<'s> for LiveInputSystem {
+    type SystemData = (
+        Read<'s, EventChannel<Event>>,
+        Write<'s, InputHandler<StringBindings>>,
+        Write<'s, EventChannel<InputEvent<StringBindings>>>,
+        ReadExpect<'s, ScreenDimensions>,
+        Read<'s, ReplayPlaybackResource>,
+    );
+
+    fn run(
+        &mut self,
+        (window_events, mut input, mut input_events, dimensions, replay_playback): Self::SystemData,
+    ) {
+        input.send_frame_begin();
+
+        // The events are always read so they don't pile up until the replay ends.
+        for event in window_events.read(&mut self.reader_id) {
+            if !replay_playback.active {
+                input.send_event(event, &mut *input_events, dimensions.hidpi_factor() as f32);
+            }
+        }
+    }
+}
@@ -0,0 +1,666 @@
+use crate::audio::sound_keys::{CROP_EATEN_SOUND, LOCUST_SQUISH_SOUND, PESTICIDE_SOUND};
+use crate::audio::{play_sound_system, SoundsResource};
+use crate::input::action_names::{SHOOT, USE_ABILITY};
+use crate::input::axis_names::{MOVE_X, MOVE_Y, ROTATE};
+use crate::input::{action_is_down, axis_value};
+use crate::playfield::{Playfield, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+use crate::resources::abilities::{AbilitiesResource, AbilityType};
+use crate::resources::rng::RngResource;
+use crate::states::locusts::LocustStateResource;
+use crate::states::{LevelComponent, LevelSecondsResource};
+use crate::systems::ability_bar::RemoveItem;
+use crate::systems::wildfires::{
+    Droplet, DROPLET_HEIGHT_AND_WIDTH, DROPLET_MAX_SECONDS_ALIVE, DROPLET_SPEED,
+};
+use crate::systems::{distance_between_points, handle_collisions, load_sprite_system};
+use crate::timers::IntervalTimer;
+use crate::{bound_transform_x_prepend, bound_transform_y_prepend};
+use amethyst::assets::{AssetStorage, Loader};
+use amethyst::audio::output::Output;
+use amethyst::audio::Source;
+use amethyst::core::ecs::{
+    Component, DenseVecStorage, Entities, Entity, Join, LazyUpdate, Read, ReadExpect, Write,
+    WriteStorage,
+};
+use amethyst::core::math::Vector2;
+use amethyst::core::{Time, Transform};
+use amethyst::input::{InputHandler, StringBindings};
+use amethyst::prelude::Builder;
+use amethyst::renderer::{SpriteRender, SpriteSheet, Texture, Transparent};
+use amethyst::window::ScreenDimensions;
+use amethyst::{
+    derive::SystemDesc,
+    ecs::prelude::{System, SystemData},
+};
+use rand::Rng;
+use std::collections::HashMap;
+
+pub const MOVEMENT_SPEED: f32 = 250.0;
+pub const ROTATION_SPEED: f32 = 5.0;
+
+pub const FARMER_HEIGHT_AND_WIDTH: f32 = 70.0;
+
+pub const CROP_HEIGHT_AND_WIDTH: f32 = 40.0;
+/// Bites it takes for the locusts to eat a crop. Each bite shows the next sprite in `crop.png`.
+pub const CROP_BITES: usize = 2;
+
+pub const LOCUST_HEIGHT_AND_WIDTH: f32 = 24.0;
+pub const LOCUST_SPEED: f32 = 70.0;
+/// How quickly a locust turns towards where it wants to go.
+pub const LOCUST_STEERING: f32 = 2.5;
+/// How strongly a locust is pulled towards the middle of its flock.
+pub const FLOCK_COHESION: f32 = 0.8;
+
+pub const PESTICIDE_HEIGHT_AND_WIDTH: f32 = 200.0;
+
+/// Locusts closer than this to the farmer fly away while the scarecrow is active.
+pub const SCARECROW_RADIUS: f32 = 160.0;
+
+/// A crop the player defends. Locusts take bites out of it until it's eaten.
+#[derive(Default)]
+pub struct Crop {
+    pub bites_taken: usize,
+}
+impl Component for Crop {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Crop {
+    pub fn is_eaten(&self) -> bool {
+        self.bites_taken >= CROP_BITES
+    }
+}
+
+/// A locust flying with its flock.
+pub struct Locust {
+    /// The flock the locust spawned with.
+    pub flock: u64,
+    pub velocity: Vector2<f32>,
+}
+impl Component for Locust {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[derive(SystemDesc, Default)]
+pub struct LocustsSystem {
+    pub farmer_entity: Option<Entity>,
+    pub droplet_sprite: Option<SpriteRender>,
+    pub locust_sprite: Option<SpriteRender>,
+
+    pub pesticide: Option<Entity>,
+
+    /// The flock the next spawned locusts will belong to.
+    pub next_flock: u64,
+
+    /// Goes off whenever a new flock should fly in.
+    pub flock_timer: Option<IntervalTimer>,
+}
+
+impl<'s> System<'s> for LocustsSystem {
+    type SystemData = (
+        Entities<'s>,
+        Write<'s, LocustStateResource>,
+        Read<'s, LevelSecondsResource>,
+        Read<'s, Time>,
+        Read<'s, LazyUpdate>,
+        Read<'s, AssetStorage<Texture>>,
+        Read<'s, AssetStorage<SpriteSheet>>,
+        ReadExpect<'s, Loader>,
+        ReadExpect<'s, ScreenDimensions>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, Droplet>,
+        WriteStorage<'s, Locust>,
+        WriteStorage<'s, Crop>,
+        WriteStorage<'s, SpriteRender>,
+        Read<'s, InputHandler<StringBindings>>,
+        Write<'s, AbilitiesResource>,
+        Read<'s, AssetStorage<Source>>,
+        ReadExpect<'s, SoundsResource>,
+        Option<Read<'s, Output>>,
+        Write<'s, RngResource>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut level_state,
+            level_seconds,
+            time,
+            lazy,
+            texture_storage,
+            sheet_storage,
+            loader,
+            dimensions,
+            mut transform_storage,
+            mut droplet_storage,
+            mut locust_storage,
+            mut crop_storage,
+            mut sprite_render_storage,
+            input,
+            mut abilities,
+            audio_storage,
+            sounds,
+            audio_output,
+            mut rng_resource,
+        ): Self::SystemData,
+    ) {
+        let rng = &mut rng_resource.rng;
+
+        // Locusts and droplets deleted this frame. Deleted entities still show up in joins until the end of the frame.
+        let mut removed_locusts: Vec<Entity> = Vec::new();
+        let mut removed_droplets: Vec<Entity> = Vec::new();
+
+        // All indexes in this ability will be removed from active_abilities
+        let mut should_be_deactivated_abilities: Vec<usize> = Vec::new();
+
+        let mut wide_spray_is_active = false;
+        let mut scarecrow_is_active = false;
+
+        // Handle abilities
+        for (index, ability) in abilities.available_abilities.iter().enumerate() {
+            // If that ability is active
+            if abilities.active_abilities.contains(&index) {
+                // The mouse is in window pixels from the top left, so convert it to a playfield point.
+                let mouse_pos = Playfield::from_dimensions(&dimensions)
+                    .mouse_to_playfield(input.mouse_position().unwrap_or((0., 0.)));
+
+                match ability.info.ability_type {
+                    AbilityType::Pesticide => {
+                        // If the ability is about to expire
+                        if ability.current_state.percentage < 0.05 {
+                            // Delete the pesticide sprite.
+                            if let Some(pesticide) = self.pesticide {
+                                entities
+                                    .delete(pesticide)
+                                    .expect("Couldn't delete pesticide!");
+
+                                self.pesticide = None;
+                            }
+                        } else if let Some(pesticide) = self.pesticide {
+                            transform_storage
+                                .get_mut(pesticide)
+                                .unwrap()
+                                .set_translation_xyz(mouse_pos.0, mouse_pos.1, 4.0);
+
+                            if action_is_down(&input, USE_ABILITY) {
+                                // Can only spray pesticide once.
+                                should_be_deactivated_abilities.push(index);
+                                entities
+                                    .delete(pesticide)
+                                    .expect("Couldn't delete pesticide!");
+                                self.pesticide = None;
+
+                                play_sound_system(
+                                    PESTICIDE_SOUND,
+                                    &sounds,
+                                    &audio_storage,
+                                    &audio_output,
+                                );
+
+                                for (entity, _locust, locust_transform) in
+                                    (&entities, &locust_storage, &transform_storage).join()
+                                {
+                                    if distance_between_points(
+                                        mouse_pos.0,
+                                        mouse_pos.1,
+                                        locust_transform.translation().x,
+                                        locust_transform.translation().y,
+                                    ) <= (PESTICIDE_HEIGHT_AND_WIDTH * 0.5)
+                                        + (LOCUST_HEIGHT_AND_WIDTH * 0.5)
+                                    {
+                                        entities.delete(entity).expect("Couldn't delete locust!");
+                                        removed_locusts.push(entity);
+
+                                        level_state.locusts_killed_by_abilities += 1;
+                                    }
+                                }
+                            }
+                        } else {
+                            let pesticide_sprite = load_sprite_system(
+                                &texture_storage,
+                                &sheet_storage,
+                                &loader,
+                                "pesticide.png",
+                                0,
+                            );
+
+                            let mut transform = Transform::default();
+
+                            transform.set_translation_xyz(mouse_pos.0, mouse_pos.1, 4.0);
+
+                            self.pesticide = Some(
+                                lazy.create_entity(&entities)
+                                    // Tag entity with LevelComponent so it gets deleted on close.
+                                    .with(LevelComponent)
+                                    .with(pesticide_sprite)
+                                    .with(Transparent)
+                                    .with(transform)
+                                    .build(),
+                            );
+                        }
+                    }
+
+                    AbilityType::WideSpray => {
+                        wide_spray_is_active = true;
+                    }
+
+                    AbilityType::Scarecrow => {
+                        scarecrow_is_active = true;
+                    }
+
+                    _ => {}
+                }
+            }
+        }
+
+        // Remove abilities that have been used
+        for index in should_be_deactivated_abilities {
+            abilities.available_abilities[index]
+                .current_state
+                .percentage = 0.0;
+            abilities.active_abilities.remove_first_found_item(&index);
+        }
+
+        if let Some(farmer_entity) = &self.farmer_entity {
+            let (farmer_x, farmer_y) = {
+                let farmer_transform = transform_storage.get(*farmer_entity).unwrap();
+
+                (
+                    farmer_transform.translation().x,
+                    farmer_transform.translation().y,
+                )
+            };
+
+            // Droplets
+            {
+                // Droplet physics
+                for (droplet, transform, entity) in
+                    (&mut droplet_storage, &mut transform_storage, &entities).join()
+                {
+                    droplet.seconds_alive += time.delta_seconds();
+
+                    transform.move_up(DROPLET_SPEED * time.delta_seconds());
+
+                    transform.prepend_translation_x(rng.gen_range(-6.0, 6.0));
+
+                    if droplet.seconds_alive >= DROPLET_MAX_SECONDS_ALIVE {
+                        entities.delete(entity).expect("Couldn't delete droplet!");
+                        removed_droplets.push(entity);
+                    }
+                }
+
+                let droplets: Vec<(Entity, f32, f32)> =
+                    (&droplet_storage, &transform_storage, &entities)
+                        .join()
+                        .filter(|(_, _, entity)| !removed_droplets.contains(entity))
+                        .map(|(_, transform, entity)| {
+                            (entity, transform.translation().x, transform.translation().y)
+                        })
+                        .collect();
+
+                // Droplet collisions
+                for (droplet_entity, droplet_x, droplet_y) in droplets {
+                    let mut droplet_hit = false;
+
+                    handle_collisions(
+                        &entities,
+                        &mut transform_storage,
+                        &mut locust_storage,
+                        LOCUST_HEIGHT_AND_WIDTH,
+                        DROPLET_HEIGHT_AND_WIDTH,
+                        droplet_x,
+                        droplet_y,
+                        |entity, _, _| {
+                            // Each droplet can only hit one locust.
+                            if droplet_hit || removed_locusts.contains(&entity) {
+                                return;
+                            }
+
+                            droplet_hit = true;
+
+                            entities.delete(entity).expect("Couldn't delete locust!");
+                            removed_locusts.push(entity);
+
+                            level_state.locusts_sprayed += 1;
+
+                            play_sound_system(
+                                LOCUST_SQUISH_SOUND,
+                                &sounds,
+                                &audio_storage,
+                                &audio_output,
+                            );
+                        },
+                    );
+
+                    if droplet_hit {
+                        entities
+                            .delete(droplet_entity)
+                            .expect("Couldn't delete droplet!");
+                    }
+                }
+            }
+
+            // Locusts
+            {
+                let standing_crops: Vec<(f32, f32)> = (&crop_storage, &transform_storage)
+                    .join()
+                    .filter(|(crop, _)| !crop.is_eaten())
+                    .map(|(_, transform)| (transform.translation().x, transform.translation().y))
+                    .collect();
+
+                // The middle of every flock and the crop it's heading for.
+                let flock_targets = {
+                    let mut flock_sums: HashMap<u64, (f32, f32, u32)> = HashMap::new();
+
+                    for (locust, transform, entity) in
+                        (&locust_storage, &transform_storage, &entities).join()
+                    {
+                        if removed_locusts.contains(&entity) {
+                            continue;
+                        }
+
+                        let sum = flock_sums.entry(locust.flock).or_insert((0., 0., 0));
+                        sum.0 += transform.translation().x;
+                        sum.1 += transform.translation().y;
+                        sum.2 += 1;
+                    }
+
+                    flock_sums
+                        .into_iter()
+                        .map(|(flock, (x, y, count))| {
+                            let center = Vector2::new(x / count as f32, y / count as f32);
+
+                            let target = standing_crops
+                                .iter()
+                                .min_by(|a, b| {
+                                    distance_between_points(center.x, center.y, a.0, a.1)
+                                        .partial_cmp(&distance_between_points(
+                                            center.x, center.y, b.0, b.1,
+                                        ))
+                                        .unwrap()
+                                })
+                                .map(|(x, y)| Vector2::new(*x, *y));
+
+                            (flock, (center, target))
+                        })
+                        .collect::<HashMap<_, _>>()
+                };
+
+                // Locust movement
+                for (locust, transform, entity) in
+                    (&mut locust_storage, &mut transform_storage, &entities).join()
+                {
+                    if removed_locusts.contains(&entity) {
+                        continue;
+                    }
+
+                    let position =
+                        Vector2::new(transform.translation().x, transform.translation().y);
+
+                    let (center, target) = flock_targets[&locust.flock];
+
+                    let away_from_farmer = position - Vector2::new(farmer_x, farmer_y);
+
+                    let mut desired_velocity =
+                        if scarecrow_is_active && away_from_farmer.norm() < SCARECROW_RADIUS {
+                            // Scared away from the farmer.
+                            away_from_farmer.normalize() * LOCUST_SPEED * 1.5
+                        } else if let Some(target) = target {
+                            (target - position).normalize() * LOCUST_SPEED
+                        } else {
+                            // Nothing left to eat, keep flying.
+                            locust.velocity
+                        };
+
+                    desired_velocity += (center - position) * FLOCK_COHESION;
+                    desired_velocity +=
+                        Vector2::new(rng.gen_range(-20.0, 20.0), rng.gen_range(-20.0, 20.0));
+
+                    let steering = (LOCUST_STEERING * time.delta_seconds()).min(1.0);
+                    locust.velocity += (desired_velocity - locust.velocity) * steering;
+
+                    transform.prepend_translation_x(locust.velocity.x * time.delta_seconds());
+                    transform.prepend_translation_y(locust.velocity.y * time.delta_seconds());
+
+                    // The sprite faces up.
+                    transform.set_rotation_2d(
+                        locust.velocity.y.atan2(locust.velocity.x) - std::f32::consts::FRAC_PI_2,
+                    );
+
+                    // Delete locusts that flew far off the screen.
+                    if transform.translation().x < -100.
+                        || transform.translation().x > PLAYFIELD_WIDTH + 100.
+                        || transform.translation().y < -100.
+                        || transform.translation().y > PLAYFIELD_HEIGHT + 100.
+                    {
+                        entities.delete(entity).expect("Couldn't delete locust!");
+                        removed_locusts.push(entity);
+                    }
+                }
+
+                let locusts: Vec<(Entity, f32, f32)> =
+                    (&locust_storage, &transform_storage, &entities)
+                        .join()
+                        .filter(|(_, _, entity)| !removed_locusts.contains(entity))
+                        .map(|(_, transform, entity)| {
+                            (entity, transform.translation().x, transform.translation().y)
+                        })
+                        .collect();
+
+                // Crop collisions. A locust that lands on a crop takes a bite and stays to eat.
+                for (locust_entity, locust_x, locust_y) in locusts {
+                    let mut took_bite = false;
+
+                    handle_collisions(
+                        &entities,
+                        &mut transform_storage,
+                        &mut crop_storage,
+                        CROP_HEIGHT_AND_WIDTH,
+                        LOCUST_HEIGHT_AND_WIDTH,
+                        locust_x,
+                        locust_y,
+                        |crop_entity, _, crop: &mut Crop| {
+                            if took_bite || crop.is_eaten() {
+                                return;
+                            }
+
+                            took_bite = true;
+                            crop.bites_taken += 1;
+
+                            if let Some(sprite) = sprite_render_storage.get_mut(crop_entity) {
+                                sprite.sprite_number = crop.bites_taken;
+                            }
+
+                            if crop.is_eaten() {
+                                level_state.eat_crop();
+
+                                play_sound_system(
+                                    CROP_EATEN_SOUND,
+                                    &sounds,
+                                    &audio_storage,
+                                    &audio_output,
+                                );
+                            }
+                        },
+                    );
+
+                    if took_bite {
+                        entities
+                            .delete(locust_entity)
+                            .expect("Couldn't delete locust!");
+                    }
+                }
+            }
+
+            let farmer_transform = transform_storage.get_mut(*farmer_entity).unwrap();
+
+            // Movement and shooting
+            {
+                let min_height_and_width = FARMER_HEIGHT_AND_WIDTH * 0.5;
+
+                let max_height = PLAYFIELD_HEIGHT - FARMER_HEIGHT_AND_WIDTH * 0.5;
+
+                let max_width = PLAYFIELD_WIDTH - FARMER_HEIGHT_AND_WIDTH * 0.5;
+
+                // Movement axes
+                {
+                    let move_y = axis_value(&input, MOVE_Y);
+                    if move_y != 0.0 {
+                        bound_transform_y_prepend(
+                            farmer_transform,
+                            move_y * MOVEMENT_SPEED * time.delta_seconds(),
+                            min_height_and_width,
+                            max_height,
+                        );
+                    }
+
+                    let move_x = axis_value(&input, MOVE_X);
+                    if move_x != 0.0 {
+                        bound_transform_x_prepend(
+                            farmer_transform,
+                            move_x * MOVEMENT_SPEED * time.delta_seconds(),
+                            min_height_and_width,
+                            max_width,
+                        );
+                    }
+                }
+
+                // Rotation axis (aims the sprayer)
+                {
+                    let rotate = axis_value(&input, ROTATE);
+                    if rotate != 0.0 {
+                        farmer_transform.rotate_2d(rotate * ROTATION_SPEED * time.delta_seconds());
+                    }
+                }
+
+                // Spraying
+                {
+                    if action_is_down(&input, SHOOT) {
+                        let droplet_sprite;
+
+                        if let Some(sprite) = &self.droplet_sprite {
+                            droplet_sprite = sprite.clone();
+                        } else {
+                            let new_sprite = load_sprite_system(
+                                &texture_storage,
+                                &sheet_storage,
+                                &loader,
+                                "droplet.png",
+                                0,
+                            );
+
+                            self.droplet_sprite = Some(new_sprite.clone());
+
+                            droplet_sprite = new_sprite;
+                        }
+
+                        // The wide spray fans five droplets out around the sprayer.
+                        let angles: &[f32] = if wide_spray_is_active {
+                            &[-0.5, -0.25, 0.0, 0.25, 0.5]
+                        } else {
+                            &[0.0]
+                        };
+
+                        for angle in angles {
+                            let mut droplet_transform = (*farmer_transform).clone();
+
+                            // The sprayer is on the farmer's right.
+                            droplet_transform.move_right(15.);
+                            droplet_transform.move_up(FARMER_HEIGHT_AND_WIDTH * 0.5);
+                            droplet_transform.rotate_2d(*angle);
+
+                            lazy.create_entity(&*entities)
+                                .with(droplet_sprite.clone())
+                                .with(droplet_transform)
+                                .with(LevelComponent)
+                                .with(Droplet { seconds_alive: 0. })
+                                .with(Transparent)
+                                .build();
+                        }
+                    }
+                }
+            }
+
+            // Flock spawning
+            {
+                if let Some(locust_sprite) = &self.locust_sprite {
+                    let flock_spawn_seconds = level_state.flock_spawn_seconds;
+
+                    let flock_timer = self
+                        .flock_timer
+                        .get_or_insert_with(|| IntervalTimer::new(flock_spawn_seconds));
+
+                    if flock_timer.tick(time.delta_seconds()) > 0 {
+                        let locusts_to_spawn =
+                            level_state.locusts_per_flock_at(level_seconds.seconds_elapsed);
+
+                        // Flocks fly in from the left or the right of the field.
+                        let from_left = rng.gen_bool(0.5);
+                        let spawn_x = if from_left {
+                            -20.
+                        } else {
+                            PLAYFIELD_WIDTH + 20.
+                        };
+                        let spawn_y = rng.gen_range(150., 450.);
+
+                        let flock = self.next_flock;
+                        self.next_flock += 1;
+
+                        for _ in 0..locusts_to_spawn {
+                            let mut transform = Transform::default();
+
+                            transform.set_translation_xyz(
+                                spawn_x + rng.gen_range(-30., 30.),
+                                spawn_y + rng.gen_range(-30., 30.),
+                                2.0,
+                            );
+
+                            let velocity = Vector2::new(
+                                if from_left {
+                                    LOCUST_SPEED
+                                } else {
+                                    -LOCUST_SPEED
+                                },
+                                0.,
+                            );
+
+                            lazy.create_entity(&entities)
+                                .with(locust_sprite.clone())
+                                .with(transform)
+                                .with(Transparent)
+                                .with(LevelComponent)
+                                .with(Locust { flock, velocity })
+                                .build();
+                        }
+                    }
+                } else {
+                    // Load locust texture
+                    self.locust_sprite = Some(load_sprite_system(
+                        &texture_storage,
+                        &sheet_storage,
+                        &loader,
+                        "locust.png",
+                        0,
+                    ));
+                }
+            }
+        } else {
+            let sprite =
+                load_sprite_system(&texture_storage, &sheet_storage, &loader, "farmer.png", 0);
+
+            let mut transform = Transform::default();
+
+            transform.set_translation_xyz(PLAYFIELD_WIDTH * 0.5, 110.0, 3.0);
+
+            self.farmer_entity = Some(
+                lazy.create_entity(&*entities)
+                    .with(sprite)
+                    .with(transform)
+                    .with(Transparent)
+                    .with(LevelComponent)
+                    .build(),
+            );
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use crate::audio::sound_keys::{
+    BOSS_APPEAR_SOUND, BOSS_DEFEATED_SOUND, BOSS_HIT_SOUND, COUGH_SOUND, SHIELD_DEFLECT_SOUND,
+};
+use crate::audio::{play_sound_system, SoundsResource};
+use crate::playfield::{Playfield, PLAYFIELD_WIDTH};
+use crate::resources::rng::RngResource;
+use crate::states::covid::CovidStateResource;
+use crate::states::{LevelComponent, LevelSecondsResource};
+use crate::systems::boss::{create_boss_health_bar, BossAttack, BossComponent, BossPhase};
+use crate::systems::covid::{CovidCellComponent, EnemyComponent};
+use crate::systems::wildfires::{Droplet, DROPLET_HEIGHT_AND_WIDTH};
+use crate::systems::{distance_between_points, load_sprite_system};
+use crate::FontsResource;
+use amethyst::assets::{AssetStorage, Loader};
+use amethyst::audio::output::Output;
+use amethyst::audio::Source;
+use amethyst::core::ecs::{
+    Component, DenseVecStorage, Entities, Entity, Join, LazyUpdate, Read, ReadExpect, ReadStorage,
+    Write, WriteStorage,
+};
+use amethyst::core::{Time, Transform};
+use amethyst::prelude::Builder;
+use amethyst::renderer::{SpriteRender, SpriteSheet, Texture, Transparent};
+use amethyst::window::ScreenDimensions;
+use amethyst::{
+    derive::SystemDesc,
+    ecs::prelude::{System, SystemData},
+};
+use rand::Rng;
+use std::f32::consts::PI;
+
+pub const MEGA_SPREADER_HEIGHT_AND_WIDTH: f32 = 140.0;
+pub const MEGA_SPREADER_SPEED: f32 = 50.0;
+
+/// Health taken off the Mega Spreader by each droplet.
+pub const DROPLET_DAMAGE: u64 = 1;
+
+/// The height the Mega Spreader drifts across the screen at.
+pub const MEGA_SPREADER_Y: f32 = 420.0;
+
+/// How far the Mega Spreader gets knocked back when a droplet hits it.
+pub const HIT_RECOIL: f32 = 2.0;
+/// The furthest the Mega Spreader can be knocked back.
+pub const MAX_RECOIL: f32 = 12.0;
+
+/// The phases the Mega Spreader cycles through.
+fn mega_spreader_phases() -> Vec<BossPhase> {
+    vec![
+        BossPhase {
+            attack: BossAttack::RadialBurst {
+                projectiles: 8,
+                interval_seconds: 1.5,
+            },
+            seconds: 6.0,
+        },
+        BossPhase {
+            attack: BossAttack::Shield,
+            seconds: 4.0,
+        },
+        BossPhase {
+            attack: BossAttack::RadialBurst {
+                projectiles: 12,
+                interval_seconds: 0.8,
+            },
+            seconds: 4.0,
+        },
+        BossPhase {
+            attack: BossAttack::Shield,
+            seconds: 3.0,
+        },
+    ]
+}
+
+/// Tags an entity as the Mega Spreader.
+pub struct MegaSpreaderComponent {
+    /// 1.0 when drifting right and -1.0 when drifting left.
+    pub direction: f32,
+}
+impl Component for MegaSpreaderComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Spawns the Mega Spreader boss in the COVID-19 level, fires its bursts and lets droplets damage it.
+#[derive(SystemDesc, Default)]
+pub struct MegaSpreaderSystem {
+    pub has_spawned: bool,
+    pub mega_spreader_sprite: Option<SpriteRender>,
+    pub covid_sprite: Option<SpriteRender>,
+}
+
+impl<'s> System<'s> for MegaSpreaderSystem {
+    type SystemData = (
+        Entities<'s>,
+        Write<'s, CovidStateResource>,
+        Read<'s, LevelSecondsResource>,
+        Read<'s, Time>,
+        Read<'s, LazyUpdate>,
+        Read<'s, AssetStorage<Texture>>,
+        Read<'s, AssetStorage<SpriteSheet>>,
+        ReadExpect<'s, Loader>,
+        ReadExpect<'s, ScreenDimensions>,
+        ReadExpect<'s, FontsResource>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, BossComponent>,
+        WriteStorage<'s, MegaSpreaderComponent>,
+        ReadStorage<'s, Droplet>,
+        Read<'s, AssetStorage<Source>>,
+        ReadExpect<'s, SoundsResource>,
+        Option<Read<'s, Output>>,
+        Write<'s, RngResource>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut level_state,
+            level_seconds,
+            time,
+            lazy,
+            texture_storage,
+            sheet_storage,
+            loader,
+            dimensions,
+            fonts,
+            mut transform_storage,
+            mut boss_storage,
+            mut mega_spreader_storage,
+            droplet_storage,
+            audio_storage,
+            sounds,
+            audio_output,
+            mut rng_resource,
+        ): Self::SystemData,
+    ) {
+        let rng = &mut rng_resource.rng;
+
+        let (mega_spreader_sprite, covid_sprite) =
+            if let (Some(mega_spreader_sprite), Some(covid_sprite)) =
+                (&self.mega_spreader_sprite, &self.covid_sprite)
+            {
+                (mega_spreader_sprite.clone(), covid_sprite.clone())
+            } else {
+                self.mega_spreader_sprite = Some(load_sprite_system(
+                    &texture_storage,
+                    &sheet_storage,
+                    &loader,
+                    "mega_spreader.png",
+                    0,
+                ));
+                self.covid_sprite = Some(load_sprite_system(
+                    &texture_storage,
+                    &sheet_storage,
+                    &loader,
+                    "covid.png",
+                    0,
+                ));
+
+                return;
+            };
+
+        // Spawn the Mega Spreader once the level has been running long enough
+        if !self.has_spawned {
+            if let Some(boss_seconds) = level_state.boss_seconds {
+                if level_seconds.seconds_elapsed >= boss_seconds {
+                    self.has_spawned = true;
+
+                    let mut transform = Transform::default();
+                    transform.set_translation_xyz(PLAYFIELD_WIDTH * 0.5, MEGA_SPREADER_Y, 1.0);
+
+                    let mega_spreader = lazy
+                        .create_entity(&entities)
+                        .with(mega_spreader_sprite)
+                        .with(transform)
+                        .with(Transparent)
+                        .with(LevelComponent)
+                        .with(BossComponent::new(
+                            level_state.boss_health,
+                            mega_spreader_phases(),
+                        ))
+                        .with(MegaSpreaderComponent { direction: 1.0 })
+                        .build();
+
+                    create_boss_health_bar(
+                        &entities,
+                        &lazy,
+                        &fonts,
+                        &Playfield::from_dimensions(&dimensions),
+                        mega_spreader,
+                        "MEGA SPREADER",
+                    );
+
+                    play_sound_system(BOSS_APPEAR_SOUND, &sounds, &audio_storage, &audio_output);
+                }
+            }
+
+            return;
+        }
+
+        // Droplets that could hit the Mega Spreader this frame.
+        let droplets: Vec<(Entity, f32, f32)> = (&entities, &droplet_storage, &transform_storage)
+            .join()
+            .map(|(entity, _, transform)| {
+                (entity, transform.translation().x, transform.translation().y)
+            })
+            .collect();
+
+        for (entity, boss, mega_spreader, transform) in (
+            &entities,
+            &mut boss_storage,
+            &mut mega_spreader_storage,
+            &mut transform_storage,
+        )
+            .join()
+        {
+            // Drift from side to side
+            let min_x = MEGA_SPREADER_HEIGHT_AND_WIDTH * 0.5;
+            let max_x = PLAYFIELD_WIDTH - MEGA_SPREADER_HEIGHT_AND_WIDTH * 0.5;
+
+            transform.prepend_translation_x(
+                mega_spreader.direction * MEGA_SPREADER_SPEED * time.delta_seconds(),
+            );
+
+            if transform.translation().x <= min_x {
+                mega_spreader.direction = 1.0;
+            } else if transform.translation().x >= max_x {
+                mega_spreader.direction = -1.0;
+            }
+
+            let (boss_x, boss_y) = (transform.translation().x, transform.translation().y);
+
+            // Radial bursts of covid cells
+            if let Some(projectiles) = boss.radial_burst_due(time.delta_seconds()) {
+                let offset = rng.gen_range(0., 2. * PI);
+
+                for index in 0..projectiles {
+                    let angle = offset + index as f32 * 2. * PI / projectiles as f32;
+
+                    let mut cell_transform = Transform::default();
+                    cell_transform.set_translation_xyz(boss_x, boss_y, 2.0);
+
+                    lazy.create_entity(&entities)
+                        .with(covid_sprite.clone())
+                        .with(cell_transform)
+                        .with(Transparent)
+                        .with(LevelComponent)
+                        .with(CovidCellComponent { heading: angle })
+                        .with(EnemyComponent)
+                        .build();
+                }
+
+                play_sound_system(COUGH_SOUND, &sounds, &audio_storage, &audio_output);
+            }
+
+            // Droplet hits
+            for (droplet_entity, droplet_x, droplet_y) in &droplets {
+                if distance_between_points(*droplet_x, *droplet_y, boss_x, boss_y)
+                    <= (0.5 * MEGA_SPREADER_HEIGHT_AND_WIDTH) + (0.5 * DROPLET_HEIGHT_AND_WIDTH)
+                {
+                    entities
+                        .delete(*droplet_entity)
+                        .expect("Couldn't delete droplet!");
+
+                    if boss.take_hit(DROPLET_DAMAGE) {
+                        let recoiled_y = (transform.translation().y + HIT_RECOIL)
+                            .min(MEGA_SPREADER_Y + MAX_RECOIL);
+                        transform.set_translation_y(recoiled_y);
+
+                        play_sound_system(BOSS_HIT_SOUND, &sounds, &audio_storage, &audio_output);
+                    } else {
+                        play_sound_system(
+                            SHIELD_DEFLECT_SOUND,
+                            &sounds,
+                            &audio_storage,
+                            &audio_output,
+                        );
+                    }
+                }
+            }
+
+            // Drift back down after being knocked back
+            if transform.translation().y > MEGA_SPREADER_Y {
+                let settled_y =
+                    (transform.translation().y - HIT_RECOIL * 0.25).max(MEGA_SPREADER_Y);
+                transform.set_translation_y(settled_y);
+            }
+
+            if boss.is_defeated() {
+                entities
+                    .delete(entity)
+                    .expect("Couldn't delete the Mega Spreader!");
+
+                level_state.defeat_boss();
+
+                play_sound_system(BOSS_DEFEATED_SOUND, &sounds, &audio_storage, &audio_output);
+            }
+        }
+    }
+}
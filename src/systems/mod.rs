@@ -1,13 +1,28 @@
 use amethyst::assets::{AssetStorage, Loader};
 use amethyst::core::ecs::shred::PanicHandler;
-use amethyst::core::ecs::{Component, Entities, Entity, Join, Read, WriteStorage};
+use amethyst::core::ecs::{Component, DenseVecStorage, Entities, Entity, Join, Read, WriteStorage};
 use amethyst::core::Transform;
 use amethyst::renderer::{ImageFormat, SpriteRender, SpriteSheet, SpriteSheetFormat, Texture};
 
 pub mod ability_bar;
+pub mod boss;
 pub mod covid;
 pub mod hornets;
+pub mod live_input;
+pub mod locusts;
+pub mod mega_spreader;
+pub mod mute;
+pub mod playfield;
+pub mod spawner;
+pub mod toilet_paper;
 pub mod wildfires;
+pub mod zoom;
+
+/// Tags the entity the player controls. Enemies from spawn timelines don't spawn too close to it.
+pub struct PlayerComponent;
+impl Component for PlayerComponent {
+    type Storage = DenseVecStorage<Self>;
+}
 
 /// Detects collisions between components and takes an `on_collide` func which can respond to the collision.
 pub fn handle_collisions<T: Component>(
@@ -34,7 +49,7 @@ pub fn handle_collisions<T: Component>(
 }
 
 /// Calculates the distance between 2 points.
-fn distance_between_points(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
+pub(crate) fn distance_between_points(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
     ((y2 - y1) * (y2 - y1) + (x2 - x1) * (x2 - x1)).sqrt()
 }
 
@@ -0,0 +1,59 @@
+use crate::audio::SoundsResource;
+use crate::input::action_names::MUTE_ALL;
+use crate::input::{action_is_down, TextEntryActiveResource};
+use crate::resources::save_data::SaveDataResource;
+use amethyst::audio::AudioSink;
+use amethyst::core::ecs::{Read, Write, WriteExpect};
+use amethyst::input::{InputHandler, StringBindings};
+use amethyst::{
+    derive::SystemDesc,
+    ecs::prelude::{System, SystemData},
+};
+
+/// Mutes and unmutes everything when the mute hotkey is pressed, and saves the change.
+/// Does nothing while the player is typing (see `TextEntryActiveResource`).
+#[derive(SystemDesc, Default)]
+pub struct MuteSystem {
+    /// Whether the hotkey was down last frame, so holding it only counts as one press.
+    hotkey_down: bool,
+    /// Whether the player was typing last frame.
+    /// The key that finishes typing (like the one bound to a control) is handled before this system runs,
+    /// so it has to be ignored for one more frame.
+    text_entry_was_active: bool,
+}
+
+impl<'s> System<'s> for MuteSystem {
+    type SystemData = (
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, TextEntryActiveResource>,
+        WriteExpect<'s, SaveDataResource>,
+        Option<Write<'s, AudioSink>>,
+        Option<Write<'s, SoundsResource>>,
+    );
+
+    fn run(&mut self, (input, text_entry, mut save_data, sink, sounds): Self::SystemData) {
+        let typing = text_entry.active || self.text_entry_was_active;
+        self.text_entry_was_active = text_entry.active;
+
+        let down = action_is_down(&input, MUTE_ALL);
+        let pressed = down && !self.hotkey_down && !typing;
+        self.hotkey_down = down;
+
+        if !pressed {
+            return;
+        }
+
+        let audio = &mut save_data.data.settings.audio;
+        audio.muted = !audio.muted;
+
+        if let Some(mut sink) = sink {
+            sink.set_volume(audio.music_level());
+        }
+
+        if let Some(mut sounds) = sounds {
+            sounds.volume = audio.sfx_level();
+        }
+
+        save_data.save();
+    }
+}
@@ -0,0 +1,86 @@
+use crate::playfield::{Playfield, PlayfieldUiComponent, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
+use amethyst::core::ecs::{Entities, Join, ReadExpect, ReadStorage, WriteStorage};
+use amethyst::core::Transform;
+use amethyst::renderer::Camera;
+use amethyst::ui::{UiText, UiTransform};
+use amethyst::window::ScreenDimensions;
+use amethyst::{
+    derive::SystemDesc,
+    ecs::prelude::{System, SystemData},
+};
+
+/// Keeps the playfield fitted to the window.
+/// Rebuilds the camera when the window is resized and moves `PlayfieldUiComponent`s to where they belong on screen.
+#[derive(SystemDesc, Default)]
+pub struct PlayfieldLayoutSystem {
+    /// The playfield the camera was last built for.
+    playfield: Option<Playfield>,
+}
+
+impl<'s> System<'s> for PlayfieldLayoutSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, ScreenDimensions>,
+        WriteStorage<'s, Camera>,
+        WriteStorage<'s, Transform>,
+        ReadStorage<'s, PlayfieldUiComponent>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiText>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            dimensions,
+            mut cameras,
+            mut transforms,
+            playfield_uis,
+            mut ui_transforms,
+            mut ui_texts,
+        ): Self::SystemData,
+    ) {
+        let playfield = Playfield::from_dimensions(&dimensions);
+
+        if self.playfield != Some(playfield) {
+            for (camera, transform) in (&mut cameras, &mut transforms).join() {
+                *camera = Camera::standard_2d(playfield.view_width(), playfield.view_height());
+
+                transform.set_translation_x(PLAYFIELD_WIDTH * 0.5);
+                transform.set_translation_y(PLAYFIELD_HEIGHT * 0.5);
+            }
+
+            self.playfield = Some(playfield);
+        }
+
+        for (entity, rect) in (&entities, &playfield_uis).join() {
+            if let Some(ui_transform) = ui_transforms.get(entity) {
+                let placed = playfield.ui_transform(ui_transform.id.clone(), rect);
+
+                // Only touch transforms that moved so the UI isn't flagged as changed every frame.
+                if ui_transform.local_x != placed.local_x
+                    || ui_transform.local_y != placed.local_y
+                    || ui_transform.local_z != placed.local_z
+                    || ui_transform.width != placed.width
+                    || ui_transform.height != placed.height
+                {
+                    let ui_transform = ui_transforms.get_mut(entity).unwrap();
+
+                    ui_transform.local_x = placed.local_x;
+                    ui_transform.local_y = placed.local_y;
+                    ui_transform.local_z = placed.local_z;
+                    ui_transform.width = placed.width;
+                    ui_transform.height = placed.height;
+                }
+            }
+
+            if let (Some(font_size), Some(ui_text)) = (rect.font_size, ui_texts.get(entity)) {
+                let scaled_font_size = font_size * playfield.scale;
+
+                if ui_text.font_size != scaled_font_size {
+                    ui_texts.get_mut(entity).unwrap().font_size = scaled_font_size;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::resources::rng::RngResource;
+use crate::resources::spawn_timeline::{SpawnRequestsResource, SpawnTimelineResource};
+use crate::states::LevelSecondsResource;
+use crate::systems::PlayerComponent;
+use amethyst::core::ecs::{Join, Read, ReadStorage, Write};
+use amethyst::core::{Time, Transform};
+use amethyst::{
+    derive::SystemDesc,
+    ecs::prelude::{System, SystemData},
+};
+
+/// Goes through the level's spawn timeline and requests the enemies of every wave that's due.
+/// The level's system creates the requested enemies.
+#[derive(SystemDesc, Default)]
+pub struct SpawnerSystem;
+
+impl<'s> System<'s> for SpawnerSystem {
+    type SystemData = (
+        Read<'s, LevelSecondsResource>,
+        Read<'s, Time>,
+        Write<'s, SpawnTimelineResource>,
+        Write<'s, SpawnRequestsResource>,
+        Write<'s, RngResource>,
+        ReadStorage<'s, PlayerComponent>,
+        ReadStorage<'s, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            level_seconds,
+            time,
+            mut timeline_resource,
+            mut spawn_requests,
+            mut rng_resource,
+            player_storage,
+            transform_storage,
+        ): Self::SystemData,
+    ) {
+        let rng = &mut rng_resource.rng;
+
+        // Requests that weren't taken last frame are dropped.
+        spawn_requests.requests.clear();
+
+        let player_position = (&player_storage, &transform_storage)
+            .join()
+            .next()
+            .map(|(_, transform)| (transform.translation().x, transform.translation().y));
+
+        let timeline = &mut *timeline_resource;
+
+        for (wave, timer) in timeline.waves.iter().zip(timeline.timers.iter_mut()) {
+            // A long frame can make a wave due more than once.
+            for _ in 0..timer.tick(time.delta_seconds()) {
+                if wave.is_over(level_seconds.seconds_elapsed) {
+                    break;
+                }
+
+                for point in wave.spawn_points(level_seconds.seconds_elapsed, player_position, rng)
+                {
+                    spawn_requests.requests.push((wave.enemy, point));
+                }
+            }
+        }
+    }
+}
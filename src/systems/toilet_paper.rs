@@ -0,0 +1,428 @@
+use crate::audio::sound_keys::{CASH_REGISTER_SOUND, RESTOCK_SOUND, TURNED_AWAY_SOUND};
+use crate::audio::{play_sound_system, SoundsResource};
+use crate::input::axis_names::{MOVE_X, MOVE_Y};
+use crate::input::axis_value;
+use crate::playfield::PLAYFIELD_HEIGHT;
+use crate::resources::abilities::{AbilitiesResource, AbilityType};
+use crate::resources::rng::RngResource;
+use crate::states::toilet_paper::ToiletPaperStateResource;
+use crate::states::{LevelComponent, LevelSecondsResource};
+use crate::systems::ability_bar::RemoveItem;
+use crate::systems::{distance_between_points, handle_collisions, load_sprite_system};
+use crate::timers::IntervalTimer;
+use crate::{bound_transform_x_prepend, bound_transform_y_prepend};
+use amethyst::assets::{AssetStorage, Loader};
+use amethyst::audio::output::Output;
+use amethyst::audio::Source;
+use amethyst::core::ecs::{
+    Component, DenseVecStorage, Entities, Entity, Join, LazyUpdate, Read, ReadExpect, Write,
+    WriteStorage,
+};
+use amethyst::core::{Time, Transform};
+use amethyst::input::{InputHandler, StringBindings};
+use amethyst::prelude::Builder;
+use amethyst::renderer::{SpriteRender, SpriteSheet, Texture, Transparent};
+use amethyst::{
+    derive::SystemDesc,
+    ecs::prelude::{System, SystemData},
+};
+use rand::Rng;
+
+pub const MOVEMENT_SPEED: f32 = 220.0;
+
+pub const CLERK_HEIGHT_AND_WIDTH: f32 = 60.0;
+
+pub const SHOPPER_HEIGHT_AND_WIDTH: f32 = 40.0;
+pub const SHOPPER_SPEED: f32 = 50.0;
+/// Rolls a shopper grabs when they reach the shelf.
+pub const SHOPPER_ROLLS: u64 = 3;
+
+pub const HOARDER_SPEED: f32 = 30.0;
+/// Rolls a hoarder grabs when they reach the shelf.
+pub const HOARDER_ROLLS: u64 = 12;
+
+pub const RESTOCK_PACK_HEIGHT_AND_WIDTH: f32 = 40.0;
+/// Rolls put back on the shelves by picking up a restock pack.
+pub const RESTOCK_PACK_ROLLS: u64 = 10;
+/// Rolls put back on the shelves by the restock truck.
+pub const RESTOCK_TRUCK_ROLLS: u64 = 40;
+
+/// The y position of the middle of each lane, from the bottom.
+pub const LANES: &[f32] = &[130.0, 210.0, 290.0, 370.0, 450.0];
+
+/// Shoppers grab their rolls once they reach this x position.
+pub const SHELF_X: f32 = 500.0;
+
+/// Tags an entity as a shopper walking towards the shelf.
+pub struct ShopperComponent {
+    /// How many rolls the shopper grabs at the shelf.
+    pub rolls: u64,
+    pub speed: f32,
+}
+impl Component for ShopperComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Tags an entity as a restock pack.
+pub struct RestockPackComponent;
+impl Component for RestockPackComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[derive(SystemDesc, Default)]
+pub struct ToiletPaperSystem {
+    pub clerk_entity: Option<Entity>,
+    pub shopper_sprite: Option<SpriteRender>,
+    pub hoarder_sprite: Option<SpriteRender>,
+    pub restock_pack_sprite: Option<SpriteRender>,
+
+    /// Goes off whenever new shoppers should come in.
+    pub shopper_timer: Option<IntervalTimer>,
+    /// Goes off whenever a restock pack should appear.
+    pub restock_pack_timer: Option<IntervalTimer>,
+}
+
+impl<'s> System<'s> for ToiletPaperSystem {
+    type SystemData = (
+        Entities<'s>,
+        Write<'s, ToiletPaperStateResource>,
+        Read<'s, LevelSecondsResource>,
+        Read<'s, Time>,
+        Read<'s, LazyUpdate>,
+        Read<'s, AssetStorage<Texture>>,
+        Read<'s, AssetStorage<SpriteSheet>>,
+        ReadExpect<'s, Loader>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, ShopperComponent>,
+        WriteStorage<'s, RestockPackComponent>,
+        Read<'s, InputHandler<StringBindings>>,
+        Write<'s, AbilitiesResource>,
+        Read<'s, AssetStorage<Source>>,
+        ReadExpect<'s, SoundsResource>,
+        Option<Read<'s, Output>>,
+        Write<'s, RngResource>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut level_state,
+            level_seconds,
+            time,
+            lazy,
+            texture_storage,
+            sheet_storage,
+            loader,
+            mut transform_storage,
+            mut shopper_storage,
+            mut restock_pack_storage,
+            input,
+            mut abilities,
+            audio_storage,
+            sounds,
+            audio_output,
+            mut rng_resource,
+        ): Self::SystemData,
+    ) {
+        let rng = &mut rng_resource.rng;
+
+        // Shoppers turned away this frame. Deleted entities still show up in joins until the end of the frame.
+        let mut turned_away_shoppers: Vec<Entity> = Vec::new();
+
+        // All indexes in this ability will be removed from active_abilities
+        let mut should_be_deactivated_abilities: Vec<usize> = Vec::new();
+
+        let mut purchase_limit_is_active = false;
+
+        // Handle abilities
+        for (index, ability) in abilities.available_abilities.iter().enumerate() {
+            // If that ability is active
+            if abilities.active_abilities.contains(&index) {
+                match ability.info.ability_type {
+                    AbilityType::PurchaseLimit => {
+                        purchase_limit_is_active = true;
+                    }
+
+                    AbilityType::RestockTruck => {
+                        // The truck restocks the shelves once.
+                        should_be_deactivated_abilities.push(index);
+
+                        level_state.restock(RESTOCK_TRUCK_ROLLS);
+
+                        play_sound_system(RESTOCK_SOUND, &sounds, &audio_storage, &audio_output);
+                    }
+
+                    _ => {}
+                }
+            }
+        }
+
+        // Remove abilities that have been used
+        for index in should_be_deactivated_abilities {
+            abilities.active_abilities.remove_first_found_item(&index);
+        }
+
+        if let Some(clerk_entity) = &self.clerk_entity {
+            let (clerk_x, clerk_y) = {
+                let clerk_transform = transform_storage.get(*clerk_entity).unwrap();
+
+                (
+                    clerk_transform.translation().x,
+                    clerk_transform.translation().y,
+                )
+            };
+
+            // Shoppers
+            {
+                // Shopper collisions
+                handle_collisions(
+                    &entities,
+                    &mut transform_storage,
+                    &mut shopper_storage,
+                    SHOPPER_HEIGHT_AND_WIDTH,
+                    CLERK_HEIGHT_AND_WIDTH,
+                    clerk_x,
+                    clerk_y,
+                    |entity, _, _| {
+                        entities.delete(entity).expect("Couldn't delete shopper!");
+                        turned_away_shoppers.push(entity);
+                        level_state.shoppers_turned_away += 1;
+
+                        play_sound_system(
+                            TURNED_AWAY_SOUND,
+                            &sounds,
+                            &audio_storage,
+                            &audio_output,
+                        );
+                    },
+                );
+
+                // Shopper movement
+                for (shopper, shopper_transform, entity) in
+                    (&shopper_storage, &mut transform_storage, &entities).join()
+                {
+                    shopper_transform.prepend_translation_x(shopper.speed * time.delta_seconds());
+
+                    // The shopper reached the shelf and grabs their rolls.
+                    if shopper_transform.translation().x >= SHELF_X
+                        && !turned_away_shoppers.contains(&entity)
+                    {
+                        entities.delete(entity).expect("Couldn't delete shopper!");
+
+                        let rolls = if purchase_limit_is_active {
+                            1
+                        } else {
+                            shopper.rolls
+                        };
+                        level_state.take_rolls(rolls);
+
+                        play_sound_system(
+                            CASH_REGISTER_SOUND,
+                            &sounds,
+                            &audio_storage,
+                            &audio_output,
+                        );
+                    }
+                }
+
+                // Shopper spawning
+                {
+                    if let (Some(shopper_sprite), Some(hoarder_sprite)) =
+                        (&self.shopper_sprite, &self.hoarder_sprite)
+                    {
+                        let shopper_spawn_seconds = level_state.shopper_spawn_seconds;
+
+                        let shopper_timer = self
+                            .shopper_timer
+                            .get_or_insert_with(|| IntervalTimer::new(shopper_spawn_seconds));
+
+                        if shopper_timer.tick(time.delta_seconds()) > 0 {
+                            let seconds_elapsed = level_seconds.seconds_elapsed;
+
+                            // Shoppers to spawn is from 1 to (1 + however many shopper ramps have gone by).
+                            let shoppers_to_spawn = rng.gen_range(
+                                1,
+                                2 + (seconds_elapsed / level_state.shopper_ramp_seconds) as u32,
+                            );
+
+                            // More and more of the shoppers are hoarders.
+                            let hoarder_chance = (0.1
+                                + (seconds_elapsed / level_state.hoarder_ramp_seconds) as f64)
+                                .min(0.5);
+
+                            for _ in 0..shoppers_to_spawn {
+                                let lane = LANES[rng.gen_range(0, LANES.len())];
+
+                                let (sprite, shopper) = if rng.gen_bool(hoarder_chance) {
+                                    (
+                                        hoarder_sprite.clone(),
+                                        ShopperComponent {
+                                            rolls: HOARDER_ROLLS,
+                                            speed: HOARDER_SPEED,
+                                        },
+                                    )
+                                } else {
+                                    (
+                                        shopper_sprite.clone(),
+                                        ShopperComponent {
+                                            rolls: SHOPPER_ROLLS,
+                                            speed: SHOPPER_SPEED,
+                                        },
+                                    )
+                                };
+
+                                let mut transform = Transform::default();
+
+                                transform.set_translation_xyz(
+                                    -SHOPPER_HEIGHT_AND_WIDTH * 0.5,
+                                    lane,
+                                    2.0,
+                                );
+
+                                lazy.create_entity(&entities)
+                                    .with(sprite)
+                                    .with(transform)
+                                    .with(Transparent)
+                                    .with(LevelComponent)
+                                    .with(shopper)
+                                    .build();
+                            }
+                        }
+                    } else {
+                        // Load shopper textures
+                        self.shopper_sprite = Some(load_sprite_system(
+                            &texture_storage,
+                            &sheet_storage,
+                            &loader,
+                            "shopper.png",
+                            0,
+                        ));
+                        self.hoarder_sprite = Some(load_sprite_system(
+                            &texture_storage,
+                            &sheet_storage,
+                            &loader,
+                            "hoarder.png",
+                            0,
+                        ));
+                    }
+                }
+            }
+
+            // Restock packs
+            {
+                // Restock pack collisions
+                handle_collisions(
+                    &entities,
+                    &mut transform_storage,
+                    &mut restock_pack_storage,
+                    RESTOCK_PACK_HEIGHT_AND_WIDTH,
+                    CLERK_HEIGHT_AND_WIDTH,
+                    clerk_x,
+                    clerk_y,
+                    |entity, _, _| {
+                        entities
+                            .delete(entity)
+                            .expect("Couldn't delete restock pack!");
+
+                        level_state.restock(RESTOCK_PACK_ROLLS);
+                        level_state.restock_packs_picked_up += 1;
+
+                        play_sound_system(RESTOCK_SOUND, &sounds, &audio_storage, &audio_output);
+                    },
+                );
+
+                // Restock pack spawning
+                {
+                    if let Some(restock_pack_sprite) = &self.restock_pack_sprite {
+                        let restock_pack_spawn_seconds = level_state.restock_pack_spawn_seconds;
+
+                        let restock_pack_timer = self
+                            .restock_pack_timer
+                            .get_or_insert_with(|| IntervalTimer::new(restock_pack_spawn_seconds));
+
+                        if restock_pack_timer.tick(time.delta_seconds()) > 0 {
+                            let pos_x = rng.gen_range(60., SHELF_X - 60.);
+                            let pos_y = rng.gen_range(LANES[0], LANES[LANES.len() - 1]);
+
+                            // Don't spawn restock packs on or really close to the clerk
+                            if distance_between_points(pos_x, pos_y, clerk_x, clerk_y)
+                                > ((CLERK_HEIGHT_AND_WIDTH * 0.5)
+                                    + (RESTOCK_PACK_HEIGHT_AND_WIDTH * 0.5))
+                            {
+                                let mut transform = Transform::default();
+
+                                transform.set_translation_xyz(pos_x, pos_y, 1.0);
+
+                                lazy.create_entity(&entities)
+                                    .with(restock_pack_sprite.clone())
+                                    .with(transform)
+                                    .with(Transparent)
+                                    .with(LevelComponent)
+                                    .with(RestockPackComponent)
+                                    .build();
+                            }
+                        }
+                    } else {
+                        // Load restock pack texture
+                        self.restock_pack_sprite = Some(load_sprite_system(
+                            &texture_storage,
+                            &sheet_storage,
+                            &loader,
+                            "restock_pack.png",
+                            0,
+                        ));
+                    }
+                }
+            }
+
+            // Movement
+            {
+                let clerk_transform = transform_storage.get_mut(*clerk_entity).unwrap();
+
+                let min_height_and_width = CLERK_HEIGHT_AND_WIDTH * 0.5;
+
+                let max_height = PLAYFIELD_HEIGHT - CLERK_HEIGHT_AND_WIDTH * 0.5;
+
+                // The clerk can't walk into the shelf.
+                let max_width = SHELF_X - CLERK_HEIGHT_AND_WIDTH * 0.5;
+
+                let move_y = axis_value(&input, MOVE_Y);
+                if move_y != 0.0 {
+                    bound_transform_y_prepend(
+                        clerk_transform,
+                        move_y * MOVEMENT_SPEED * time.delta_seconds(),
+                        min_height_and_width,
+                        max_height,
+                    );
+                }
+
+                let move_x = axis_value(&input, MOVE_X);
+                if move_x != 0.0 {
+                    bound_transform_x_prepend(
+                        clerk_transform,
+                        move_x * MOVEMENT_SPEED * time.delta_seconds(),
+                        min_height_and_width,
+                        max_width,
+                    );
+                }
+            }
+        } else {
+            let sprite =
+                load_sprite_system(&texture_storage, &sheet_storage, &loader, "clerk.png", 0);
+
+            let mut transform = Transform::default();
+
+            transform.set_translation_xyz(SHELF_X - 80.0, PLAYFIELD_HEIGHT * 0.5, 3.0);
+
+            self.clerk_entity = Some(
+                lazy.create_entity(&*entities)
+                    .with(sprite)
+                    .with(transform)
+                    .with(Transparent)
+                    .with(LevelComponent)
+                    .build(),
+            );
+        }
+    }
+}
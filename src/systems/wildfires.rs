@@ -1,11 +1,17 @@
 use crate::audio::sound_keys::{BUCKET_SOUND, FIRE_OUT_SOUND, FIRE_SOUND};
 use crate::audio::{play_sound_system, SoundsResource};
+use crate::input::action_names::{SHOOT, USE_ABILITY};
+use crate::input::axis_names::{MOVE_X, MOVE_Y, ROTATE};
+use crate::input::{action_is_down, axis_value};
+use crate::playfield::{Playfield, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH};
 use crate::resources::abilities::{AbilitiesResource, AbilityType};
+use crate::resources::rng::RngResource;
+use crate::resources::spawn_timeline::{EnemyType, SpawnRequestsResource};
 use crate::states::wildfires::WildfireStateResource;
-use crate::states::{LevelComponent, LevelSecondsResource};
+use crate::states::LevelComponent;
 use crate::systems::ability_bar::RemoveItem;
-use crate::systems::{distance_between_points, load_sprite_system};
-use crate::{bound_transform_x_prepend, bound_transform_y_prepend, every_n_seconds};
+use crate::systems::{distance_between_points, load_sprite_system, PlayerComponent};
+use crate::{bound_transform_x_prepend, bound_transform_y_prepend};
 use amethyst::assets::{AssetStorage, Loader};
 use amethyst::audio::output::Output;
 use amethyst::audio::Source;
@@ -16,10 +22,8 @@ use amethyst::core::ecs::{
 use amethyst::core::{Time, Transform};
 use amethyst::input::{InputHandler, StringBindings};
 use amethyst::prelude::Builder;
-use amethyst::renderer::rendy::wsi::winit::VirtualKeyCode;
 use amethyst::renderer::{SpriteRender, SpriteSheet, Texture, Transparent};
 use amethyst::window::ScreenDimensions;
-use amethyst::winit::MouseButton;
 use amethyst::{
     derive::SystemDesc,
     ecs::prelude::{System, SystemData},
@@ -67,7 +71,6 @@ impl<'s> System<'s> for WildfiresSystem {
     type SystemData = (
         Entities<'s>,
         Write<'s, WildfireStateResource>,
-        Read<'s, LevelSecondsResource>,
         Read<'s, Time>,
         Read<'s, LazyUpdate>,
         Read<'s, AssetStorage<Texture>>,
@@ -82,6 +85,8 @@ impl<'s> System<'s> for WildfiresSystem {
         Read<'s, AssetStorage<Source>>,
         ReadExpect<'s, SoundsResource>,
         Option<Read<'s, Output>>,
+        Write<'s, RngResource>,
+        Write<'s, SpawnRequestsResource>,
     );
 
     fn run(
@@ -89,7 +94,6 @@ impl<'s> System<'s> for WildfiresSystem {
         (
             entities,
             mut level_state,
-            level_seconds,
             time,
             lazy,
             texture_storage,
@@ -104,9 +108,11 @@ impl<'s> System<'s> for WildfiresSystem {
             audio_storage,
             sounds,
             audio_output,
+            mut rng_resource,
+            mut spawn_requests,
         ): Self::SystemData,
     ) {
-        let mut rng = rand::thread_rng();
+        let rng = &mut rng_resource.rng;
 
         // All indexes in this ability will be removed from active_abilities
         let mut should_be_deactivated_abilities: Vec<usize> = Vec::new();
@@ -118,10 +124,9 @@ impl<'s> System<'s> for WildfiresSystem {
         for (index, ability) in abilities.available_abilities.iter().enumerate() {
             // If that ability is active
             if abilities.active_abilities.contains(&index) {
-                let mut mouse_pos = input.mouse_position().unwrap_or((0., 0.));
-
-                // Mouse pos height is determined from top left instead of bottom left so we have to flip this.
-                mouse_pos.1 = dimensions.height() - mouse_pos.1;
+                // The mouse is in window pixels from the top left, so convert it to a playfield point.
+                let mouse_pos = Playfield::from_dimensions(&dimensions)
+                    .mouse_to_playfield(input.mouse_position().unwrap_or((0., 0.)));
 
                 match ability.info.ability_type {
                     AbilityType::Bucket => {
@@ -144,7 +149,7 @@ impl<'s> System<'s> for WildfiresSystem {
                                 (mouse_pos.0, mouse_pos.1)
                             };
 
-                            if input.mouse_button_is_down(MouseButton::Left) {
+                            if action_is_down(&input, USE_ABILITY) {
                                 // Can only use bucket once.
                                 should_be_deactivated_abilities.push(index);
                                 entities
@@ -173,8 +178,7 @@ impl<'s> System<'s> for WildfiresSystem {
                                         // Delete the fire
                                         entities.delete(entity).expect("Couldn't delete fire.");
 
-                                        level_state.current_fires =
-                                            level_state.current_fires.saturating_sub(1);
+                                        level_state.put_out_fire();
                                     }
                                 }
                             }
@@ -262,7 +266,7 @@ impl<'s> System<'s> for WildfiresSystem {
                                 .delete(droplet_entity)
                                 .expect("Couldn't delete droplet!");
 
-                            level_state.current_fires = level_state.current_fires.saturating_sub(1);
+                            level_state.put_out_fire();
 
                             play_sound_system(
                                 FIRE_OUT_SOUND,
@@ -314,64 +318,45 @@ impl<'s> System<'s> for WildfiresSystem {
             {
                 let min_height_and_width = PLAYER_HEIGHT_AND_WIDTH * 0.5;
 
-                let max_height = dimensions.height() - PLAYER_HEIGHT_AND_WIDTH * 0.5;
+                let max_height = PLAYFIELD_HEIGHT - PLAYER_HEIGHT_AND_WIDTH * 0.5;
 
-                let max_width = dimensions.width() - PLAYER_HEIGHT_AND_WIDTH * 0.5;
+                let max_width = PLAYFIELD_WIDTH - PLAYER_HEIGHT_AND_WIDTH * 0.5;
 
-                // Movement keys
+                // Movement axes
                 {
-                    if input.key_is_down(VirtualKeyCode::W) {
-                        bound_transform_y_prepend(
-                            firefighter_transform,
-                            MOVEMENT_SPEED * time.delta_seconds(),
-                            min_height_and_width,
-                            max_height,
-                        );
-                    }
-                    if input.key_is_down(VirtualKeyCode::S) {
+                    let move_y = axis_value(&input, MOVE_Y);
+                    if move_y != 0.0 {
                         bound_transform_y_prepend(
                             firefighter_transform,
-                            -MOVEMENT_SPEED * time.delta_seconds(),
+                            move_y * MOVEMENT_SPEED * time.delta_seconds(),
                             min_height_and_width,
                             max_height,
                         );
                     }
 
-                    if input.key_is_down(VirtualKeyCode::A) {
-                        bound_transform_x_prepend(
-                            firefighter_transform,
-                            -MOVEMENT_SPEED * time.delta_seconds(),
-                            min_height_and_width,
-                            max_width,
-                        );
-                    }
-
-                    if input.key_is_down(VirtualKeyCode::D) {
+                    let move_x = axis_value(&input, MOVE_X);
+                    if move_x != 0.0 {
                         bound_transform_x_prepend(
                             firefighter_transform,
-                            MOVEMENT_SPEED * time.delta_seconds(),
+                            move_x * MOVEMENT_SPEED * time.delta_seconds(),
                             min_height_and_width,
                             max_width,
                         );
                     }
                 }
 
-                // Rotation keys
+                // Rotation axis
                 {
-                    if input.key_is_down(VirtualKeyCode::Left) {
-                        firefighter_transform.rotate_2d(-ROTATION_SPEED * time.delta_seconds());
-                    }
-
-                    if input.key_is_down(VirtualKeyCode::Right) {
-                        firefighter_transform.rotate_2d(ROTATION_SPEED * time.delta_seconds());
+                    let rotate = axis_value(&input, ROTATE);
+                    if rotate != 0.0 {
+                        firefighter_transform
+                            .rotate_2d(rotate * ROTATION_SPEED * time.delta_seconds());
                     }
                 }
 
                 // Shooting
                 {
-                    if input.key_is_down(VirtualKeyCode::Up)
-                        || input.key_is_down(VirtualKeyCode::Space)
-                    {
+                    if action_is_down(&input, SHOOT) {
                         let droplet_sprite;
 
                         if let Some(sprite) = &self.droplet_sprite {
@@ -426,46 +411,20 @@ impl<'s> System<'s> for WildfiresSystem {
             // Fire spawning
             {
                 if let Some(fire_sprite) = &self.fire_sprite {
-                    if every_n_seconds(0.7, &*time) {
-                        // Fires to spawn is from 1 to (2 + however many chunks of 20 seconds have gone by).
-                        let fires_to_spawn =
-                            rng.gen_range(1, 2 + (level_seconds.seconds_elapsed / 20.) as u32);
-
-                        let mut fires_left_to_spawn = fires_to_spawn;
-
-                        while fires_left_to_spawn != 0 {
-                            let pos_x = rng.gen_range(10., 590.);
-                            let pos_y = rng.gen_range(100., 500.);
-
-                            let firefighter_transform =
-                                transform_storage.get(*firefighter_entity).unwrap();
-
-                            // Don't spawn fire on or really close to player
-                            if distance_between_points(
-                                pos_x,
-                                pos_y,
-                                firefighter_transform.translation().x,
-                                firefighter_transform.translation().y,
-                            ) <= ((PLAYER_HEIGHT_AND_WIDTH * 0.5) + 30.0)
-                            {
-                                continue;
-                            }
+                    for point in spawn_requests.take(EnemyType::Fire) {
+                        let mut transform = Transform::default();
 
-                            let mut transform = Transform::default();
-
-                            transform.set_translation_xyz(pos_x, pos_y, 0.0);
+                        transform.set_translation_xyz(point.x, point.y, 0.0);
 
-                            lazy.create_entity(&entities)
-                                .with(fire_sprite.clone())
-                                .with(transform)
-                                .with(Transparent)
-                                .with(LevelComponent)
-                                .with(Fire)
-                                .build();
+                        lazy.create_entity(&entities)
+                            .with(fire_sprite.clone())
+                            .with(transform)
+                            .with(Transparent)
+                            .with(LevelComponent)
+                            .with(Fire)
+                            .build();
 
-                            fires_left_to_spawn -= 1;
-                            level_state.current_fires += 1;
-                        }
+                        level_state.current_fires += 1;
                     }
                 } else {
                     // Load fire texture
@@ -484,7 +443,7 @@ impl<'s> System<'s> for WildfiresSystem {
 
             let mut transform = Transform::default();
 
-            transform.set_translation_xyz(dimensions.height() * 0.5, dimensions.width() * 0.5, 3.0);
+            transform.set_translation_xyz(PLAYFIELD_WIDTH * 0.5, PLAYFIELD_HEIGHT * 0.5, 3.0);
 
             self.firefighter_entity = Some(
                 lazy.create_entity(&*entities)
@@ -492,6 +451,7 @@ impl<'s> System<'s> for WildfiresSystem {
                     .with(transform)
                     .with(Transparent)
                     .with(LevelComponent)
+                    .with(PlayerComponent)
                     .build(),
             );
         }
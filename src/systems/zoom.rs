@@ -0,0 +1,400 @@
+use amethyst::core::ecs::{
+    Component, DenseVecStorage, Entities, LazyUpdate, Read, ReadExpect, ReaderId, System, Write,
+};
+
+use crate::audio::sound_keys::{
+    HAND_RAISED_SOUND, INCIDENT_RESOLVED_SOUND, UNMUTED_NOISE_SOUND, VIDEO_FROZEN_SOUND,
+    WRONG_CLICK_SOUND,
+};
+use crate::audio::{play_sound_system, SoundsResource};
+use crate::playfield::{Playfield, PlayfieldUiComponent};
+use crate::resources::abilities::{AbilitiesResource, AbilityType};
+use crate::resources::rng::RngResource;
+use crate::states::zoom::ZoomStateResource;
+use crate::states::{LevelComponent, LevelSecondsResource};
+use crate::systems::ability_bar::RemoveItem;
+use crate::systems::load_sprite_system;
+use crate::timers::IntervalTimer;
+use amethyst::assets::Loader;
+use amethyst::core::ecs::shrev::EventChannel;
+use amethyst::core::Time;
+use amethyst::derive::SystemDesc;
+use amethyst::ecs::prelude::*;
+use amethyst::prelude::Builder;
+use amethyst::renderer::{SpriteRender, SpriteSheet, Texture};
+use amethyst::ui::{UiEvent, UiEventType, UiImage};
+use amethyst::window::ScreenDimensions;
+use amethyst::{
+    assets::AssetStorage,
+    audio::{output::Output, Source},
+};
+use rand::Rng;
+
+pub const TILE_WIDTH: f32 = 160.0;
+pub const TILE_HEIGHT: f32 = 110.0;
+/// Space between the video tiles.
+pub const TILE_GAP: f32 = 10.0;
+
+/// The meeting is a square grid with this many tiles on each side.
+pub const GRID_SIZE: usize = 3;
+
+/// How many participants there are in `zoom_tile.png`.
+pub const PARTICIPANT_SPRITES: usize = 4;
+
+pub const INCIDENT_ICON_HEIGHT_AND_WIDTH: f32 = 56.0;
+
+pub const RESPONSE_BUTTON_WIDTH: f32 = 150.0;
+pub const RESPONSE_BUTTON_HEIGHT: f32 = 36.0;
+
+/// Something going wrong on a participant's tile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Incident {
+    /// Resolved by calling on the participant.
+    RaisedHand,
+    /// Resolved by muting the participant.
+    Unmuted,
+    /// Resolved by reconnecting the participant.
+    Frozen,
+}
+
+/// Every incident, in the order of their icons and response buttons.
+pub const INCIDENTS: &[Incident] = &[Incident::RaisedHand, Incident::Unmuted, Incident::Frozen];
+
+impl Incident {
+    /// The incident's icon in `zoom_incidents.png`.
+    pub fn sprite_number(self) -> usize {
+        match self {
+            Incident::RaisedHand => 0,
+            Incident::Unmuted => 1,
+            Incident::Frozen => 2,
+        }
+    }
+
+    /// The sprite of the button that responds to the incident in `zoom_responses.png`.
+    /// Every button has a normal frame followed by a selected frame.
+    pub fn response_sprite_number(self, selected: bool) -> usize {
+        self.sprite_number() * 2 + if selected { 1 } else { 0 }
+    }
+
+    fn sound(self) -> &'static str {
+        match self {
+            Incident::RaisedHand => HAND_RAISED_SOUND,
+            Incident::Unmuted => UNMUTED_NOISE_SOUND,
+            Incident::Frozen => VIDEO_FROZEN_SOUND,
+        }
+    }
+}
+
+/// A participant's video tile.
+pub struct VideoTile {
+    /// Position of the tile in the grid, row by row from the top left.
+    pub index: usize,
+    pub incident: Option<Incident>,
+    /// Seconds left before the current incident is missed.
+    pub incident_seconds_left: f32,
+    /// The icon shown on top of the tile while it has an incident.
+    pub icon: Option<Entity>,
+}
+impl Component for VideoTile {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl VideoTile {
+    pub fn new(index: usize) -> Self {
+        VideoTile {
+            index,
+            incident: None,
+            incident_seconds_left: 0.,
+            icon: None,
+        }
+    }
+
+    /// Removes the tile's incident and its icon.
+    fn clear_incident(&mut self, entities: &Entities) {
+        self.incident = None;
+
+        if let Some(icon) = self.icon.take() {
+            entities
+                .delete(icon)
+                .expect("Couldn't delete incident icon!");
+        }
+    }
+}
+
+/// A button that picks the response used when clicking on a tile.
+pub struct ResponseButton {
+    /// The incident this response resolves.
+    pub incident: Incident,
+}
+impl Component for ResponseButton {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The middle of a video tile, from the bottom left.
+pub fn tile_position(index: usize) -> (f32, f32) {
+    let column = (index % GRID_SIZE) as f32;
+    let row = (index / GRID_SIZE) as f32;
+
+    (
+        130.0 + column * (TILE_WIDTH + TILE_GAP),
+        435.0 - row * (TILE_HEIGHT + TILE_GAP),
+    )
+}
+
+/// The middle of the button for a response, from the bottom left.
+pub fn response_button_position(index: usize) -> (f32, f32) {
+    (
+        140.0 + index as f32 * (RESPONSE_BUTTON_WIDTH + TILE_GAP),
+        100.0,
+    )
+}
+
+#[derive(SystemDesc)]
+#[system_desc(name(ZoomSystemDesc))]
+pub struct ZoomSystem {
+    #[system_desc(event_channel_reader)]
+    reader_id: ReaderId<UiEvent>,
+
+    pub incident_icons: Option<SpriteRender>,
+
+    /// The response used when clicking on a tile.
+    pub selected_response: Incident,
+
+    /// Goes off whenever a new incident should start.
+    pub incident_timer: Option<IntervalTimer>,
+}
+
+impl ZoomSystem {
+    pub fn new(reader_id: ReaderId<UiEvent>) -> Self {
+        Self {
+            reader_id,
+            incident_icons: None,
+            selected_response: Incident::RaisedHand,
+            incident_timer: None,
+        }
+    }
+}
+
+impl<'s> System<'s> for ZoomSystem {
+    type SystemData = (
+        Read<'s, LevelSecondsResource>,
+        Entities<'s>,
+        Read<'s, AssetStorage<Texture>>,
+        Read<'s, AssetStorage<SpriteSheet>>,
+        ReadExpect<'s, Loader>,
+        WriteStorage<'s, VideoTile>,
+        ReadStorage<'s, ResponseButton>,
+        WriteStorage<'s, UiImage>,
+        Read<'s, EventChannel<UiEvent>>,
+        Read<'s, LazyUpdate>,
+        Read<'s, AssetStorage<Source>>,
+        ReadExpect<'s, SoundsResource>,
+        Option<Read<'s, Output>>,
+        Write<'s, AbilitiesResource>,
+        Write<'s, RngResource>,
+        Write<'s, ZoomStateResource>,
+        Read<'s, Time>,
+        ReadExpect<'s, ScreenDimensions>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            level_seconds,
+            entities,
+            texture_storage,
+            sheet_storage,
+            loader,
+            mut tile_storage,
+            response_button_storage,
+            mut ui_image_storage,
+            events,
+            lazy,
+            audio_storage,
+            sounds,
+            audio_output,
+            mut abilities,
+            mut rng_resource,
+            mut level_state,
+            time,
+            dimensions,
+        ): Self::SystemData,
+    ) {
+        let rng = &mut rng_resource.rng;
+
+        // All indexes in this ability will be removed from active_abilities
+        let mut should_be_deactivated_abilities: Vec<usize> = Vec::new();
+
+        let mut breakout_rooms_are_active = false;
+
+        // Handle abilities
+        for (index, ability) in abilities.available_abilities.iter().enumerate() {
+            // If that ability is active
+            if abilities.active_abilities.contains(&index) {
+                match ability.info.ability_type {
+                    AbilityType::MuteAll => {
+                        // Mutes everyone once.
+                        should_be_deactivated_abilities.push(index);
+
+                        play_sound_system(
+                            INCIDENT_RESOLVED_SOUND,
+                            &sounds,
+                            &audio_storage,
+                            &audio_output,
+                        );
+
+                        for tile in (&mut tile_storage).join() {
+                            if tile.incident == Some(Incident::Unmuted) {
+                                tile.clear_incident(&entities);
+
+                                level_state.incidents_resolved += 1;
+                                level_state.incidents_muted_by_ability += 1;
+                            }
+                        }
+                    }
+
+                    AbilityType::BreakoutRooms => {
+                        breakout_rooms_are_active = true;
+                    }
+
+                    _ => {}
+                }
+            }
+        }
+
+        // Remove abilities that have been used
+        for index in should_be_deactivated_abilities {
+            abilities.active_abilities.remove_first_found_item(&index);
+        }
+
+        // Handle clicking on response buttons and tiles
+        for ui_event in events.read(&mut self.reader_id) {
+            if ui_event.event_type != UiEventType::Click {
+                continue;
+            }
+
+            if let Some(button) = response_button_storage.get(ui_event.target) {
+                self.selected_response = button.incident;
+                continue;
+            }
+
+            // Clicking on an incident's icon counts as clicking on its tile.
+            let tile_entity = if tile_storage.get(ui_event.target).is_some() {
+                Some(ui_event.target)
+            } else {
+                (&entities, &tile_storage)
+                    .join()
+                    .find(|(_, tile)| tile.icon == Some(ui_event.target))
+                    .map(|(entity, _)| entity)
+            };
+
+            if let Some(tile) = tile_entity.and_then(|entity| tile_storage.get_mut(entity)) {
+                if tile.incident == Some(self.selected_response) {
+                    tile.clear_incident(&entities);
+
+                    play_sound_system(
+                        INCIDENT_RESOLVED_SOUND,
+                        &sounds,
+                        &audio_storage,
+                        &audio_output,
+                    );
+
+                    level_state.incidents_resolved += 1;
+                } else {
+                    play_sound_system(WRONG_CLICK_SOUND, &sounds, &audio_storage, &audio_output);
+
+                    level_state.wrong_clicks += 1;
+                }
+            }
+        }
+
+        // Highlight the selected response
+        for (button, ui_image) in (&response_button_storage, &mut ui_image_storage).join() {
+            if let UiImage::Sprite(sprite) = ui_image {
+                sprite.sprite_number = button
+                    .incident
+                    .response_sprite_number(button.incident == self.selected_response);
+            }
+        }
+
+        // Miss incidents that ran out of time. They don't run out of time while everyone is in breakout rooms.
+        if !breakout_rooms_are_active {
+            for tile in (&mut tile_storage).join() {
+                if tile.incident.is_some() {
+                    tile.incident_seconds_left -= time.delta_seconds();
+
+                    if tile.incident_seconds_left <= 0. {
+                        tile.clear_incident(&entities);
+
+                        level_state.incidents_missed += 1;
+                    }
+                }
+            }
+        }
+
+        if let Some(incident_icons) = &self.incident_icons {
+            let incident_spawn_seconds =
+                level_state.incident_spawn_seconds_at(level_seconds.seconds_elapsed);
+
+            let incident_timer = self
+                .incident_timer
+                .get_or_insert_with(|| IntervalTimer::new(incident_spawn_seconds));
+
+            incident_timer.set_interval(incident_spawn_seconds);
+
+            // Start a new incident on a tile that doesn't have one
+            if incident_timer.tick(time.delta_seconds()) > 0 {
+                let calm_tiles: Vec<Entity> = (&entities, &tile_storage)
+                    .join()
+                    .filter(|(_, tile)| tile.incident.is_none())
+                    .map(|(entity, _)| entity)
+                    .collect();
+
+                if !calm_tiles.is_empty() {
+                    let tile_entity = calm_tiles[rng.gen_range(0, calm_tiles.len())];
+                    let incident = INCIDENTS[rng.gen_range(0, INCIDENTS.len())];
+
+                    let tile = tile_storage.get_mut(tile_entity).unwrap();
+                    let (tile_x, tile_y) = tile_position(tile.index);
+
+                    let mut icon = incident_icons.clone();
+                    icon.sprite_number = incident.sprite_number();
+
+                    tile.incident = Some(incident);
+                    tile.incident_seconds_left = level_state.incident_seconds;
+
+                    let layout = PlayfieldUiComponent::square(
+                        tile_x,
+                        tile_y,
+                        INCIDENT_ICON_HEIGHT_AND_WIDTH,
+                    )
+                    .with_z(2.0);
+
+                    tile.icon = Some(
+                        lazy.create_entity(&entities)
+                            // Tag entity with LevelComponent so it gets deleted on close.
+                            .with(LevelComponent)
+                            .with(UiImage::Sprite(icon))
+                            .with(
+                                Playfield::from_dimensions(&dimensions)
+                                    .ui_transform(format!("zoom_incident_{}", tile.index), &layout),
+                            )
+                            .with(layout)
+                            .build(),
+                    );
+
+                    play_sound_system(incident.sound(), &sounds, &audio_storage, &audio_output);
+                }
+            }
+        } else {
+            // Load incident icons texture
+            self.incident_icons = Some(load_sprite_system(
+                &texture_storage,
+                &sheet_storage,
+                &loader,
+                "zoom_incidents.png",
+                0,
+            ));
+        }
+    }
+}
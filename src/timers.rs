@@ -0,0 +1,194 @@
+use amethyst::core::ecs::{Component, DenseVecStorage};
+
+/// The shortest interval an `IntervalTimer` can have, so a tiny interval can't go off forever in one tick.
+const MIN_INTERVAL_SECONDS: f32 = 0.001;
+
+/// A timer that goes off once or over and over, driven by delta seconds instead of frame numbers,
+/// so it goes off just as often no matter the frame rate.
+#[derive(Clone, Debug)]
+pub struct IntervalTimer {
+    /// Seconds between the timer going off. None if it only goes off once.
+    interval_seconds: Option<f32>,
+    /// Seconds until the timer next goes off. None once a one-off timer has gone off.
+    seconds_until_next: Option<f32>,
+}
+
+impl IntervalTimer {
+    /// A timer that first goes off after one interval, then every interval after that.
+    pub fn new(interval_seconds: f32) -> Self {
+        IntervalTimer::starting_in(interval_seconds, Some(interval_seconds))
+    }
+
+    /// A timer that first goes off after `first_seconds`, then every `interval_seconds` after that
+    /// (or never again if there is no interval).
+    pub fn starting_in(first_seconds: f32, interval_seconds: Option<f32>) -> Self {
+        IntervalTimer {
+            interval_seconds: interval_seconds.map(|interval| interval.max(MIN_INTERVAL_SECONDS)),
+            seconds_until_next: Some(first_seconds),
+        }
+    }
+
+    /// Changes the interval. Takes effect after the timer next goes off.
+    pub fn set_interval(&mut self, interval_seconds: f32) {
+        self.interval_seconds = Some(interval_seconds.max(MIN_INTERVAL_SECONDS));
+    }
+
+    /// Advances the timer. Returns how many times it went off, which can be more than once on a long frame.
+    pub fn tick(&mut self, delta_seconds: f32) -> u32 {
+        let mut times_gone_off = 0;
+
+        while let Some(seconds_until_next) = self.seconds_until_next {
+            if seconds_until_next > delta_seconds {
+                self.seconds_until_next = Some(seconds_until_next - delta_seconds);
+                break;
+            }
+
+            times_gone_off += 1;
+
+            // Whatever was left of the frame counts towards the next interval
+            self.seconds_until_next = self
+                .interval_seconds
+                .map(|interval_seconds| seconds_until_next + interval_seconds);
+        }
+
+        times_gone_off
+    }
+}
+
+/// Gives an entity a limited amount of time to live.
+/// The level's system ticks it and decides what happens when it runs out.
+#[derive(Clone, Debug)]
+pub struct LifetimeComponent {
+    pub seconds_left: f32,
+}
+impl Component for LifetimeComponent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl LifetimeComponent {
+    pub fn new(seconds: f32) -> Self {
+        LifetimeComponent {
+            seconds_left: seconds,
+        }
+    }
+
+    /// Gives the entity more time to live.
+    pub fn extend(&mut self, seconds: f32) {
+        self.seconds_left += seconds;
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.seconds_left <= 0.
+    }
+
+    /// Counts down the time left. Returns true once it has run out.
+    pub fn tick(&mut self, delta_seconds: f32) -> bool {
+        self.seconds_left -= delta_seconds;
+
+        self.is_expired()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_RATES: &[f32] = &[30., 60., 144., 240.];
+
+    /// Ticks a timer at a frame rate for some seconds, returning how many times it went off.
+    fn simulate(timer: &mut IntervalTimer, frame_rate: f32, seconds: f32) -> u32 {
+        let frames = (seconds * frame_rate).round() as u32;
+
+        (0..frames).map(|_| timer.tick(1. / frame_rate)).sum()
+    }
+
+    #[test]
+    fn interval_timer_goes_off_as_often_at_any_frame_rate() {
+        for frame_rate in FRAME_RATES {
+            let mut timer = IntervalTimer::new(0.5);
+
+            // 10.25 seconds so float error can't decide whether the last interval counts.
+            assert_eq!(
+                simulate(&mut timer, *frame_rate, 10.25),
+                20,
+                "{} FPS",
+                frame_rate
+            );
+        }
+    }
+
+    #[test]
+    fn interval_timer_starting_now_goes_off_on_the_first_tick() {
+        for frame_rate in FRAME_RATES {
+            let mut timer = IntervalTimer::starting_in(0., Some(1.));
+
+            assert_eq!(timer.tick(1. / frame_rate), 1, "{} FPS", frame_rate);
+            assert_eq!(
+                simulate(&mut timer, *frame_rate, 3.5),
+                3,
+                "{} FPS",
+                frame_rate
+            );
+        }
+    }
+
+    #[test]
+    fn one_off_timer_only_goes_off_once() {
+        for frame_rate in FRAME_RATES {
+            let mut timer = IntervalTimer::starting_in(2., None);
+
+            assert_eq!(
+                simulate(&mut timer, *frame_rate, 1.9),
+                0,
+                "{} FPS",
+                frame_rate
+            );
+            assert_eq!(
+                simulate(&mut timer, *frame_rate, 10.),
+                1,
+                "{} FPS",
+                frame_rate
+            );
+        }
+    }
+
+    #[test]
+    fn interval_timer_catches_up_on_long_frames() {
+        let mut timer = IntervalTimer::new(0.1);
+
+        assert_eq!(timer.tick(0.35), 3);
+        assert_eq!(timer.tick(0.1), 1);
+    }
+
+    #[test]
+    fn lifetime_expires_after_the_same_time_at_any_frame_rate() {
+        for frame_rate in FRAME_RATES {
+            let mut lifetime = LifetimeComponent::new(1.5);
+
+            let frames_lived = (0..1000)
+                .take_while(|_| !lifetime.tick(1. / frame_rate))
+                .count() as f32;
+
+            let seconds_lived = frames_lived / frame_rate;
+
+            assert!(
+                (seconds_lived - 1.5).abs() <= 1. / frame_rate,
+                "Lived {} seconds at {} FPS",
+                seconds_lived,
+                frame_rate
+            );
+        }
+    }
+
+    #[test]
+    fn extended_lifetime_lasts_longer() {
+        let mut lifetime = LifetimeComponent::new(0.5);
+
+        assert!(!lifetime.tick(0.4));
+
+        lifetime.extend(1.);
+
+        assert!(!lifetime.tick(0.4));
+        assert!(lifetime.tick(0.8));
+    }
+}